@@ -0,0 +1,94 @@
+//! `get_graph_data` の応答をフロントエンドへ渡す 2 通りの方法を比べるベンチマーク
+//!
+//! - decode/re-encode: `GetAmbientConditionsResponse` にデコードしてから `encode_to_vec` し直す（以前の方法）
+//! - passthrough: 受信バッファからバイト列を切り出すだけ（`RawResponseCodec` の方法）
+//!
+//! どちらも tonic の受信バッファ (`BytesMut`) から始め、IPC に渡す `Vec<u8>` を作るまでを測る。
+//! `cargo bench --bench graph_data_passthrough` で実行する
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bytes::BytesMut;
+use prost::Message;
+use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+
+#[allow(dead_code)]
+#[path = "../src/infrastructure/raw_codec.rs"]
+mod raw_codec;
+
+const SAMPLE_COUNTS: [usize; 3] = [100, 1_000, 10_000];
+const MEASURE_FOR: Duration = Duration::from_secs(2);
+
+/// サーバーから届くのと同じ形の応答をエンコードしておく
+fn wire_response(samples: usize) -> Vec<u8> {
+    let ambient_conditions: HashMap<_, _> = (0..samples)
+        .map(|i| {
+            (
+                format!("{}-0", 1_700_000_000_000u64 + i as u64 * 1_000),
+                AmbientCondition {
+                    temperature: 21.5,
+                    humidity: 40.25,
+                    illumination: 120.0,
+                },
+            )
+        })
+        .collect();
+    GetAmbientConditionsResponse { ambient_conditions }.encode_to_vec()
+}
+
+fn decode_and_reencode(mut buf: BytesMut) -> Vec<u8> {
+    GetAmbientConditionsResponse::decode(&mut buf)
+        .expect("decode")
+        .encode_to_vec()
+}
+
+fn passthrough(mut buf: BytesMut) -> Vec<u8> {
+    Vec::from(raw_codec::take_message(&mut buf))
+}
+
+/// `MEASURE_FOR` の間 `f` を繰り返し、1 回あたりの平均時間を返す
+fn measure(wire: &[u8], f: fn(BytesMut) -> Vec<u8>) -> Duration {
+    // ウォームアップ
+    for _ in 0..10 {
+        black_box(f(BytesMut::from(wire)));
+    }
+    let mut iterations = 0u32;
+    let mut elapsed = Duration::ZERO;
+    while elapsed < MEASURE_FOR {
+        // 受信バッファの用意は計測に含めない
+        let buf = BytesMut::from(wire);
+        let started = Instant::now();
+        black_box(f(black_box(buf)));
+        elapsed += started.elapsed();
+        iterations += 1;
+    }
+    elapsed / iterations
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>10} {:>18} {:>14} {:>8}",
+        "samples", "bytes", "decode/re-encode", "passthrough", "speedup"
+    );
+    for samples in SAMPLE_COUNTS {
+        let wire = wire_response(samples);
+        // 両方の方法が同じバイト列を渡すことを確かめておく
+        assert_eq!(
+            GetAmbientConditionsResponse::decode(passthrough(BytesMut::from(&wire[..])).as_slice())
+                .expect("decode"),
+            GetAmbientConditionsResponse::decode(wire.as_slice()).expect("decode"),
+        );
+
+        let reencode = measure(&wire, decode_and_reencode);
+        let raw = measure(&wire, passthrough);
+        println!(
+            "{:>8} {:>10} {:>18?} {:>14?} {:>7.1}x",
+            samples,
+            wire.len(),
+            reencode,
+            raw,
+            reencode.as_secs_f64() / raw.as_secs_f64()
+        );
+    }
+}
@@ -1,17 +1,28 @@
 use std::sync::Arc;
 
-use tempgrpcd_protos::tempgrpcd::v1::tempgrpcd_service_client::TempgrpcdServiceClient;
-use tokio::sync::Mutex;
-use tonic::{service::interceptor::InterceptedService, transport::Channel};
+use crate::domain::graph_data::GraphData;
+use crate::domain::time_range::TimeRange;
+use crate::infrastructure::{
+    alert_evaluator::AlertEvaluator, connection::ConnectionManager, db::DbPool,
+    live_poller::LivePoller, request_registry::RequestRegistry, single_flight::SingleFlight,
+};
+use crate::presentation::ui_error::UIError;
 
-use crate::infrastructure::{db::DbPool, grpc_client::AuthInterceptor};
-
-type MyGrpcClient =
-    Arc<Mutex<Option<TempgrpcdServiceClient<InterceptedService<Channel, AuthInterceptor>>>>>;
+/// 実行中の `get_graph_data`。キーは（接続先 URL, 期間, サンプル数）
+pub type GraphDataFlights = SingleFlight<(String, TimeRange, u32), Result<GraphData, UIError>>;
 
 /// アプリケーション全体で共有する状態
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DbPool,
-    pub grpc_connection: MyGrpcClient,
+    /// gRPC サーバーとの接続（状態管理と自動再接続を含む）
+    pub connection: ConnectionManager,
+    /// 接続中に新しい測定値を取得して webview に送るタスク
+    pub live: LivePoller,
+    /// 同時に届いた同じ `get_graph_data` を 1 回の取得にまとめる
+    pub graph_data_flights: Arc<GraphDataFlights>,
+    /// 取り消せるように覚えておく実行中の要求
+    pub requests: Arc<RequestRegistry>,
+    /// ライブ更新で届いた測定値をアラートの規則で判定するスレッド
+    pub alerts: AlertEvaluator,
 }
@@ -0,0 +1,48 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::domain::aggregation::{AggregateBucket, AggregateInterval};
+use crate::domain::ambient::AmbientReading;
+use crate::domain::time_range::TimeRange;
+use crate::repository::diesel_aggregate_repository::DieselAggregateRepository;
+use crate::usecase::aggregate::{self, AggregateError, RollupPlan};
+use crate::usecase::ambient_cache::SETTLE_MARGIN;
+
+/// 区切りごとの集計と、そのキャッシュを受け付けるコントローラー
+pub struct AggregateController<'a> {
+    pub repo: &'a mut DieselAggregateRepository,
+}
+
+impl<'a> AggregateController<'a> {
+    pub fn new(repo: &'a mut DieselAggregateRepository) -> Self {
+        Self { repo }
+    }
+
+    /// `range` を区切り、保存済みの集計を読み出す
+    pub fn plan(
+        &mut self,
+        endpoint: &str,
+        range: &TimeRange,
+        interval: AggregateInterval,
+        time_zone: &str,
+    ) -> Result<RollupPlan, AggregateError> {
+        aggregate::plan_rollups(self.repo, endpoint, range, interval, time_zone)
+    }
+
+    /// 足りない区切りを集計する。`complete` が false（測定値が欠けているか間引かれている）なら保存しない。
+    /// 終わったばかりの区切りはまだ測定値が増えうるので保存しない
+    pub fn complete(
+        &mut self,
+        endpoint: &str,
+        plan: RollupPlan,
+        readings: Vec<AmbientReading>,
+        complete: bool,
+    ) -> Result<Vec<AggregateBucket>, AggregateError> {
+        let settled_before_ms = complete.then(|| {
+            let settled = SystemTime::now() - SETTLE_MARGIN;
+            settled
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as i64)
+        });
+        aggregate::complete_rollups(self.repo, endpoint, plan, readings, settled_before_ms)
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use jiff::tz::TimeZone;
+
+use crate::domain::alert::{AlertEvent, AlertFired, AlertRule, RuleState};
+use crate::domain::ambient::AmbientReading;
+use crate::repository::diesel_alert_repository::DieselAlertRepository;
+use crate::usecase::alert::{self, AlertError};
+
+/// 発報履歴の一覧で既定で返す件数
+pub const DEFAULT_EVENT_LIMIT: u32 = 100;
+
+/// アラートの規則の管理と、新しい測定値の判定を受け付けるコントローラー
+pub struct AlertController<'a> {
+    pub repo: &'a mut DieselAlertRepository,
+}
+
+impl<'a> AlertController<'a> {
+    pub fn new(repo: &'a mut DieselAlertRepository) -> Self {
+        Self { repo }
+    }
+
+    /// 規則の一覧
+    pub fn rules(&mut self) -> Result<Vec<AlertRule>, AlertError> {
+        alert::list_rules(self.repo)
+    }
+
+    /// 規則を追加する（`rule.id` は無視する）
+    pub fn create_rule(&mut self, rule: AlertRule) -> Result<AlertRule, AlertError> {
+        alert::create_rule(self.repo, rule)
+    }
+
+    /// `rule.id` の規則を置き換える
+    pub fn update_rule(&mut self, rule: AlertRule) -> Result<(), AlertError> {
+        alert::update_rule(self.repo, rule)
+    }
+
+    /// 規則とその履歴を削除する。規則がなければ false
+    pub fn delete_rule(&mut self, id: i32) -> Result<bool, AlertError> {
+        alert::delete_rule(self.repo, id)
+    }
+
+    /// 新しい順の発報履歴（`limit` を省くと `DEFAULT_EVENT_LIMIT` 件まで）
+    pub fn events(&mut self, limit: Option<u32>) -> Result<Vec<AlertEvent>, AlertError> {
+        alert::list_events(self.repo, limit.unwrap_or(DEFAULT_EVENT_LIMIT).into())
+    }
+
+    /// 新しい測定値を端末のタイムゾーンで判定し、新たに発報したものを返す
+    pub fn evaluate(
+        &mut self,
+        states: &mut HashMap<i32, RuleState>,
+        readings: &[AmbientReading],
+    ) -> Result<Vec<AlertFired>, AlertError> {
+        alert::evaluate_rules(self.repo, states, readings, &TimeZone::system())
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, SystemTime};
+
+use crate::domain::time_range::TimeRange;
+use crate::repository::diesel_ambient_reading_repository::DieselAmbientReadingRepository;
+use crate::usecase::ambient_cache::{self, AmbientCacheError, CachedResponse};
+
+/// 測定値のキャッシュを参照・更新するコントローラー。キャッシュは接続先 URL ごとに分ける
+pub struct AmbientCacheController<'a> {
+    pub repo: &'a mut DieselAmbientReadingRepository,
+}
+
+impl<'a> AmbientCacheController<'a> {
+    pub fn new(repo: &'a mut DieselAmbientReadingRepository) -> Self {
+        Self { repo }
+    }
+
+    /// サーバーから取得する必要がある期間
+    pub fn missing_spans(
+        &mut self,
+        endpoint: &str,
+        range: &TimeRange,
+        interval: Duration,
+    ) -> Result<Vec<TimeRange>, AmbientCacheError> {
+        ambient_cache::missing_spans(self.repo, endpoint, range, interval)
+    }
+
+    /// キャッシュから組み立てた `GetAmbientConditionsResponse`
+    pub fn cached_response(
+        &mut self,
+        endpoint: &str,
+        ranges: &[TimeRange],
+        interval: Duration,
+    ) -> Result<CachedResponse, AmbientCacheError> {
+        ambient_cache::cached_response(self.repo, endpoint, ranges, interval)
+    }
+
+    /// 最後にサーバーから取得した時刻（Unix エポックからのミリ秒）
+    pub fn last_synced_at(&mut self, endpoint: &str) -> Result<Option<i64>, AmbientCacheError> {
+        ambient_cache::last_synced_at(self.repo, endpoint)
+    }
+
+    /// サーバーから取得した応答を保存する
+    pub fn store_fetched(
+        &mut self,
+        endpoint: &str,
+        window: &TimeRange,
+        interval: Duration,
+        body: &[u8],
+    ) -> Result<(), AmbientCacheError> {
+        ambient_cache::store_fetched(
+            self.repo,
+            endpoint,
+            window,
+            interval,
+            body,
+            SystemTime::now(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use prost::Message as _;
+    use std::collections::HashMap;
+    use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+
+    #[test]
+    fn fetched_spans_are_served_from_cache() {
+        let manager = ConnectionManager::<SqliteConnection>::new(
+            "file:memdb_ambient_cache?mode=memory&cache=shared",
+        );
+        let pool = Pool::builder().build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselAmbientReadingRepository {
+            conn: pool.get().expect("conn"),
+        };
+        let mut controller = AmbientCacheController::new(&mut repo);
+
+        let endpoint = "https://tempgrpcd.example";
+        let range = TimeRange::from_millis(0, 3_600_000).unwrap();
+        let interval = Duration::from_secs(60);
+        assert_eq!(controller.last_synced_at(endpoint).expect("sync"), None);
+        assert_eq!(
+            controller
+                .missing_spans(endpoint, &range, interval)
+                .expect("gaps"),
+            vec![range]
+        );
+
+        let mut ambient_conditions = HashMap::new();
+        ambient_conditions.insert(
+            "60000-0".to_string(),
+            AmbientCondition {
+                temperature: 21.5,
+                humidity: 40.0,
+                illumination: 120.0,
+            },
+        );
+        let response = GetAmbientConditionsResponse { ambient_conditions };
+        controller
+            .store_fetched(endpoint, &range, interval, &response.encode_to_vec())
+            .expect("store");
+        assert!(controller.last_synced_at(endpoint).expect("sync").is_some());
+
+        assert!(
+            controller
+                .missing_spans(endpoint, &range, interval)
+                .expect("gaps")
+                .is_empty()
+        );
+        let cached = controller
+            .cached_response(endpoint, &[range], interval)
+            .expect("cached");
+        assert_eq!(
+            GetAmbientConditionsResponse::decode(cached.body.as_slice()).expect("decode"),
+            response
+        );
+    }
+}
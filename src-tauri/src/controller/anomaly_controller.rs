@@ -0,0 +1,39 @@
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+use crate::domain::anomaly::{Anomaly, AnomalyThresholds};
+use crate::repository::diesel_anomaly_threshold_repository::DieselAnomalyThresholdRepository;
+use crate::usecase::anomaly::{self, AnomalyError};
+
+/// 異常検出のしきい値の参照・更新と、検出を受け付けるコントローラー
+pub struct AnomalyController<'a> {
+    pub repo: &'a mut DieselAnomalyThresholdRepository,
+}
+
+impl<'a> AnomalyController<'a> {
+    pub fn new(repo: &'a mut DieselAnomalyThresholdRepository) -> Self {
+        Self { repo }
+    }
+
+    /// 全指標のしきい値
+    pub fn thresholds(&mut self) -> Result<Vec<AnomalyThresholds>, AnomalyError> {
+        anomaly::get_thresholds(self.repo)
+    }
+
+    /// 指定した指標のしきい値を置き換える
+    pub fn set_thresholds(&mut self, thresholds: &[AnomalyThresholds]) -> Result<(), AnomalyError> {
+        anomaly::set_thresholds(self.repo, thresholds)
+    }
+
+    /// `metrics` を調べる（空なら全指標）
+    pub fn detect(
+        &mut self,
+        readings: &[AmbientReading],
+        metrics: &[AmbientMetric],
+    ) -> Result<Vec<Anomaly>, AnomalyError> {
+        let metrics = if metrics.is_empty() {
+            &AmbientMetric::ALL[..]
+        } else {
+            metrics
+        };
+        anomaly::detect_anomalies(self.repo, readings, metrics)
+    }
+}
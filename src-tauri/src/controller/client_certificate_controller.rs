@@ -0,0 +1,76 @@
+use crate::domain::client_certificate::{
+    ClientCertificateImport, ClientCertificateInfo, ClientIdentity,
+};
+use crate::repository::diesel_client_certificate_repository::DieselClientCertificateRepository;
+use crate::usecase::client_certificate::{self, ClientCertificateError};
+
+/// mTLS クライアント証明書の登録・参照・削除を受け付けるコントローラー
+pub struct ClientCertificateController<'a> {
+    pub repo: &'a mut DieselClientCertificateRepository,
+}
+
+impl<'a> ClientCertificateController<'a> {
+    pub fn new(repo: &'a mut DieselClientCertificateRepository) -> Self {
+        Self { repo }
+    }
+
+    /// 証明書と秘密鍵を取り込み、既存の証明書を置き換える
+    pub fn import(
+        &mut self,
+        import: ClientCertificateImport,
+    ) -> Result<ClientCertificateInfo, ClientCertificateError> {
+        client_certificate::import_client_certificate(self.repo, import)
+    }
+
+    /// 保存済み証明書の subject と有効期限
+    pub fn info(&mut self) -> Result<Option<ClientCertificateInfo>, ClientCertificateError> {
+        client_certificate::get_client_certificate_info(self.repo)
+    }
+
+    /// 接続に使う証明書と秘密鍵
+    pub fn identity(&mut self) -> Result<Option<ClientIdentity>, ClientCertificateError> {
+        client_certificate::get_client_identity(self.repo)
+    }
+
+    /// 保存済み証明書を削除する（未登録でもエラーにしない）
+    pub fn delete(&mut self) -> Result<(), ClientCertificateError> {
+        client_certificate::delete_client_certificate(self.repo)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::keystore::KeyStore;
+    use crate::infrastructure::test_server::TestPki;
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+
+    #[test]
+    fn import_info_and_delete() {
+        KeyStore::set_test_key([6u8; 32]);
+        let database_url = "file:memdb_client_cert?mode=memory&cache=shared".to_string();
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder().build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselClientCertificateRepository {
+            conn: pool.get().expect("conn"),
+        };
+        let mut controller = ClientCertificateController::new(&mut repo);
+        let pki = TestPki::generate();
+
+        let imported = controller
+            .import(ClientCertificateImport::Pem {
+                cert_pem: pki.client_cert_pem.clone(),
+                key_pem: pki.client_key_pem.clone(),
+            })
+            .expect("import ok");
+        assert_eq!(controller.info().expect("info ok"), Some(imported));
+        assert!(controller.identity().expect("identity ok").is_some());
+
+        controller.delete().expect("delete ok");
+        assert_eq!(controller.info().expect("info ok"), None);
+        controller.delete().expect("deleting twice is fine");
+    }
+}
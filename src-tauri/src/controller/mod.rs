@@ -1 +1,6 @@
+pub mod aggregate_controller;
+pub mod alert_controller;
+pub mod ambient_cache_controller;
+pub mod anomaly_controller;
+pub mod client_certificate_controller;
 pub mod settings_controller;
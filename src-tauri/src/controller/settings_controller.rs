@@ -18,21 +18,9 @@ impl<'a> SettingsController<'a> {
         Ok(setting)
     }
 
-    /// 設定の更新
-    pub fn set(
-        &mut self,
-        url: String,
-        access_token: String,
-        use_proxies: bool,
-        proxy_url: String,
-    ) -> Result<(), SettingsError> {
-        let setting = Settings {
-            id: 1,
-            url,
-            access_token,
-            use_proxies,
-            proxy_url,
-        };
+    /// 設定の更新（設定レコードは常に id = 1 の 1 件のみ）
+    pub fn set(&mut self, setting: Settings) -> Result<(), SettingsError> {
+        let setting = Settings { id: 1, ..setting };
         settings::set_setting(self.repo, setting)?;
         Ok(())
     }
@@ -61,7 +49,24 @@ mod tests {
                 encrypted_access_token BLOB NOT NULL,
                 encrypted_access_token_nonce BLOB NOT NULL,
                 use_proxies BOOLEAN NOT NULL,
-                proxy_url TEXT NOT NULL
+                proxy_url TEXT NOT NULL,
+                auth_mode TEXT NOT NULL DEFAULT 'bearer',
+                auth_header_name TEXT NOT NULL DEFAULT '',
+                auth_header_prefix TEXT NOT NULL DEFAULT '',
+                proxy_username TEXT NOT NULL DEFAULT '',
+                encrypted_proxy_password BLOB,
+                encrypted_proxy_password_nonce BLOB,
+                ca_bundle_pem TEXT NOT NULL DEFAULT '',
+                ca_bundle_path TEXT NOT NULL DEFAULT '',
+                disable_webpki_roots BOOLEAN NOT NULL DEFAULT 0,
+                connect_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                request_timeout_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_interval_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                keepalive_while_idle BOOLEAN NOT NULL DEFAULT 0,
+                min_samples INTEGER NOT NULL DEFAULT 100,
+                max_samples INTEGER NOT NULL DEFAULT 5000,
+                poll_interval_ms INTEGER NOT NULL DEFAULT 10000
             );",
         )
         .unwrap();
@@ -74,10 +79,18 @@ mod tests {
         let mut repo = make_repo();
         let mut ctrl = SettingsController::new(&mut repo);
 
-        ctrl.set("https://x".into(), "tok".into(), true, "http://p".into())
-            .expect("set ok");
+        ctrl.set(Settings {
+            id: 0,
+            url: "https://x".into(),
+            access_token: "tok".into(),
+            use_proxies: true,
+            proxy_url: "http://p".into(),
+            ..Settings::default()
+        })
+        .expect("set ok");
 
         let got = ctrl.get().expect("get ok").expect("some");
+        assert_eq!(got.id, 1);
         assert_eq!(got.url, "https://x");
         assert!(got.use_proxies);
     }
@@ -0,0 +1,361 @@
+//! 測定値を暦の区切り（1 時間・1 日・1 週間）ごとにまとめた集計。
+//!
+//! 区切りは指定したタイムゾーンの現地時刻で決める。夏時間の切り替えがある日は 1 日が 23 時間や 25 時間になり、
+//! 時刻が重複する 1 時間はそれぞれ別の区切りになる
+use std::collections::BTreeMap;
+
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, ToSpan as _};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+use crate::domain::graph_data::GraphDataMeta;
+use crate::domain::time_range::TimeRange;
+
+/// 1 回の要求で返す区切りの数の上限
+pub const MAX_BUCKETS: usize = 20_000;
+
+/// 1 時間の秒数
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// 集計の区切り方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateInterval {
+    /// 現地時刻の毎正時から
+    Hourly,
+    /// 現地時刻の 0 時から（夏時間で 0 時がない日はその日の最初の時刻から）
+    Daily,
+    /// 月曜日の 0 時から
+    Weekly,
+}
+
+impl AggregateInterval {
+    /// データベースに保存するときの名前
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AggregateInterval::Hourly => "hourly",
+            AggregateInterval::Daily => "daily",
+            AggregateInterval::Weekly => "weekly",
+        }
+    }
+
+    /// `at` を含む区切りの開始時刻
+    fn start_of(self, at: Timestamp, tz: &TimeZone) -> Result<Timestamp, jiff::Error> {
+        match self {
+            AggregateInterval::Hourly => Ok(start_of_hour(at, tz)),
+            AggregateInterval::Daily => Ok(at.to_zoned(tz.clone()).start_of_day()?.timestamp()),
+            AggregateInterval::Weekly => {
+                let date = at.to_zoned(tz.clone()).date();
+                let monday =
+                    date.checked_sub(i64::from(date.weekday().to_monday_zero_offset()).days())?;
+                start_of_date(monday, tz)
+            }
+        }
+    }
+
+    /// `start`（区切りの開始時刻）の次の区切りの開始時刻
+    fn next(self, start: Timestamp, tz: &TimeZone) -> Result<Timestamp, jiff::Error> {
+        let date = start.to_zoned(tz.clone()).date();
+        match self {
+            AggregateInterval::Hourly => {
+                // 30 分ずれる夏時間でも毎正時に揃える。揃えた結果が戻ってしまうときは 1 時間後にする
+                let later = start.checked_add(1.hour())?;
+                let next = start_of_hour(later, tz);
+                Ok(if next > start { next } else { later })
+            }
+            AggregateInterval::Daily => start_of_date(date.checked_add(1.day())?, tz),
+            AggregateInterval::Weekly => start_of_date(date.checked_add(1.week())?, tz),
+        }
+    }
+}
+
+/// `at` を含む現地時刻の 1 時間の開始時刻（時差の分単位の端数も考える）
+fn start_of_hour(at: Timestamp, tz: &TimeZone) -> Timestamp {
+    let offset = i64::from(tz.to_offset(at).seconds());
+    let local = at.as_second() + offset;
+    let truncated = at.as_second() - local.rem_euclid(SECONDS_PER_HOUR);
+    Timestamp::from_second(truncated).unwrap_or(at)
+}
+
+/// `date` の最初の時刻（多くは 0 時）
+fn start_of_date(date: Date, tz: &TimeZone) -> Result<Timestamp, jiff::Error> {
+    Ok(date.to_zoned(tz.clone())?.timestamp())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AggregationError {
+    #[error("too many buckets (more than {MAX_BUCKETS}); choose a longer interval")]
+    TooManyBuckets,
+    #[error("time is out of range: {0}")]
+    OutOfRange(#[from] jiff::Error),
+}
+
+/// 集計の区切り。開始を含み終了を含まない（Unix エポックからのミリ秒）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// `range` と重なる区切りを古い順に返す。最初と最後の区切りも暦どおりの長さで、`range` からはみ出しうる
+pub fn buckets(
+    range: &TimeRange,
+    interval: AggregateInterval,
+    tz: &TimeZone,
+) -> Result<Vec<Bucket>, AggregationError> {
+    let millis = range.to_millis();
+    let end = Timestamp::from_millisecond(millis.end)?;
+    let mut start = interval.start_of(Timestamp::from_millisecond(millis.start)?, tz)?;
+    let mut buckets = Vec::new();
+    while start < end {
+        if buckets.len() == MAX_BUCKETS {
+            return Err(AggregationError::TooManyBuckets);
+        }
+        let next = interval.next(start, tz)?;
+        buckets.push(Bucket {
+            start_ms: start.as_millisecond(),
+            end_ms: next.as_millisecond(),
+        });
+        start = next;
+    }
+    Ok(buckets)
+}
+
+/// 1 つの区切りの 1 つの指標の集計
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricAggregate {
+    pub count: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+/// 1 つの区切りの集計。有効な値が 1 つもない指標は `metrics` に含めない
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregateBucket {
+    /// 区切りの開始（Unix エポックからのミリ秒）
+    pub start: i64,
+    /// 区切りの終了（含まない）
+    pub end: i64,
+    pub metrics: BTreeMap<AmbientMetric, MetricAggregate>,
+}
+
+/// `get_aggregates` の応答。`meta` は集計し直した区切りの測定値の取得状況（`get_graph_data` と同じ）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregateReport {
+    pub interval: AggregateInterval,
+    /// 区切りに使った IANA タイムゾーン名
+    #[serde(rename = "timeZone")]
+    pub time_zone: String,
+    pub buckets: Vec<AggregateBucket>,
+    /// `buckets` のうち、保存済みの集計から返したものの数
+    #[serde(rename = "cachedBuckets")]
+    pub cached_buckets: usize,
+    pub meta: GraphDataMeta,
+}
+
+/// 記録時刻の順に並んだ `readings` を `buckets` ごとに集計する（測定値のない区切りも返す）
+pub fn aggregate(readings: &[AmbientReading], buckets: &[Bucket]) -> Vec<AggregateBucket> {
+    buckets
+        .iter()
+        .map(|bucket| {
+            let from = readings.partition_point(|r| r.recorded_at_ms < bucket.start_ms);
+            let to = readings.partition_point(|r| r.recorded_at_ms < bucket.end_ms);
+            let inside = &readings[from..to];
+            AggregateBucket {
+                start: bucket.start_ms,
+                end: bucket.end_ms,
+                metrics: AmbientMetric::ALL
+                    .into_iter()
+                    .filter_map(|metric| {
+                        let values = inside.iter().map(|r| metric.value(r));
+                        Some((metric, metric_aggregate(values)?))
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// 有限の値だけで集計する。有効な値がなければ `None`
+fn metric_aggregate(values: impl Iterator<Item = f64>) -> Option<MetricAggregate> {
+    let mut aggregate: Option<MetricAggregate> = None;
+    let mut sum = 0.0;
+    for value in values.filter(|value| value.is_finite()) {
+        sum += value;
+        let aggregate = aggregate.get_or_insert(MetricAggregate {
+            count: 0,
+            min: value,
+            mean: 0.0,
+            max: value,
+        });
+        aggregate.count += 1;
+        aggregate.min = aggregate.min.min(value);
+        aggregate.max = aggregate.max.max(value);
+    }
+    aggregate.map(|aggregate| MetricAggregate {
+        mean: sum / aggregate.count as f64,
+        ..aggregate
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+
+    fn span_ms(bucket: &Bucket) -> i64 {
+        bucket.end_ms - bucket.start_ms
+    }
+
+    fn range(start: &str, end: &str) -> TimeRange {
+        let ms = |at: &str| at.parse::<Timestamp>().unwrap().as_millisecond();
+        TimeRange::from_millis(ms(start), ms(end)).unwrap()
+    }
+
+    fn new_york() -> TimeZone {
+        TimeZone::get("America/New_York").unwrap()
+    }
+
+    #[test]
+    fn daily_buckets_follow_dst() {
+        // 2024-03-10 は 23 時間、2024-11-03 は 25 時間
+        let buckets = buckets(
+            &range("2024-03-09T12:00:00Z", "2024-03-11T12:00:00Z"),
+            AggregateInterval::Daily,
+            &new_york(),
+        )
+        .unwrap();
+        let lengths: Vec<_> = buckets.iter().map(span_ms).collect();
+        assert_eq!(lengths, [24 * HOUR_MS, 23 * HOUR_MS, 24 * HOUR_MS]);
+        // 2024-03-09 00:00 EST
+        assert_eq!(
+            buckets[0].start_ms,
+            "2024-03-09T05:00:00Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .as_millisecond()
+        );
+
+        let buckets = buckets_for_fall_back(AggregateInterval::Daily);
+        assert_eq!(span_ms(&buckets[0]), 25 * HOUR_MS);
+    }
+
+    fn buckets_for_fall_back(interval: AggregateInterval) -> Vec<Bucket> {
+        buckets(
+            &range("2024-11-03T04:00:00Z", "2024-11-03T08:00:00Z"),
+            interval,
+            &new_york(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn hourly_buckets_split_the_repeated_hour() {
+        // 現地 0 時から 3 時（EST）まで。1 時台は EDT と EST の 2 回ある
+        let buckets = buckets_for_fall_back(AggregateInterval::Hourly);
+        assert_eq!(buckets.len(), 4);
+        assert!(buckets.iter().all(|bucket| span_ms(bucket) == HOUR_MS));
+        assert!(buckets.windows(2).all(|w| w[0].end_ms == w[1].start_ms));
+    }
+
+    #[test]
+    fn hourly_buckets_align_to_half_hour_offsets() {
+        let kolkata = TimeZone::get("Asia/Kolkata").unwrap();
+        let buckets = buckets(
+            &range("2024-01-01T00:00:00Z", "2024-01-01T01:00:00Z"),
+            AggregateInterval::Hourly,
+            &kolkata,
+        )
+        .unwrap();
+        // 5:30 と 6:30（IST）の始まりは 23:30Z と 0:30Z
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(
+            buckets[0].start_ms,
+            "2023-12-31T23:30:00Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .as_millisecond()
+        );
+    }
+
+    #[test]
+    fn weekly_buckets_start_on_monday() {
+        // 2024-01-03 は水曜日
+        let buckets = buckets(
+            &range("2024-01-03T00:00:00Z", "2024-01-10T00:00:00Z"),
+            AggregateInterval::Weekly,
+            &TimeZone::UTC,
+        )
+        .unwrap();
+        let starts: Vec<_> = buckets.iter().map(|bucket| bucket.start_ms).collect();
+        let ms = |at: &str| at.parse::<Timestamp>().unwrap().as_millisecond();
+        assert_eq!(
+            starts,
+            [ms("2024-01-01T00:00:00Z"), ms("2024-01-08T00:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn too_many_buckets_are_rejected() {
+        let result = buckets(
+            &range("2000-01-01T00:00:00Z", "2024-01-01T00:00:00Z"),
+            AggregateInterval::Hourly,
+            &TimeZone::UTC,
+        );
+        assert!(matches!(result, Err(AggregationError::TooManyBuckets)));
+    }
+
+    #[test]
+    fn aggregates_finite_values_per_bucket() {
+        let reading = |recorded_at_ms: i64, temperature: f64| AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 50.0,
+            illumination: f64::NAN,
+        };
+        let readings = [
+            reading(0, 10.0),
+            reading(1_000, 20.0),
+            reading(2_000, f64::NAN),
+            reading(HOUR_MS, 30.0),
+        ];
+        let buckets = [
+            Bucket {
+                start_ms: 0,
+                end_ms: HOUR_MS,
+            },
+            Bucket {
+                start_ms: HOUR_MS,
+                end_ms: 2 * HOUR_MS,
+            },
+            Bucket {
+                start_ms: 2 * HOUR_MS,
+                end_ms: 3 * HOUR_MS,
+            },
+        ];
+        let aggregates = aggregate(&readings, &buckets);
+
+        assert_eq!(
+            aggregates[0].metrics[&AmbientMetric::Temperature],
+            MetricAggregate {
+                count: 2,
+                min: 10.0,
+                mean: 15.0,
+                max: 20.0
+            }
+        );
+        assert_eq!(aggregates[0].metrics[&AmbientMetric::Humidity].count, 3);
+        assert!(
+            !aggregates[0]
+                .metrics
+                .contains_key(&AmbientMetric::Illumination)
+        );
+        assert_eq!(aggregates[1].metrics[&AmbientMetric::Temperature].count, 1);
+        assert!(aggregates[2].metrics.is_empty());
+    }
+}
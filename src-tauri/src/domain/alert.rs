@@ -0,0 +1,378 @@
+//! しきい値によるアラートの規則と、測定値に対する発報・解除の判定
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+
+/// 1 日の分数
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// 測定値としきい値の比べ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparator {
+    /// しきい値より大きい
+    Gt,
+    /// しきい値以上
+    Ge,
+    /// しきい値より小さい
+    Lt,
+    /// しきい値以下
+    Le,
+}
+
+impl Comparator {
+    /// `value` がしきい値を超えている（規則に当てはまる）かどうか
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparator::Gt => value > threshold,
+            Comparator::Ge => value >= threshold,
+            Comparator::Lt => value < threshold,
+            Comparator::Le => value <= threshold,
+        }
+    }
+
+    /// データベースに保存するときの名前
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparator::Gt => "gt",
+            Comparator::Ge => "ge",
+            Comparator::Lt => "lt",
+            Comparator::Le => "le",
+        }
+    }
+
+    /// `as_str` の逆。知らない名前なら `None`
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            Comparator::Gt,
+            Comparator::Ge,
+            Comparator::Lt,
+            Comparator::Le,
+        ]
+        .into_iter()
+        .find(|comparator| comparator.as_str() == name)
+    }
+
+    /// 通知の文面で使う言い方
+    pub fn describe(self) -> &'static str {
+        match self {
+            Comparator::Gt => "above",
+            Comparator::Ge => "at or above",
+            Comparator::Lt => "below",
+            Comparator::Le => "at or below",
+        }
+    }
+}
+
+/// 通知しない時間帯（端末のタイムゾーンでの 0 時からの分数）。
+/// `start` から `end` の直前までで、`start > end` なら日付をまたぐ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    #[serde(rename = "startMinute")]
+    pub start_minute: u16,
+    #[serde(rename = "endMinute")]
+    pub end_minute: u16,
+}
+
+impl QuietHours {
+    /// `at_ms` が `tz` の時刻でこの時間帯に入るかどうか
+    pub fn contains(&self, at_ms: i64, tz: &TimeZone) -> bool {
+        let Ok(timestamp) = Timestamp::from_millisecond(at_ms) else {
+            return false;
+        };
+        let time = timestamp.to_zoned(tz.clone()).time();
+        let minute = time.hour() as u16 * 60 + time.minute() as u16;
+        if self.start_minute <= self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+}
+
+/// アラートの規則
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// 作成時にデータベースが振る ID（作成の要求では無視する）
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    pub metric: AmbientMetric,
+    pub comparator: Comparator,
+    pub threshold: f64,
+    /// しきい値を超えた状態がこの時間（ミリ秒）続いたら発報する
+    #[serde(rename = "sustainedMs")]
+    pub sustained_ms: i64,
+    /// この時間帯には発報しない（解除はする）
+    #[serde(rename = "quietHours")]
+    pub quiet_hours: Option<QuietHours>,
+    pub enabled: bool,
+}
+
+impl AlertRule {
+    /// 名前が空、しきい値が有限でない、継続時間が負、時間帯が 1 日を超える場合は false
+    pub fn is_valid(&self) -> bool {
+        !self.name.trim().is_empty()
+            && self.threshold.is_finite()
+            && self.sustained_ms >= 0
+            && self.quiet_hours.is_none_or(|quiet| {
+                quiet.start_minute < MINUTES_PER_DAY && quiet.end_minute < MINUTES_PER_DAY
+            })
+    }
+}
+
+/// 発報の履歴。解除されるまで `cleared_at_ms` は `None`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertEvent {
+    pub id: i32,
+    #[serde(rename = "ruleId")]
+    pub rule_id: i32,
+    /// 発報した測定値の記録時刻（Unix エポックからのミリ秒）
+    #[serde(rename = "firedAt")]
+    pub fired_at_ms: i64,
+    /// 解除した測定値の記録時刻
+    #[serde(rename = "clearedAt")]
+    pub cleared_at_ms: Option<i64>,
+    /// 発報した測定値
+    pub value: f64,
+}
+
+/// 発報したときにデスクトップ通知を出し、webview へ送る内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertFired {
+    pub rule: AlertRule,
+    pub event: AlertEvent,
+}
+
+impl AlertFired {
+    /// デスクトップ通知の本文（題名は規則の名前）
+    pub fn message(&self) -> String {
+        format!(
+            "{} is {} {} ({:.2})",
+            self.rule.metric.as_str(),
+            self.rule.comparator.describe(),
+            self.rule.threshold,
+            self.event.value
+        )
+    }
+}
+
+/// 規則ごとの判定の途中経過
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleState {
+    /// 発報中かどうか
+    pub active: bool,
+    /// しきい値を超え続けている測定値の最初の記録時刻
+    pub breached_since_ms: Option<i64>,
+}
+
+/// 判定の結果、状態が変わったこと
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    Fire { at_ms: i64, value: f64 },
+    Clear { at_ms: i64 },
+}
+
+/// 記録時刻の順に並んだ `readings` を `rule` で判定し、`state` を進めて発報・解除を返す。
+/// 発報中は新たに発報せず、しきい値を下回る（規則に当てはまらない）測定値が来たら解除する
+pub fn evaluate(
+    rule: &AlertRule,
+    readings: &[AmbientReading],
+    state: &mut RuleState,
+    tz: &TimeZone,
+) -> Vec<Transition> {
+    let mut transitions = Vec::new();
+    for reading in readings {
+        let value = rule.metric.value(reading);
+        if !value.is_finite() {
+            continue;
+        }
+        let at_ms = reading.recorded_at_ms;
+        if !rule.comparator.holds(value, rule.threshold) {
+            state.breached_since_ms = None;
+            if state.active {
+                state.active = false;
+                transitions.push(Transition::Clear { at_ms });
+            }
+            continue;
+        }
+
+        let since = *state.breached_since_ms.get_or_insert(at_ms);
+        let quiet = rule
+            .quiet_hours
+            .is_some_and(|quiet| quiet.contains(at_ms, tz));
+        if !state.active && at_ms - since >= rule.sustained_ms && !quiet {
+            state.active = true;
+            transitions.push(Transition::Fire { at_ms, value });
+        }
+    }
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(sustained_ms: i64, quiet_hours: Option<QuietHours>) -> AlertRule {
+        AlertRule {
+            id: 1,
+            name: "Too hot".into(),
+            metric: AmbientMetric::Temperature,
+            comparator: Comparator::Gt,
+            threshold: 28.0,
+            sustained_ms,
+            quiet_hours,
+            enabled: true,
+        }
+    }
+
+    /// 1 分ごとの温度
+    fn readings(start_ms: i64, temperatures: &[f64]) -> Vec<AmbientReading> {
+        temperatures
+            .iter()
+            .enumerate()
+            .map(|(i, temperature)| {
+                let recorded_at_ms = start_ms + i as i64 * 60_000;
+                AmbientReading {
+                    id: format!("{recorded_at_ms}-0"),
+                    recorded_at_ms,
+                    temperature: *temperature,
+                    humidity: 40.0,
+                    illumination: 0.0,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fires_once_after_sustained_breach_and_clears() {
+        let rule = rule(2 * 60_000, None);
+        let mut state = RuleState::default();
+        let tz = TimeZone::UTC;
+
+        // 2 分続く前に戻ったので発報しない
+        let transitions = evaluate(&rule, &readings(0, &[29.0, 29.0, 27.0]), &mut state, &tz);
+        assert_eq!(transitions, []);
+
+        let transitions = evaluate(
+            &rule,
+            &readings(180_000, &[29.0, 30.0, 31.0, 32.0, 27.0]),
+            &mut state,
+            &tz,
+        );
+        assert_eq!(
+            transitions,
+            [
+                Transition::Fire {
+                    at_ms: 300_000,
+                    value: 31.0
+                },
+                Transition::Clear { at_ms: 420_000 },
+            ]
+        );
+        assert_eq!(state, RuleState::default());
+    }
+
+    #[test]
+    fn does_not_refire_while_active_across_batches() {
+        let rule = rule(0, None);
+        let mut state = RuleState::default();
+        let tz = TimeZone::UTC;
+
+        assert_eq!(
+            evaluate(&rule, &readings(0, &[29.0]), &mut state, &tz).len(),
+            1
+        );
+        assert_eq!(
+            evaluate(
+                &rule,
+                &readings(60_000, &[30.0, f64::NAN, 31.0]),
+                &mut state,
+                &tz
+            ),
+            []
+        );
+        assert!(state.active);
+    }
+
+    #[test]
+    fn quiet_hours_delay_firing_until_they_end() {
+        // 23:00 から 0:02 まで（UTC）
+        let quiet = QuietHours {
+            start_minute: 23 * 60,
+            end_minute: 2,
+        };
+        let rule = rule(0, Some(quiet));
+        let mut state = RuleState::default();
+        let tz = TimeZone::UTC;
+        let midnight = 86_400_000;
+
+        let transitions = evaluate(
+            &rule,
+            &readings(midnight - 60_000, &[29.0, 29.0, 29.0, 29.0]),
+            &mut state,
+            &tz,
+        );
+        assert_eq!(
+            transitions,
+            [Transition::Fire {
+                at_ms: midnight + 120_000,
+                value: 29.0
+            }]
+        );
+    }
+
+    #[test]
+    fn quiet_hours_use_the_given_time_zone() {
+        let quiet = QuietHours {
+            start_minute: 22 * 60,
+            end_minute: 7 * 60,
+        };
+        // 2024-01-01T00:00:00Z は東京では 9:00
+        let at_ms = 1_704_067_200_000;
+        assert!(quiet.contains(at_ms, &TimeZone::UTC));
+        assert!(!quiet.contains(at_ms, &TimeZone::get("Asia/Tokyo").unwrap()));
+    }
+
+    #[test]
+    fn validates_rules() {
+        assert!(rule(0, None).is_valid());
+        assert!(
+            !AlertRule {
+                name: " ".into(),
+                ..rule(0, None)
+            }
+            .is_valid()
+        );
+        assert!(!rule(-1, None).is_valid());
+        assert!(
+            !rule(
+                0,
+                Some(QuietHours {
+                    start_minute: 0,
+                    end_minute: MINUTES_PER_DAY
+                })
+            )
+            .is_valid()
+        );
+        assert_eq!(Comparator::parse("le"), Some(Comparator::Le));
+        assert_eq!(Comparator::parse("eq"), None);
+    }
+
+    #[test]
+    fn describes_fired_alert() {
+        let fired = AlertFired {
+            rule: rule(0, None),
+            event: AlertEvent {
+                id: 1,
+                rule_id: 1,
+                fired_at_ms: 0,
+                cleared_at_ms: None,
+                value: 29.456,
+            },
+        };
+        assert_eq!(fired.message(), "temperature is above 28 (29.46)");
+    }
+}
@@ -0,0 +1,133 @@
+//! 室内環境の測定値と、手元に保存済みの期間
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsResponse;
+
+use crate::domain::time_range::TimeRange;
+
+/// 1 回分の測定値
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AmbientReading {
+    /// サーバーが付けた ID（`<記録時刻のミリ秒>-<連番>`）
+    pub id: String,
+    /// 記録時刻（Unix エポックからのミリ秒）
+    #[serde(rename = "recordedAt")]
+    pub recorded_at_ms: i64,
+    pub temperature: f64,
+    pub humidity: f64,
+    pub illumination: f64,
+}
+
+/// 測定値の指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AmbientMetric {
+    Temperature,
+    Humidity,
+    Illumination,
+}
+
+impl AmbientMetric {
+    pub const ALL: [AmbientMetric; 3] = [
+        AmbientMetric::Temperature,
+        AmbientMetric::Humidity,
+        AmbientMetric::Illumination,
+    ];
+
+    /// `reading` のこの指標の値
+    pub fn value(self, reading: &AmbientReading) -> f64 {
+        match self {
+            AmbientMetric::Temperature => reading.temperature,
+            AmbientMetric::Humidity => reading.humidity,
+            AmbientMetric::Illumination => reading.illumination,
+        }
+    }
+
+    /// データベースに保存するときの名前
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AmbientMetric::Temperature => "temperature",
+            AmbientMetric::Humidity => "humidity",
+            AmbientMetric::Illumination => "illumination",
+        }
+    }
+
+    /// `as_str` の逆。知らない名前なら `None`
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.as_str() == name)
+    }
+}
+
+/// サーバーの ID から記録時刻（ミリ秒）を取り出す
+pub fn recorded_at_ms(id: &str) -> Option<i64> {
+    id.split_once('-')
+        .map_or(id, |(millis, _)| millis)
+        .parse()
+        .ok()
+}
+
+/// 応答の測定値を記録時刻の順に並べて返す。ID から記録時刻を読めないものは捨てる
+pub fn readings_from_response(response: GetAmbientConditionsResponse) -> Vec<AmbientReading> {
+    let mut readings: Vec<_> = response
+        .ambient_conditions
+        .into_iter()
+        .filter_map(|(id, condition)| {
+            Some(AmbientReading {
+                recorded_at_ms: recorded_at_ms(&id)?,
+                id,
+                temperature: f64::from(condition.temperature),
+                humidity: f64::from(condition.humidity),
+                illumination: f64::from(condition.illumination),
+            })
+        })
+        .collect();
+    readings.sort_by(|a, b| (a.recorded_at_ms, &a.id).cmp(&(b.recorded_at_ms, &b.id)));
+    readings
+}
+
+/// ライブ更新で webview に送る、前回から増えた測定値
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AmbientUpdate {
+    pub readings: Vec<AmbientReading>,
+}
+
+/// サーバーから取得済みの期間。`interval` はそのときのサンプル間隔で、
+/// これより粗い解像度の要求にはキャッシュだけで応えられる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedSpan {
+    pub range: TimeRange,
+    pub interval: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_time_from_stream_id() {
+        assert_eq!(recorded_at_ms("1700000000000-0"), Some(1_700_000_000_000));
+        assert_eq!(recorded_at_ms("1700000000000"), Some(1_700_000_000_000));
+        assert_eq!(recorded_at_ms("garbage-1"), None);
+    }
+
+    #[test]
+    fn readings_from_response_sorts_by_recorded_time() {
+        let condition = tempgrpcd_protos::tempgrpcd::v1::AmbientCondition {
+            temperature: 21.5,
+            humidity: 40.0,
+            illumination: 120.0,
+        };
+        let response = GetAmbientConditionsResponse {
+            ambient_conditions: ["3000-0", "garbage", "1000-1", "1000-0"]
+                .into_iter()
+                .map(|id| (id.to_string(), condition))
+                .collect(),
+        };
+        let ids: Vec<_> = readings_from_response(response)
+            .into_iter()
+            .map(|reading| reading.id)
+            .collect();
+        assert_eq!(ids, ["1000-0", "1000-1", "3000-0"]);
+    }
+}
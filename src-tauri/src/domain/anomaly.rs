@@ -0,0 +1,246 @@
+//! 測定値の異常（センサーの不調や急な変化）の検出。
+//!
+//! 指標ごとに、直前の測定値の窓から求める z スコアと四分位範囲 (IQR)、隣り合う測定値の変化率の
+//! 3 つの方法で調べ、しきい値を超えた測定値を返す
+use serde::{Deserialize, Serialize};
+
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+use crate::domain::graph_data::GraphDataMeta;
+use crate::domain::statistics;
+
+/// 指標ごとの検出のしきい値
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyThresholds {
+    pub metric: AmbientMetric,
+    /// z スコアと IQR で比べる直前の測定値の数
+    pub window: u32,
+    /// z スコアの絶対値がこれを超えたら異常
+    #[serde(rename = "zScore")]
+    pub z_score: f64,
+    /// 第 1・第 3 四分位数から IQR のこの倍数より外れたら異常
+    #[serde(rename = "iqrMultiplier")]
+    pub iqr_multiplier: f64,
+    /// 1 分あたりの変化量の絶対値がこれを超えたら異常
+    #[serde(rename = "rateOfChange")]
+    pub rate_of_change: f64,
+}
+
+impl AnomalyThresholds {
+    /// 保存されていない指標に使うしきい値
+    pub fn default_for(metric: AmbientMetric) -> Self {
+        let rate_of_change = match metric {
+            AmbientMetric::Temperature => 2.0,
+            AmbientMetric::Humidity => 10.0,
+            AmbientMetric::Illumination => 1_000.0,
+        };
+        Self {
+            metric,
+            window: 30,
+            z_score: 3.0,
+            iqr_multiplier: 1.5,
+            rate_of_change,
+        }
+    }
+
+    /// 窓が 2 未満、またはしきい値が正の有限値でなければ false
+    pub fn is_valid(&self) -> bool {
+        self.window >= 2
+            && [self.z_score, self.iqr_multiplier, self.rate_of_change]
+                .iter()
+                .all(|value| value.is_finite() && *value > 0.0)
+    }
+}
+
+/// 検出方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Detector {
+    ZScore,
+    Iqr,
+    RateOfChange,
+}
+
+/// 異常と判定した測定値
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    /// 記録時刻（Unix エポックからのミリ秒）
+    #[serde(rename = "recordedAt")]
+    pub recorded_at_ms: i64,
+    pub metric: AmbientMetric,
+    pub value: f64,
+    /// 検出方法ごとの大きさ。z スコア、四分位の外側への IQR 何個分の距離、1 分あたりの変化量（符号付き）
+    pub score: f64,
+    pub detector: Detector,
+}
+
+/// `detect_anomalies` の応答。`meta` は元にした測定値の取得状況（`get_graph_data` と同じ）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnomalyReport {
+    pub anomalies: Vec<Anomaly>,
+    pub meta: GraphDataMeta,
+}
+
+/// 記録時刻の順に並んだ `readings` を `thresholds` の指標ごとに調べ、異常を記録時刻の順に返す
+pub fn detect(readings: &[AmbientReading], thresholds: &[AnomalyThresholds]) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    for thresholds in thresholds {
+        let metric = thresholds.metric;
+        // NaN などは比べられないので除く
+        let samples: Vec<(i64, f64)> = readings
+            .iter()
+            .map(|r| (r.recorded_at_ms, metric.value(r)))
+            .filter(|(_, value)| value.is_finite())
+            .collect();
+        let mut flag = |(recorded_at_ms, value): (i64, f64), score, detector| {
+            anomalies.push(Anomaly {
+                recorded_at_ms,
+                metric,
+                value,
+                score,
+                detector,
+            })
+        };
+
+        let window = thresholds.window as usize;
+        for (i, &sample) in samples.iter().enumerate().skip(window) {
+            let previous: Vec<f64> = samples[i - window..i].iter().map(|(_, v)| *v).collect();
+            if let Some(z) = z_score(&previous, sample.1)
+                && z.abs() > thresholds.z_score
+            {
+                flag(sample, z, Detector::ZScore);
+            }
+            if let Some(score) = iqr_score(previous, sample.1, thresholds.iqr_multiplier) {
+                flag(sample, score, Detector::Iqr);
+            }
+        }
+
+        for pair in samples.windows(2) {
+            let ((prev_at, prev), (at, value)) = (pair[0], pair[1]);
+            if at <= prev_at {
+                continue;
+            }
+            let per_minute = (value - prev) / ((at - prev_at) as f64 / 60_000.0);
+            if per_minute.abs() > thresholds.rate_of_change {
+                flag((at, value), per_minute, Detector::RateOfChange);
+            }
+        }
+    }
+    anomalies.sort_by_key(|anomaly| (anomaly.recorded_at_ms, anomaly.metric));
+    anomalies
+}
+
+/// `previous` の平均と母標準偏差から見た `value` の z スコア。ばらつきがなければ `None`
+fn z_score(previous: &[f64], value: f64) -> Option<f64> {
+    let n = previous.len() as f64;
+    let mean = previous.iter().sum::<f64>() / n;
+    let std_dev = (previous.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    (std_dev > 0.0).then(|| (value - mean) / std_dev)
+}
+
+/// `value` が `previous` の四分位数から IQR の `multiplier` 倍より外れていれば、その距離（IQR 何個分か）。
+/// 下に外れたら負。IQR がゼロなら比べられないので `None`
+fn iqr_score(mut previous: Vec<f64>, value: f64, multiplier: f64) -> Option<f64> {
+    previous.sort_by(f64::total_cmp);
+    let q1 = statistics::percentile(&previous, 25.0);
+    let q3 = statistics::percentile(&previous, 75.0);
+    let iqr = q3 - q1;
+    if iqr <= 0.0 {
+        return None;
+    }
+    if value > q3 + multiplier * iqr {
+        Some((value - q3) / iqr)
+    } else if value < q1 - multiplier * iqr {
+        Some((value - q1) / iqr)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 分ごとの測定値。温度は 20.0 と 20.5 を繰り返す
+    fn readings(n: i64) -> Vec<AmbientReading> {
+        (0..n)
+            .map(|i| AmbientReading {
+                id: format!("{}-0", i * 60_000),
+                recorded_at_ms: i * 60_000,
+                temperature: if i % 2 == 0 { 20.0 } else { 20.5 },
+                humidity: 40.0 + (i % 4) as f64,
+                illumination: f64::NAN,
+            })
+            .collect()
+    }
+
+    fn thresholds(metric: AmbientMetric) -> AnomalyThresholds {
+        AnomalyThresholds {
+            window: 10,
+            ..AnomalyThresholds::default_for(metric)
+        }
+    }
+
+    #[test]
+    fn steady_series_has_no_anomalies() {
+        let anomalies = detect(&readings(100), &AmbientMetric::ALL.map(thresholds));
+        assert_eq!(anomalies, []);
+    }
+
+    #[test]
+    fn spike_is_flagged_by_every_detector() {
+        let mut readings = readings(100);
+        readings[50].temperature = 30.0;
+        let anomalies = detect(&readings, &[thresholds(AmbientMetric::Temperature)]);
+
+        let at_spike: Vec<_> = anomalies
+            .iter()
+            .filter(|anomaly| anomaly.recorded_at_ms == 50 * 60_000)
+            .map(|anomaly| anomaly.detector)
+            .collect();
+        assert!(at_spike.contains(&Detector::ZScore), "{anomalies:?}");
+        assert!(at_spike.contains(&Detector::Iqr), "{anomalies:?}");
+        assert!(at_spike.contains(&Detector::RateOfChange), "{anomalies:?}");
+
+        // 戻りの急な下降も変化率で検出する
+        let drop = anomalies
+            .iter()
+            .find(|anomaly| {
+                anomaly.recorded_at_ms == 51 * 60_000 && anomaly.detector == Detector::RateOfChange
+            })
+            .expect("drop after spike");
+        assert!(drop.score < -2.0);
+        assert!(
+            anomalies
+                .windows(2)
+                .all(|w| w[0].recorded_at_ms <= w[1].recorded_at_ms)
+        );
+    }
+
+    #[test]
+    fn scores_are_signed() {
+        let previous: Vec<f64> = (0..10).map(f64::from).collect();
+        assert!(z_score(&previous, -20.0).unwrap() < 0.0);
+        assert_eq!(z_score(&[1.0, 1.0], 5.0), None);
+
+        // Q1 = 2.25, Q3 = 6.75, IQR = 4.5
+        assert_eq!(iqr_score(previous.clone(), 6.0, 1.5), None);
+        let above = iqr_score(previous.clone(), 20.0, 1.5).unwrap();
+        assert!((above - (20.0 - 6.75) / 4.5).abs() < 1e-9);
+        assert!(iqr_score(previous, -20.0, 1.5).unwrap() < 0.0);
+    }
+
+    #[test]
+    fn validates_thresholds() {
+        assert!(AnomalyThresholds::default_for(AmbientMetric::Humidity).is_valid());
+        let invalid = AnomalyThresholds {
+            window: 1,
+            ..AnomalyThresholds::default_for(AmbientMetric::Humidity)
+        };
+        assert!(!invalid.is_valid());
+        let invalid = AnomalyThresholds {
+            z_score: f64::NAN,
+            ..AnomalyThresholds::default_for(AmbientMetric::Humidity)
+        };
+        assert!(!invalid.is_valid());
+    }
+}
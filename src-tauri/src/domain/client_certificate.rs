@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// mTLS で使うクライアント証明書（チェーン）と秘密鍵。どちらも PEM 形式
+#[derive(Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// 先頭がクライアント証明書、続いて中間証明書
+    pub cert_pem: String,
+    /// 秘密鍵の PEM。取り込んだ形式のまま PKCS#8・PKCS#1 (RSA)・SEC1 (EC) のいずれか
+    pub key_pem: String,
+}
+
+// 秘密鍵がログに出ないよう Debug は手で実装する
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("cert_pem", &self.cert_pem)
+            .field("key_pem", &"<KEY>")
+            .finish()
+    }
+}
+
+/// クライアント証明書のインポート要求
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum ClientCertificateImport {
+    /// 証明書と秘密鍵をそれぞれ PEM で渡す
+    Pem {
+        #[serde(rename = "certPem")]
+        cert_pem: String,
+        #[serde(rename = "keyPem")]
+        key_pem: String,
+    },
+    /// PKCS#12 (.p12 / .pfx) ファイルのパスとそのパスワード
+    Pkcs12 { path: String, password: String },
+}
+
+/// UI に表示するクライアント証明書の概要
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientCertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// 有効期間の開始（Unix 時刻、ミリ秒）
+    #[serde(rename = "notBefore")]
+    pub not_before: i64,
+    /// 有効期限（Unix 時刻、ミリ秒）
+    #[serde(rename = "notAfter")]
+    pub not_after: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_deserializes_by_format_tag() {
+        let pem: ClientCertificateImport =
+            serde_json::from_str(r#"{"format":"pem","certPem":"c","keyPem":"k"}"#).expect("pem");
+        assert!(matches!(
+            pem,
+            ClientCertificateImport::Pem { ref cert_pem, ref key_pem } if cert_pem == "c" && key_pem == "k"
+        ));
+
+        let p12: ClientCertificateImport =
+            serde_json::from_str(r#"{"format":"pkcs12","path":"/a.p12","password":"pw"}"#)
+                .expect("pkcs12");
+        assert!(matches!(p12, ClientCertificateImport::Pkcs12 { .. }));
+    }
+
+    #[test]
+    fn identity_debug_hides_private_key() {
+        let identity = ClientIdentity {
+            cert_pem: "CERT".into(),
+            key_pem: "SECRET KEY".into(),
+        };
+        assert!(!format!("{identity:?}").contains("SECRET"));
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+/// gRPC サーバーとの接続状態。変化するたびに webview へイベントとして通知する
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum ConnectionState {
+    /// まだ接続していない
+    Disconnected,
+    /// 接続を試行中
+    Connecting,
+    /// 接続済み
+    Connected,
+    /// 再接続までの待機中。`attempt` は何回目の再接続か
+    Backoff {
+        attempt: u32,
+        #[serde(rename = "retryInMs")]
+        retry_in_ms: u64,
+    },
+    /// 再接続を諦めた（設定の誤りなど、再試行しても直らない場合を含む）
+    Failed { reason: String },
+}
+
+/// `get_connection_status` で返す接続の詳細
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionStatus {
+    #[serde(flatten)]
+    pub state: ConnectionState,
+    /// 現在の接続先 URL（まだ接続していなければ None）
+    pub endpoint: Option<String>,
+    /// 接続が確立した時刻（Unix エポックからのミリ秒）。接続中でなければ None
+    #[serde(rename = "connectedAt")]
+    pub connected_at: Option<u64>,
+    /// 直近の接続エラー。接続し直しても消さない
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_state_tag() {
+        assert_eq!(
+            serde_json::to_value(ConnectionState::Connected).unwrap(),
+            serde_json::json!({ "state": "connected" })
+        );
+        assert_eq!(
+            serde_json::to_value(ConnectionState::Backoff {
+                attempt: 2,
+                retry_in_ms: 1500
+            })
+            .unwrap(),
+            serde_json::json!({ "state": "backoff", "attempt": 2, "retryInMs": 1500 })
+        );
+        assert_eq!(
+            serde_json::to_value(ConnectionState::Failed {
+                reason: "boom".into()
+            })
+            .unwrap(),
+            serde_json::json!({ "state": "failed", "reason": "boom" })
+        );
+    }
+
+    #[test]
+    fn status_flattens_state() {
+        let status = ConnectionStatus {
+            state: ConnectionState::Connected,
+            endpoint: Some("https://lab:50051".into()),
+            connected_at: Some(1_700_000_000_000),
+            last_error: None,
+        };
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({
+                "state": "connected",
+                "endpoint": "https://lab:50051",
+                "connectedAt": 1_700_000_000_000u64,
+                "lastError": null
+            })
+        );
+    }
+}
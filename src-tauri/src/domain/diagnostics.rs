@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+/// 接続テストの各段階（実行する順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticStepKind {
+    /// 接続先・プロキシ URL の解析
+    Url,
+    /// 最初に接続するホスト（プロキシがあればプロキシ）の名前解決
+    Dns,
+    /// 最初に接続するホストへの TCP 接続
+    Tcp,
+    /// プロキシ経由でのトンネル確立 (CONNECT / SOCKS5)
+    Proxy,
+    /// サーバーとの TLS ハンドシェイク
+    Tls,
+    /// 認証ヘッダー付きの RPC 呼び出し
+    Rpc,
+}
+
+impl DiagnosticStepKind {
+    pub const ALL: [DiagnosticStepKind; 6] = [
+        DiagnosticStepKind::Url,
+        DiagnosticStepKind::Dns,
+        DiagnosticStepKind::Tcp,
+        DiagnosticStepKind::Proxy,
+        DiagnosticStepKind::Tls,
+        DiagnosticStepKind::Rpc,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepOutcome {
+    Ok,
+    Failed,
+    /// 設定上不要、または前の段階が失敗したため実行しなかった
+    Skipped,
+}
+
+/// 1 段階分の結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticStep {
+    pub step: DiagnosticStepKind,
+    pub outcome: StepOutcome,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    /// 成功時は接続先などの補足、失敗時はエラー内容
+    pub detail: String,
+}
+
+/// `test_connection` の結果。`steps` は `DiagnosticStepKind::ALL` の順に全段階を含む
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionReport {
+    /// 失敗した段階がなければ true
+    pub ok: bool,
+    pub steps: Vec<DiagnosticStep>,
+}
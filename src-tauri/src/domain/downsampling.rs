@@ -0,0 +1,221 @@
+//! グラフに描ける点数まで測定値を間引く。
+//!
+//! 指標（温度・湿度・照度）ごとに残す点を選び、どれかの指標で選ばれた測定値を残す。
+//! 山や谷が消えないよう、LTTB (Largest-Triangle-Three-Buckets) か、区間ごとの最小値と最大値を残す方法で選ぶ
+use serde::Deserialize;
+
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+
+/// 間引き方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownsampleMethod {
+    /// 隣の区間の平均と作る三角形が最も大きい点を区間ごとに 1 つ残す（形を保つ）
+    #[default]
+    Lttb,
+    /// 区間ごとに最小値と最大値の 2 点を残す（外れ値を必ず残す）
+    MinMax,
+}
+
+/// `get_graph_data` に渡す間引きの指定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct DownsampleOptions {
+    #[serde(default)]
+    pub method: DownsampleMethod,
+    /// 指標ごとに残す点数。省略時は要求したサンプル数
+    #[serde(rename = "targetPoints")]
+    pub target_points: Option<u32>,
+}
+
+/// 記録時刻の順に並んだ `readings` を、指標ごとに `target` 点まで間引く。
+/// 各指標で選ばれた測定値をまとめて記録時刻の順に返すので、結果は最大で指標数 × `target` 点になる
+pub fn downsample(
+    readings: &[AmbientReading],
+    target: usize,
+    method: DownsampleMethod,
+) -> Vec<AmbientReading> {
+    if readings.len() <= target {
+        return readings.to_vec();
+    }
+    let mut keep = vec![false; readings.len()];
+    for metric in AmbientMetric::ALL {
+        // NaN などは描けないので選ばない。元の位置を覚えておく
+        let (indices, points): (Vec<usize>, Vec<(f64, f64)>) = readings
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (i, (r.recorded_at_ms as f64, metric.value(r))))
+            .filter(|(_, (_, y))| y.is_finite())
+            .unzip();
+        let selected = match method {
+            DownsampleMethod::Lttb => lttb(&points, target),
+            DownsampleMethod::MinMax => min_max(&points, target),
+        };
+        for i in selected {
+            keep[indices[i]] = true;
+        }
+    }
+    readings
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(reading, _)| reading.clone())
+        .collect()
+}
+
+/// `points` を `threshold` 点に間引いたときに残す点の位置。最初と最後の点は必ず残す
+fn lttb(points: &[(f64, f64)], threshold: usize) -> Vec<usize> {
+    let n = points.len();
+    let threshold = threshold.max(3);
+    if n <= threshold {
+        return (0..n).collect();
+    }
+
+    // 最初と最後を除いた点を threshold - 2 個の区間に分ける
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    let bucket = |i: usize| {
+        let start = (i as f64 * every) as usize + 1;
+        let end = (((i + 1) as f64 * every) as usize + 1).min(n - 1);
+        start..end
+    };
+
+    let mut selected = Vec::with_capacity(threshold);
+    selected.push(0);
+    let mut a = 0;
+    for i in 0..threshold - 2 {
+        // 次の区間の平均（最後の区間では最後の点）
+        let next = bucket(i + 1);
+        let next = if next.is_empty() { n - 1..n } else { next };
+        let len = next.len() as f64;
+        let (sum_x, sum_y) = points[next]
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (avg_x, avg_y) = (sum_x / len, sum_y / len);
+
+        let (ax, ay) = points[a];
+        let chosen = bucket(i)
+            .max_by(|&p, &q| {
+                let area = |j: usize| {
+                    let (x, y) = points[j];
+                    ((ax - avg_x) * (y - ay) - (ax - x) * (avg_y - ay)).abs()
+                };
+                area(p).total_cmp(&area(q))
+            })
+            .expect("buckets are never empty");
+        selected.push(chosen);
+        a = chosen;
+    }
+    selected.push(n - 1);
+    selected
+}
+
+/// `points` を区間に分け、区間ごとに最小値と最大値の点を残したときの位置（時刻の順）。
+/// 最初と最後の点も残し、合わせて `threshold` 点以下にする
+fn min_max(points: &[(f64, f64)], threshold: usize) -> Vec<usize> {
+    let n = points.len();
+    let threshold = threshold.max(4);
+    if n <= threshold {
+        return (0..n).collect();
+    }
+
+    let buckets = (threshold - 2) / 2;
+    let every = (n - 2) as f64 / buckets as f64;
+    let mut selected = Vec::with_capacity(threshold);
+    selected.push(0);
+    for i in 0..buckets {
+        let start = (i as f64 * every) as usize + 1;
+        let end = (((i + 1) as f64 * every) as usize + 1).min(n - 1);
+        let by_value = |&p: &usize, &q: &usize| points[p].1.total_cmp(&points[q].1);
+        let (Some(min), Some(max)) = ((start..end).min_by(by_value), (start..end).max_by(by_value))
+        else {
+            continue;
+        };
+        selected.push(min.min(max));
+        if min != max {
+            selected.push(min.max(max));
+        }
+    }
+    selected.push(n - 1);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(recorded_at_ms: i64, temperature: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 50.0,
+            illumination: f64::NAN,
+        }
+    }
+
+    /// 平らな系列の途中に山と谷が 1 つずつある
+    fn series_with_spikes(n: i64) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|i| {
+                let y = match i {
+                    300 => 100.0,
+                    700 => -100.0,
+                    _ => (i % 7) as f64 * 0.1,
+                };
+                (i as f64, y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lttb_keeps_endpoints_and_peaks() {
+        let points = series_with_spikes(1_000);
+        let selected = lttb(&points, 50);
+        assert_eq!(selected.len(), 50);
+        assert_eq!(selected.first(), Some(&0));
+        assert_eq!(selected.last(), Some(&999));
+        assert!(selected.windows(2).all(|w| w[0] < w[1]));
+        assert!(selected.contains(&300));
+        assert!(selected.contains(&700));
+    }
+
+    #[test]
+    fn min_max_keeps_extremes_of_each_bucket() {
+        let points = series_with_spikes(1_000);
+        let selected = min_max(&points, 50);
+        assert!(selected.len() <= 50);
+        assert_eq!(selected.first(), Some(&0));
+        assert_eq!(selected.last(), Some(&999));
+        assert!(selected.windows(2).all(|w| w[0] < w[1]));
+        assert!(selected.contains(&300));
+        assert!(selected.contains(&700));
+    }
+
+    #[test]
+    fn short_series_are_left_alone() {
+        let points = series_with_spikes(10);
+        assert_eq!(lttb(&points, 50), (0..10).collect::<Vec<_>>());
+        assert_eq!(min_max(&points, 50), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn downsample_merges_points_selected_for_each_metric() {
+        let mut readings: Vec<_> = (0..1_000).map(|i| reading(i * 1_000, 20.0)).collect();
+        readings[300].temperature = 35.0;
+        readings[700].humidity = 90.0;
+
+        let kept = downsample(&readings, 20, DownsampleMethod::Lttb);
+        assert!(kept.len() <= 40, "{}", kept.len());
+        assert!(
+            kept.windows(2)
+                .all(|w| w[0].recorded_at_ms < w[1].recorded_at_ms)
+        );
+        assert!(kept.iter().any(|r| r.temperature == 35.0));
+        assert!(kept.iter().any(|r| r.humidity == 90.0));
+
+        // 目標より少なければそのまま
+        assert_eq!(
+            downsample(&readings[..10], 20, DownsampleMethod::MinMax).len(),
+            10
+        );
+    }
+}
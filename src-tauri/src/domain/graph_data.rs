@@ -0,0 +1,241 @@
+//! `get_graph_data` の応答の形式。
+//!
+//! `[メタデータの長さ (u32, リトルエンディアン)][メタデータ (JSON)][GetAmbientConditionsResponse]`
+//! の順に並べる。本体の protobuf は、間引かなかった場合はサーバーから受け取ったバイト列をそのまま続ける
+use bytes::{Buf as _, Bytes};
+use prost::Message as _;
+use prost::encoding::{self, DecodeContext, WireType};
+use serde::Serialize;
+use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+
+use crate::domain::ambient::{self, AmbientReading};
+use crate::domain::downsampling::{self, DownsampleMethod};
+use crate::domain::psychrometrics::DerivedSeries;
+use crate::domain::time_range::TimeRangeInput;
+
+/// 枠に入れる前の応答。`chunks` はそれぞれが `GetAmbientConditionsResponse`
+#[derive(Debug, Clone, Default)]
+pub struct GraphData {
+    pub meta: GraphDataMeta,
+    pub chunks: Vec<Bytes>,
+}
+
+impl GraphData {
+    /// 本体をデコードし、測定値を記録時刻の順に返す
+    pub fn readings(&self) -> Result<Vec<AmbientReading>, prost::DecodeError> {
+        let response = GetAmbientConditionsResponse::decode(self.chunks.concat().as_slice())?;
+        Ok(ambient::readings_from_response(response))
+    }
+
+    /// 本体の測定値の数。デコードせずにフィールドの長さだけを読む。
+    /// チャンクをまたいで同じ ID があれば重ねて数えるので、多めになりうる
+    pub fn point_count(&self) -> Result<usize, prost::DecodeError> {
+        let mut count = 0;
+        for chunk in &self.chunks {
+            let mut buf = chunk.clone();
+            while buf.has_remaining() {
+                let (tag, wire_type) = encoding::decode_key(&mut buf)?;
+                if tag == 1 && wire_type == WireType::LengthDelimited {
+                    count += 1;
+                }
+                encoding::skip_field(wire_type, tag, &mut buf, DecodeContext::default())?;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 測定値が `target` 点より多ければ指標ごとに間引き、本体を間引いた測定値だけに置き換えて、
+    /// 残った測定値を記録時刻の順に返す。`target` 点以下なら本体はそのままにして `None` を返す
+    pub fn downsample(
+        &mut self,
+        target: usize,
+        method: DownsampleMethod,
+    ) -> Result<Option<Vec<AmbientReading>>, prost::DecodeError> {
+        if self.point_count()? <= target {
+            return Ok(None);
+        }
+        let readings = self.readings()?;
+        if readings.len() <= target {
+            return Ok(None);
+        }
+        let kept = downsampling::downsample(&readings, target, method);
+        let response = GetAmbientConditionsResponse {
+            ambient_conditions: kept
+                .iter()
+                .map(|reading| {
+                    let condition = AmbientCondition {
+                        temperature: reading.temperature as _,
+                        humidity: reading.humidity as _,
+                        illumination: reading.illumination as _,
+                    };
+                    (reading.id.clone(), condition)
+                })
+                .collect(),
+        };
+        self.meta.downsampled_from = Some(readings.len());
+        self.chunks = vec![Bytes::from(response.encode_to_vec())];
+        Ok(Some(kept))
+    }
+}
+
+/// 応答の本体に添えるメタデータ
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GraphDataMeta {
+    /// 取得できなかった区間があり、本体が期間の一部しか含まないかどうか
+    pub partial: bool,
+    /// 取得できなかった区間とその理由
+    #[serde(rename = "failedWindows")]
+    pub failed_windows: Vec<FailedWindow>,
+    /// サーバーに届かず、手元のキャッシュだけで応答したかどうか（表示は古い可能性がある）
+    #[serde(rename = "servedFromCache")]
+    pub served_from_cache: bool,
+    /// 最後にサーバーから取得した時刻（Unix エポックからのミリ秒）。一度も取得していなければ `None`
+    #[serde(rename = "lastSync")]
+    pub last_sync: Option<i64>,
+    /// キャッシュから返した測定値の最初から最後まで。キャッシュに測定値がなければ `None`
+    #[serde(rename = "coveredRange")]
+    pub covered_range: Option<TimeRangeInput>,
+    /// 要求された追加の系列（露点など）。要求がなければ省く
+    #[serde(
+        rename = "derivedSeries",
+        skip_serializing_if = "DerivedSeries::is_empty"
+    )]
+    pub derived_series: DerivedSeries,
+    /// 間引く前の測定値の数。間引かなかった場合は省く
+    #[serde(rename = "downsampledFrom", skip_serializing_if = "Option::is_none")]
+    pub downsampled_from: Option<usize>,
+}
+
+/// 取得に失敗した区間
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedWindow {
+    #[serde(flatten)]
+    pub range: TimeRangeInput,
+    pub error: String,
+}
+
+/// メタデータと本体を 1 つのバイト列にまとめる。
+/// `chunks` はそれぞれが `GetAmbientConditionsResponse` で、protobuf では連結がそのままマージになる
+pub fn frame(meta: &GraphDataMeta, chunks: &[Bytes]) -> Vec<u8> {
+    let header = serde_json::to_vec(meta).expect("graph data metadata is always serializable");
+    let body_len: usize = chunks.iter().map(Bytes::len).sum();
+
+    let mut framed = Vec::with_capacity(4 + header.len() + body_len);
+    framed.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    framed.extend_from_slice(&header);
+    for chunk in chunks {
+        framed.extend_from_slice(chunk);
+    }
+    framed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::time_range::TimeUnit;
+
+    #[test]
+    fn frames_metadata_before_body() {
+        let meta = GraphDataMeta {
+            partial: true,
+            failed_windows: vec![FailedWindow {
+                range: TimeRangeInput {
+                    start: 1,
+                    end: 2,
+                    unit: TimeUnit::Milliseconds,
+                },
+                error: "boom".into(),
+            }],
+            served_from_cache: true,
+            last_sync: Some(5),
+            covered_range: Some(TimeRangeInput {
+                start: 1,
+                end: 2,
+                unit: TimeUnit::Milliseconds,
+            }),
+            ..GraphDataMeta::default()
+        };
+        let framed = frame(
+            &meta,
+            &[Bytes::from_static(b"ab"), Bytes::from_static(b"c")],
+        );
+
+        let header_len = u32::from_le_bytes(framed[..4].try_into().unwrap()) as usize;
+        let header: serde_json::Value = serde_json::from_slice(&framed[4..4 + header_len]).unwrap();
+        assert_eq!(
+            header,
+            serde_json::json!({
+                "partial": true,
+                "failedWindows": [
+                    { "start": 1, "end": 2, "unit": "milliseconds", "error": "boom" }
+                ],
+                "servedFromCache": true,
+                "lastSync": 5,
+                "coveredRange": { "start": 1, "end": 2, "unit": "milliseconds" },
+            })
+        );
+        assert_eq!(&framed[4 + header_len..], b"abc");
+    }
+
+    #[test]
+    fn readings_merge_all_chunks() {
+        let chunk = |id: &str| {
+            let condition = tempgrpcd_protos::tempgrpcd::v1::AmbientCondition {
+                temperature: 21.5,
+                humidity: 40.0,
+                illumination: 120.0,
+            };
+            let response = GetAmbientConditionsResponse {
+                ambient_conditions: [(id.to_string(), condition)].into_iter().collect(),
+            };
+            Bytes::from(response.encode_to_vec())
+        };
+        let data = GraphData {
+            meta: GraphDataMeta::default(),
+            chunks: vec![chunk("2000-0"), Bytes::new(), chunk("1000-0")],
+        };
+        let ids: Vec<_> = data
+            .readings()
+            .expect("decode")
+            .into_iter()
+            .map(|reading| reading.id)
+            .collect();
+        assert_eq!(ids, ["1000-0", "2000-0"]);
+    }
+
+    #[test]
+    fn downsample_replaces_body_only_when_over_target() {
+        let response = GetAmbientConditionsResponse {
+            ambient_conditions: (0..100)
+                .map(|i| {
+                    let condition = tempgrpcd_protos::tempgrpcd::v1::AmbientCondition {
+                        temperature: if i == 40 { 30.0 } else { 20.0 },
+                        humidity: 40.0,
+                        illumination: 120.0,
+                    };
+                    (format!("{}-0", i * 1_000), condition)
+                })
+                .collect(),
+        };
+        let body = Bytes::from(response.encode_to_vec());
+        let mut data = GraphData {
+            meta: GraphDataMeta::default(),
+            chunks: vec![body.clone(), Bytes::new()],
+        };
+        assert_eq!(data.point_count().unwrap(), 100);
+
+        // 点数以内なら受け取ったバイト列をそのまま残す
+        assert_eq!(data.downsample(100, DownsampleMethod::Lttb).unwrap(), None);
+        assert_eq!(data.meta.downsampled_from, None);
+        assert_eq!(data.chunks, [body, Bytes::new()]);
+
+        let kept = data
+            .downsample(10, DownsampleMethod::Lttb)
+            .unwrap()
+            .expect("downsampled");
+        assert_eq!(data.meta.downsampled_from, Some(100));
+        assert_eq!(data.readings().unwrap(), kept);
+        assert!(kept.len() <= 30);
+        assert!(kept.iter().any(|reading| reading.id == "40000-0"));
+    }
+}
@@ -1 +1,15 @@
+pub mod aggregation;
+pub mod alert;
+pub mod ambient;
+pub mod anomaly;
+pub mod client_certificate;
+pub mod connection;
+pub mod diagnostics;
+pub mod downsampling;
+pub mod graph_data;
+pub mod psychrometrics;
+pub mod sampling;
+pub mod server_info;
 pub mod settings;
+pub mod statistics;
+pub mod time_range;
@@ -0,0 +1,226 @@
+//! 温度と相対湿度から求める空気線図上の指標（露点・暑さ指数・湿り空気の水分量など）
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::ambient::AmbientReading;
+
+/// 温度・湿度から求める追加の系列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DerivedMetric {
+    /// 露点（℃）
+    DewPoint,
+    /// 米国気象局の暑さ指数 (heat index)（℃）
+    HeatIndex,
+    /// カナダ気象局の体感温度 (humidex)（℃ 相当の無次元量）
+    Humidex,
+    /// 絶対湿度（g/m³）
+    AbsoluteHumidity,
+    /// 飽差 (vapour pressure deficit)（kPa）
+    #[serde(rename = "vpd")]
+    VapourPressureDeficit,
+}
+
+impl DerivedMetric {
+    /// 温度 `temperature`（℃）と相対湿度 `humidity`（%）からこの指標を求める。
+    /// 湿度が 0 のときの露点のように求まらない場合は NaN や無限大になる
+    pub fn compute(self, temperature: f64, humidity: f64) -> f64 {
+        match self {
+            DerivedMetric::DewPoint => dew_point(temperature, humidity),
+            DerivedMetric::HeatIndex => heat_index(temperature, humidity),
+            DerivedMetric::Humidex => humidex(temperature, dew_point(temperature, humidity)),
+            DerivedMetric::AbsoluteHumidity => absolute_humidity(temperature, humidity),
+            DerivedMetric::VapourPressureDeficit => vapour_pressure_deficit(temperature, humidity),
+        }
+    }
+}
+
+/// 指標ごとの系列。測定値の ID から値を引ける（求まらなかった測定値は含まない）
+pub type DerivedSeries = BTreeMap<DerivedMetric, BTreeMap<String, f64>>;
+
+/// `readings` のそれぞれについて `metrics` を求める
+pub fn derive_series(readings: &[AmbientReading], metrics: &[DerivedMetric]) -> DerivedSeries {
+    metrics
+        .iter()
+        .map(|metric| {
+            let values = readings
+                .iter()
+                .map(|r| (r.id.clone(), metric.compute(r.temperature, r.humidity)))
+                .filter(|(_, value)| value.is_finite())
+                .collect();
+            (*metric, values)
+        })
+        .collect()
+}
+
+// Magnus 式の係数 (Alduchov & Eskridge, 1996)
+const MAGNUS_A: f64 = 6.1094;
+const MAGNUS_B: f64 = 17.625;
+const MAGNUS_C: f64 = 243.04;
+
+/// 飽和水蒸気圧（hPa）
+fn saturation_vapour_pressure(temperature: f64) -> f64 {
+    MAGNUS_A * (MAGNUS_B * temperature / (temperature + MAGNUS_C)).exp()
+}
+
+/// 露点（℃）。Magnus 式を逆に解く
+fn dew_point(temperature: f64, humidity: f64) -> f64 {
+    let gamma = (humidity / 100.0).ln() + MAGNUS_B * temperature / (temperature + MAGNUS_C);
+    MAGNUS_C * gamma / (MAGNUS_B - gamma)
+}
+
+/// 絶対湿度（g/m³）。水蒸気を理想気体として扱う
+fn absolute_humidity(temperature: f64, humidity: f64) -> f64 {
+    let vapour_pressure = saturation_vapour_pressure(temperature) * humidity / 100.0;
+    216.7 * vapour_pressure / (temperature + 273.15)
+}
+
+/// 飽差（kPa）
+fn vapour_pressure_deficit(temperature: f64, humidity: f64) -> f64 {
+    saturation_vapour_pressure(temperature) * (1.0 - humidity / 100.0) / 10.0
+}
+
+/// 暑さ指数（℃）。米国気象局の計算手順（Steadman の簡易式と Rothfusz の回帰式、補正）に従う
+fn heat_index(temperature: f64, humidity: f64) -> f64 {
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let fahrenheit = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.04901523 * t + 10.14333127 * rh
+            - 0.22475541 * t * rh
+            - 0.00683783 * t * t
+            - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh
+            + 0.00085282 * t * rh * rh
+            - 0.00000199 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+        }
+        hi
+    };
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+/// humidex。露点 `dew_point`（℃）から水蒸気圧を求める (Masterton & Richardson, 1979)
+fn humidex(temperature: f64, dew_point: f64) -> f64 {
+    let vapour_pressure = 6.11 * (5417.7530 * (1.0 / 273.16 - 1.0 / (273.15 + dew_point))).exp();
+    temperature + 0.5555 * (vapour_pressure - 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    fn fahrenheit_to_celsius(f: f64) -> f64 {
+        (f - 32.0) * 5.0 / 9.0
+    }
+
+    #[test]
+    fn saturation_vapour_pressure_matches_steam_tables() {
+        // 水の飽和水蒸気圧（hPa）
+        for (t, expected) in [(0.0, 6.112), (10.0, 12.28), (20.0, 23.39), (30.0, 42.46)] {
+            assert_close(saturation_vapour_pressure(t), expected, expected * 0.005);
+        }
+    }
+
+    #[test]
+    fn dew_point_matches_reference_table() {
+        for (t, rh, expected) in [
+            (20.0, 50.0, 9.3),
+            (25.0, 60.0, 16.7),
+            (30.0, 80.0, 26.2),
+            (10.0, 100.0, 10.0),
+        ] {
+            assert_close(dew_point(t, rh), expected, 0.1);
+        }
+        assert!(!dew_point(20.0, 0.0).is_finite());
+    }
+
+    #[test]
+    fn heat_index_matches_nws_table() {
+        // 米国気象局の早見表（℉）。表の値は丸めてあるので 1℉ までの差を許す
+        for (t, rh, expected) in [
+            (80.0, 40.0, 80.0),
+            (90.0, 50.0, 95.0),
+            (90.0, 70.0, 106.0),
+            (100.0, 40.0, 109.0),
+            (96.0, 65.0, 121.0),
+        ] {
+            assert_close(
+                heat_index(fahrenheit_to_celsius(t), rh),
+                fahrenheit_to_celsius(expected),
+                fahrenheit_to_celsius(33.0) - fahrenheit_to_celsius(32.0),
+            );
+        }
+        // 涼しいときは Steadman の簡易式だけを使う（59℉, 50% で 56.95℉）
+        assert_close(heat_index(15.0, 50.0), fahrenheit_to_celsius(56.95), 1e-9);
+    }
+
+    #[test]
+    fn humidex_matches_environment_canada_table() {
+        // (気温, 露点, humidex)
+        for (t, td, expected) in [(30.0, 15.0, 34.0), (30.0, 20.0, 38.0), (35.0, 25.0, 47.0)] {
+            assert_close(humidex(t, td), expected, 0.5);
+        }
+    }
+
+    #[test]
+    fn absolute_humidity_matches_reference_table() {
+        // 飽和空気の水分量（g/m³）
+        for (t, expected) in [(0.0, 4.85), (10.0, 9.40), (20.0, 17.3), (30.0, 30.4)] {
+            assert_close(absolute_humidity(t, 100.0), expected, 0.15);
+        }
+        assert_close(absolute_humidity(20.0, 50.0), 8.65, 0.1);
+    }
+
+    #[test]
+    fn vapour_pressure_deficit_matches_reference_table() {
+        for (t, rh, expected) in [(25.0, 50.0, 1.58), (30.0, 70.0, 1.27), (20.0, 100.0, 0.0)] {
+            assert_close(vapour_pressure_deficit(t, rh), expected, 0.02);
+        }
+    }
+
+    #[test]
+    fn derive_series_skips_values_that_cannot_be_computed() {
+        let reading = |id: &str, humidity| AmbientReading {
+            id: id.into(),
+            recorded_at_ms: 0,
+            temperature: 20.0,
+            humidity,
+            illumination: 0.0,
+        };
+        let series = derive_series(
+            &[reading("1-0", 50.0), reading("2-0", 0.0)],
+            &[
+                DerivedMetric::DewPoint,
+                DerivedMetric::VapourPressureDeficit,
+            ],
+        );
+        assert_eq!(
+            series[&DerivedMetric::DewPoint].keys().collect::<Vec<_>>(),
+            ["1-0"]
+        );
+        assert_eq!(series[&DerivedMetric::VapourPressureDeficit].len(), 2);
+        assert_eq!(
+            serde_json::to_value(DerivedMetric::VapourPressureDeficit).unwrap(),
+            "vpd"
+        );
+        assert_eq!(
+            serde_json::to_value(DerivedMetric::DewPoint).unwrap(),
+            "dewPoint"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! グラフの表示解像度から、サーバーに要求するサンプル数を決める
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::domain::settings::Settings;
+use crate::domain::time_range::TimeRange;
+
+/// 解像度が指定されなかったときに要求するサンプル数（上下限の範囲に収める前の値）
+pub const DEFAULT_TARGET_SAMPLES: u32 = 1_000;
+
+/// `get_graph_data` に渡す表示解像度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Resolution {
+    /// グラフの描画幅（物理ピクセル）。1 ピクセルに 1 サンプルを割り当てる
+    Pixels { width: u32 },
+    /// 隣り合うサンプルの間隔（ミリ秒）
+    Interval {
+        #[serde(rename = "intervalMs")]
+        interval_ms: u64,
+    },
+}
+
+/// 1 回の取得で要求するサンプル数の範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleBounds {
+    min: u32,
+    max: u32,
+}
+
+impl SampleBounds {
+    /// 下限は 1 以上に、上限は下限以上に直す
+    pub fn new(min: u32, max: u32) -> Self {
+        let min = min.max(1);
+        Self {
+            min,
+            max: max.max(min),
+        }
+    }
+
+    /// 要求できる最大のサンプル数
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+}
+
+impl From<&Settings> for SampleBounds {
+    fn from(settings: &Settings) -> Self {
+        Self::new(settings.min_samples, settings.max_samples)
+    }
+}
+
+/// `range` を `resolution` で表示するのに必要なサンプル数を `bounds` の範囲で返す
+pub fn sample_count(
+    range: &TimeRange,
+    resolution: Option<Resolution>,
+    bounds: SampleBounds,
+) -> u32 {
+    let target = match resolution {
+        None => DEFAULT_TARGET_SAMPLES,
+        Some(Resolution::Pixels { width }) => width,
+        Some(Resolution::Interval { interval_ms }) => {
+            samples_per_interval(range.duration(), Duration::from_millis(interval_ms))
+        }
+    };
+    target.clamp(bounds.min, bounds.max)
+}
+
+/// 期間を `interval` ごとに区切ったときの区間の数（端数は切り上げ）
+fn samples_per_interval(duration: Duration, interval: Duration) -> u32 {
+    if interval.is_zero() {
+        return u32::MAX;
+    }
+    let count = duration.as_nanos().div_ceil(interval.as_nanos());
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_NANOS: i128 = 3_600 * 1_000_000_000;
+
+    fn hours(n: i128) -> TimeRange {
+        TimeRange::from_nanos(0, n * HOUR_NANOS).expect("valid range")
+    }
+
+    fn bounds() -> SampleBounds {
+        SampleBounds::new(100, 5_000)
+    }
+
+    #[test]
+    fn defaults_when_no_resolution_is_given() {
+        assert_eq!(
+            sample_count(&hours(1), None, bounds()),
+            DEFAULT_TARGET_SAMPLES
+        );
+    }
+
+    #[test]
+    fn follows_pixel_width_within_bounds() {
+        let pixels = |width| Some(Resolution::Pixels { width });
+        assert_eq!(sample_count(&hours(1), pixels(300), bounds()), 300);
+        assert_eq!(sample_count(&hours(1), pixels(3_840), bounds()), 3_840);
+        assert_eq!(sample_count(&hours(1), pixels(20), bounds()), 100);
+        assert_eq!(sample_count(&hours(1), pixels(7_680), bounds()), 5_000);
+    }
+
+    #[test]
+    fn derives_count_from_interval_and_range_length() {
+        let every = |interval_ms| Some(Resolution::Interval { interval_ms });
+        // 1 時間を 1 分ごと
+        assert_eq!(sample_count(&hours(1), every(60_000), bounds()), 100);
+        // 1 日を 1 分ごと（端数なし）
+        assert_eq!(sample_count(&hours(24), every(60_000), bounds()), 1_440);
+        // 1 時間を 7 秒ごと（端数は切り上げ）
+        assert_eq!(sample_count(&hours(1), every(7_000), bounds()), 515);
+        // 1 年を 1 分ごとは上限で抑える
+        assert_eq!(
+            sample_count(&hours(24 * 365), every(60_000), bounds()),
+            5_000
+        );
+        // 0 ミリ秒は「できるだけ細かく」
+        assert_eq!(sample_count(&hours(1), every(0), bounds()), 5_000);
+    }
+
+    #[test]
+    fn bounds_are_normalized() {
+        assert_eq!(SampleBounds::new(0, 0), SampleBounds { min: 1, max: 1 });
+        assert_eq!(
+            SampleBounds::new(500, 100),
+            SampleBounds { min: 500, max: 500 }
+        );
+    }
+}
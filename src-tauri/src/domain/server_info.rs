@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+/// このアプリが前提としている tempgrpcd の API バージョン（proto パッケージ `tempgrpcd.v1`）
+pub const EXPECTED_API_VERSION: &str = "v1";
+
+/// tempgrpcd のサービスが属する proto パッケージの接頭辞
+const TEMPGRPCD_PACKAGE_PREFIX: &str = "tempgrpcd.";
+
+/// サーバーリフレクションで分かった接続先の機能
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServerDescription {
+    pub services: Vec<ServiceDescription>,
+    /// サーバーが提供する tempgrpcd の API バージョン（tempgrpcd のサービスがなければ None）
+    #[serde(rename = "apiVersion")]
+    pub api_version: Option<String>,
+    #[serde(rename = "expectedApiVersion")]
+    pub expected_api_version: String,
+}
+
+impl ServerDescription {
+    /// サービス名のパッケージ（`tempgrpcd.v1.TempgrpcdService` なら `v1`）から API バージョンを読み取る
+    pub fn new(services: Vec<ServiceDescription>) -> Self {
+        let api_version = services.iter().find_map(|service| {
+            let (package, _) = service.name.rsplit_once('.')?;
+            package
+                .strip_prefix(TEMPGRPCD_PACKAGE_PREFIX)
+                .map(str::to_string)
+        });
+        Self {
+            services,
+            api_version,
+            expected_api_version: EXPECTED_API_VERSION.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServiceDescription {
+    /// パッケージを含む完全修飾名
+    pub name: String,
+    pub methods: Vec<MethodDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MethodDescription {
+    pub name: String,
+    /// 引数のメッセージ型（完全修飾名）
+    #[serde(rename = "inputType")]
+    pub input_type: String,
+    /// 戻り値のメッセージ型（完全修飾名）
+    #[serde(rename = "outputType")]
+    pub output_type: String,
+    #[serde(rename = "clientStreaming")]
+    pub client_streaming: bool,
+    #[serde(rename = "serverStreaming")]
+    pub server_streaming: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str) -> ServiceDescription {
+        ServiceDescription {
+            name: name.into(),
+            methods: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reads_api_version_from_tempgrpcd_package() {
+        let desc = ServerDescription::new(vec![
+            service("grpc.health.v1.Health"),
+            service("tempgrpcd.v2.TempgrpcdService"),
+        ]);
+        assert_eq!(desc.api_version.as_deref(), Some("v2"));
+        assert_eq!(desc.expected_api_version, EXPECTED_API_VERSION);
+    }
+
+    #[test]
+    fn api_version_is_none_without_tempgrpcd_service() {
+        let desc = ServerDescription::new(vec![service("grpc.health.v1.Health")]);
+        assert_eq!(desc.api_version, None);
+    }
+}
@@ -1,7 +1,66 @@
-use serde::Serialize;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// gRPC サーバーへの認証方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    /// 認証ヘッダーを送らない
+    None,
+    /// `authorization: Bearer <token>` を送る
+    #[default]
+    Bearer,
+    /// 任意のヘッダー名とプレフィックスでトークンを送る
+    Custom,
+}
+
+impl AuthMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthMode::None => "none",
+            AuthMode::Bearer => "bearer",
+            AuthMode::Custom => "custom",
+        }
+    }
+
+    /// アクセストークンが必要な認証方式かどうか
+    pub fn requires_token(&self) -> bool {
+        !matches!(self, AuthMode::None)
+    }
+}
+
+impl FromStr for AuthMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(AuthMode::None),
+            "bearer" => Ok(AuthMode::Bearer),
+            "custom" => Ok(AuthMode::Custom),
+            other => Err(format!("unknown auth mode: {other}")),
+        }
+    }
+}
+
+/// 接続確立の制限時間の既定値（ミリ秒）
+pub const DEFAULT_CONNECT_TIMEOUT_MS: u32 = 10_000;
+/// RPC ごとの制限時間の既定値（ミリ秒）
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u32 = 30_000;
+/// HTTP/2 PING の送信間隔の既定値（ミリ秒）
+pub const DEFAULT_KEEPALIVE_INTERVAL_MS: u32 = 30_000;
+/// HTTP/2 PING の応答待ち時間の既定値（ミリ秒）
+pub const DEFAULT_KEEPALIVE_TIMEOUT_MS: u32 = 10_000;
+/// 1 回の取得で要求するサンプル数の下限の既定値
+pub const DEFAULT_MIN_SAMPLES: u32 = 100;
+/// 1 回の取得で要求するサンプル数の上限の既定値
+pub const DEFAULT_MAX_SAMPLES: u32 = 5_000;
+/// 新しい測定値を問い合わせる間隔の既定値（ミリ秒）
+pub const DEFAULT_POLL_INTERVAL_MS: u32 = 10_000;
 
 /// アプリケーションの設定を表すエンティティ
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub id: i32,
     pub url: String,
@@ -12,4 +71,128 @@ pub struct Settings {
     pub use_proxies: bool,
     #[serde(rename = "proxyUrl")]
     pub proxy_url: String,
+    /// HTTP プロキシの Basic 認証ユーザー名（空なら認証しない）
+    #[serde(rename = "proxyUsername")]
+    pub proxy_username: String,
+    /// プロキシのパスワード。UI には平文で返さない。
+    /// 保存時の `None` は「変更しない」、`Some("")` は「削除する」を意味する
+    #[serde(rename = "proxyPassword", skip_serializing)]
+    pub proxy_password: Option<String>,
+    /// プロキシのパスワードが保存済みかどうか（UI 表示用）
+    #[serde(rename = "hasProxyPassword", skip_deserializing)]
+    pub has_proxy_password: bool,
+    #[serde(rename = "authMode")]
+    pub auth_mode: AuthMode,
+    /// `AuthMode::Custom` のときに使うヘッダー名
+    #[serde(rename = "authHeaderName")]
+    pub auth_header_name: String,
+    /// `AuthMode::Custom` のときにトークンの前に付ける文字列（空なら付けない）
+    #[serde(rename = "authHeaderPrefix")]
+    pub auth_header_prefix: String,
+    /// 追加で信頼する CA 証明書（PEM、複数可）。自己署名のサーバー証明書もここに貼り付けられる
+    #[serde(rename = "caBundlePem")]
+    pub ca_bundle_pem: String,
+    /// 追加で信頼する CA 証明書を含む PEM ファイルのパス（空なら読み込まない）
+    #[serde(rename = "caBundlePath")]
+    pub ca_bundle_path: String,
+    /// true なら webpki の既定ルート証明書を使わず、CA バンドルだけを信頼する
+    #[serde(rename = "disableWebpkiRoots")]
+    pub disable_webpki_roots: bool,
+    /// 接続確立（TCP・プロキシ・TLS）の制限時間（ミリ秒、0 なら無制限）
+    #[serde(rename = "connectTimeoutMs")]
+    pub connect_timeout_ms: u32,
+    /// RPC ごとの制限時間（ミリ秒、0 なら無制限）
+    #[serde(rename = "requestTimeoutMs")]
+    pub request_timeout_ms: u32,
+    /// HTTP/2 PING を送る間隔（ミリ秒、0 なら keepalive を使わない）
+    #[serde(rename = "keepaliveIntervalMs")]
+    pub keepalive_interval_ms: u32,
+    /// PING の応答がこの時間（ミリ秒）内になければ接続を切る（0 なら hyper の既定値）
+    #[serde(rename = "keepaliveTimeoutMs")]
+    pub keepalive_timeout_ms: u32,
+    /// 実行中の RPC がないときも PING を送るかどうか
+    #[serde(rename = "keepaliveWhileIdle")]
+    pub keepalive_while_idle: bool,
+    /// グラフ取得で要求するサンプル数の下限
+    #[serde(rename = "minSamples")]
+    pub min_samples: u32,
+    /// グラフ取得で要求するサンプル数の上限
+    #[serde(rename = "maxSamples")]
+    pub max_samples: u32,
+    /// 新しい測定値を問い合わせる間隔（ミリ秒、0 ならライブ更新しない）
+    #[serde(rename = "pollIntervalMs")]
+    pub poll_interval_ms: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            url: String::new(),
+            access_token: String::new(),
+            use_proxies: false,
+            proxy_url: String::new(),
+            proxy_username: String::new(),
+            proxy_password: None,
+            has_proxy_password: false,
+            auth_mode: AuthMode::default(),
+            auth_header_name: String::new(),
+            auth_header_prefix: String::new(),
+            ca_bundle_pem: String::new(),
+            ca_bundle_path: String::new(),
+            disable_webpki_roots: false,
+            connect_timeout_ms: DEFAULT_CONNECT_TIMEOUT_MS,
+            request_timeout_ms: DEFAULT_REQUEST_TIMEOUT_MS,
+            keepalive_interval_ms: DEFAULT_KEEPALIVE_INTERVAL_MS,
+            keepalive_timeout_ms: DEFAULT_KEEPALIVE_TIMEOUT_MS,
+            keepalive_while_idle: false,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            max_samples: DEFAULT_MAX_SAMPLES,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_mode_roundtrips_through_str() {
+        for mode in [AuthMode::None, AuthMode::Bearer, AuthMode::Custom] {
+            assert_eq!(AuthMode::from_str(mode.as_str()), Ok(mode));
+        }
+        assert!(AuthMode::from_str("basic").is_err());
+    }
+
+    #[test]
+    fn settings_deserialize_defaults_missing_fields() {
+        let s: Settings = serde_json::from_str(
+            r#"{"url":"https://x","accessToken":"t","useProxies":false,"proxyUrl":""}"#,
+        )
+        .expect("deserialize");
+        assert_eq!(s.url, "https://x");
+        assert_eq!(s.auth_mode, AuthMode::Bearer);
+        assert!(s.auth_header_name.is_empty());
+        assert_eq!(s.proxy_password, None);
+        assert_eq!(s.connect_timeout_ms, DEFAULT_CONNECT_TIMEOUT_MS);
+        assert_eq!(s.request_timeout_ms, DEFAULT_REQUEST_TIMEOUT_MS);
+        assert_eq!(s.min_samples, DEFAULT_MIN_SAMPLES);
+        assert_eq!(s.max_samples, DEFAULT_MAX_SAMPLES);
+        assert_eq!(s.poll_interval_ms, DEFAULT_POLL_INTERVAL_MS);
+    }
+
+    #[test]
+    fn settings_never_serialize_proxy_password() {
+        let s = Settings {
+            proxy_username: "alice".into(),
+            proxy_password: Some("s3cret".into()),
+            has_proxy_password: true,
+            ..Settings::default()
+        };
+        let json = serde_json::to_value(&s).expect("serialize");
+        assert!(json.get("proxyPassword").is_none());
+        assert_eq!(json["hasProxyPassword"], true);
+        assert!(!json.to_string().contains("s3cret"));
+    }
 }
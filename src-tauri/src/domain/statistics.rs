@@ -0,0 +1,209 @@
+//! 期間内の測定値の要約統計量（最小・最大・平均・中央値・標準偏差・パーセンタイル）
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::domain::ambient::AmbientReading;
+use crate::domain::graph_data::GraphDataMeta;
+use crate::domain::psychrometrics::DerivedMetric;
+
+/// 最小値または最大値と、それが記録された時刻
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Extreme {
+    pub value: f64,
+    /// 記録時刻（Unix エポックからのミリ秒）。同じ値が複数あれば最も早いもの
+    #[serde(rename = "recordedAt")]
+    pub recorded_at_ms: i64,
+}
+
+/// 1 つの指標の統計量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricStatistics {
+    pub count: usize,
+    pub min: Extreme,
+    pub max: Extreme,
+    pub mean: f64,
+    pub median: f64,
+    /// 母標準偏差（n で割る）
+    #[serde(rename = "stdDev")]
+    pub std_dev: f64,
+    pub p5: f64,
+    pub p95: f64,
+}
+
+/// 指標ごとの統計量。有効な値が 1 つもない指標は `None`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AmbientStatistics {
+    pub temperature: Option<MetricStatistics>,
+    pub humidity: Option<MetricStatistics>,
+    pub illumination: Option<MetricStatistics>,
+}
+
+/// `get_statistics` の応答。`meta` は元にした測定値の取得状況（`get_graph_data` と同じ）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatisticsReport {
+    #[serde(flatten)]
+    pub statistics: AmbientStatistics,
+    /// 要求された追加の系列（露点など）の統計量。要求がなければ省く
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub derived: BTreeMap<DerivedMetric, Option<MetricStatistics>>,
+    pub meta: GraphDataMeta,
+}
+
+/// 測定値から指標ごとの統計量を求める
+pub fn summarize(readings: &[AmbientReading]) -> AmbientStatistics {
+    let metric = |value: fn(&AmbientReading) -> f64| {
+        metric_statistics(readings.iter().map(|r| (r.recorded_at_ms, value(r))))
+    };
+    AmbientStatistics {
+        temperature: metric(|r| r.temperature),
+        humidity: metric(|r| r.humidity),
+        illumination: metric(|r| r.illumination),
+    }
+}
+
+/// 測定値から求めた追加の系列それぞれの統計量
+pub fn summarize_derived(
+    readings: &[AmbientReading],
+    metrics: &[DerivedMetric],
+) -> BTreeMap<DerivedMetric, Option<MetricStatistics>> {
+    metrics
+        .iter()
+        .map(|metric| {
+            let samples = readings
+                .iter()
+                .map(|r| (r.recorded_at_ms, metric.compute(r.temperature, r.humidity)));
+            (*metric, metric_statistics(samples))
+        })
+        .collect()
+}
+
+/// (記録時刻, 値) の列から統計量を求める。NaN や無限大は数えない
+fn metric_statistics(samples: impl Iterator<Item = (i64, f64)>) -> Option<MetricStatistics> {
+    let mut samples: Vec<(i64, f64)> = samples.filter(|(_, value)| value.is_finite()).collect();
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+    let count = samples.len();
+    let (min_at, min) = samples[0];
+    // 最大値が複数あるときも最も早い時刻を返す
+    let max = samples[count - 1].1;
+    let max_at = samples
+        .iter()
+        .find(|(_, value)| *value == max)
+        .map_or(samples[count - 1].0, |(at, _)| *at);
+
+    let values: Vec<f64> = samples.iter().map(|(_, value)| *value).collect();
+    let mean = values.iter().sum::<f64>() / count as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+    Some(MetricStatistics {
+        count,
+        min: Extreme {
+            value: min,
+            recorded_at_ms: min_at,
+        },
+        max: Extreme {
+            value: max,
+            recorded_at_ms: max_at,
+        },
+        mean,
+        median: percentile(&values, 50.0),
+        std_dev: variance.sqrt(),
+        p5: percentile(&values, 5.0),
+        p95: percentile(&values, 95.0),
+    })
+}
+
+/// 昇順に並んだ `sorted` の `p` パーセンタイル。順位の間は線形補間する（NumPy の既定と同じ）
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(recorded_at_ms: i64, temperature: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 50.0,
+            illumination: f64::NAN,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn summarizes_each_metric() {
+        // 1..=10 を順不同で並べる
+        let readings: Vec<_> = [3.0, 10.0, 1.0, 7.0, 5.0, 2.0, 9.0, 4.0, 8.0, 6.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| reading(i as i64 * 1_000, t))
+            .collect();
+        let stats = summarize(&readings);
+
+        let t = stats.temperature.expect("temperature");
+        assert_eq!(t.count, 10);
+        assert_eq!(
+            t.min,
+            Extreme {
+                value: 1.0,
+                recorded_at_ms: 2_000
+            }
+        );
+        assert_eq!(
+            t.max,
+            Extreme {
+                value: 10.0,
+                recorded_at_ms: 1_000
+            }
+        );
+        assert_close(t.mean, 5.5);
+        assert_close(t.median, 5.5);
+        assert_close(t.std_dev, 8.25_f64.sqrt());
+        // numpy.percentile(range(1, 11), [5, 95]) == [1.45, 9.55]
+        assert_close(t.p5, 1.45);
+        assert_close(t.p95, 9.55);
+
+        let h = stats.humidity.expect("humidity");
+        assert_close(h.std_dev, 0.0);
+        assert_eq!(h.min.recorded_at_ms, 0);
+        assert_eq!(h.max.recorded_at_ms, 0);
+
+        // 有効な値がない指標
+        assert_eq!(stats.illumination, None);
+    }
+
+    #[test]
+    fn summarizes_derived_metrics() {
+        let readings = [reading(0, 20.0), reading(1_000, 30.0)];
+        let derived = summarize_derived(&readings, &[DerivedMetric::DewPoint]);
+        let dew_point = derived[&DerivedMetric::DewPoint].as_ref().unwrap();
+        assert_eq!(dew_point.count, 2);
+        assert_eq!(dew_point.min.recorded_at_ms, 0);
+        assert_eq!(dew_point.max.recorded_at_ms, 1_000);
+    }
+
+    #[test]
+    fn single_sample_and_empty_input() {
+        let t = summarize(&[reading(5, 20.0)]).temperature.unwrap();
+        assert_eq!(t.count, 1);
+        assert_close(t.median, 20.0);
+        assert_close(t.p5, 20.0);
+        assert_close(t.p95, 20.0);
+        assert_close(t.std_dev, 0.0);
+
+        assert_eq!(summarize(&[]).temperature, None);
+    }
+}
@@ -0,0 +1,348 @@
+//! グラフなどで取得する期間。単位を明示して受け取り、内部ではナノ秒で持つ
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use pbjson_types::Timestamp;
+use serde::{Deserialize, Serialize};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// 終了時刻が現在よりこれ以上先の期間は受け付けない（時計のずれを見込んだ余裕）
+pub const MAX_FUTURE_SKEW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `TimeRangeInput` の値の単位。フロントエンドの `Date.getTime()` はミリ秒
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    fn nanos(self) -> i128 {
+        match self {
+            TimeUnit::Seconds => NANOS_PER_SECOND,
+            TimeUnit::Milliseconds => 1_000_000,
+            TimeUnit::Microseconds => 1_000,
+            TimeUnit::Nanoseconds => 1,
+        }
+    }
+}
+
+/// フロントエンドとやり取りする期間（Unix エポックからの経過時間を `unit` 単位で）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRangeInput {
+    pub start: i64,
+    pub end: i64,
+    pub unit: TimeUnit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TimeRangeError {
+    #[error("time range is empty (start equals end)")]
+    Empty,
+    #[error("time range is inverted (end is before start)")]
+    Inverted,
+    #[error("time range ends too far in the future")]
+    FarFuture,
+}
+
+/// 検証済みの期間。開始と終了は Unix エポックからのナノ秒で、開始 < 終了
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeRange {
+    start_nanos: i128,
+    end_nanos: i128,
+}
+
+impl TimeRange {
+    /// 現在時刻を基準に `input` を検証する
+    pub fn new(input: TimeRangeInput) -> Result<Self, TimeRangeError> {
+        Self::new_at(input, SystemTime::now())
+    }
+
+    /// `now` を現在時刻とみなして `input` を検証する
+    pub fn new_at(input: TimeRangeInput, now: SystemTime) -> Result<Self, TimeRangeError> {
+        let unit = input.unit.nanos();
+        let range = Self::from_nanos(i128::from(input.start) * unit, i128::from(input.end) * unit)?;
+        if range.end_nanos > nanos_since_epoch(now) + MAX_FUTURE_SKEW.as_nanos() as i128 {
+            return Err(TimeRangeError::FarFuture);
+        }
+        Ok(range)
+    }
+
+    /// ナノ秒で表した期間を作る。未来かどうかは確かめない
+    pub fn from_nanos(start_nanos: i128, end_nanos: i128) -> Result<Self, TimeRangeError> {
+        match start_nanos.cmp(&end_nanos) {
+            std::cmp::Ordering::Less => Ok(Self {
+                start_nanos,
+                end_nanos,
+            }),
+            std::cmp::Ordering::Equal => Err(TimeRangeError::Empty),
+            std::cmp::Ordering::Greater => Err(TimeRangeError::Inverted),
+        }
+    }
+
+    /// ミリ秒で表した期間を作る。未来かどうかは確かめない
+    pub fn from_millis(start_ms: i64, end_ms: i64) -> Result<Self, TimeRangeError> {
+        let unit = TimeUnit::Milliseconds.nanos();
+        Self::from_nanos(i128::from(start_ms) * unit, i128::from(end_ms) * unit)
+    }
+
+    /// 期間の長さ
+    pub fn duration(&self) -> Duration {
+        let nanos = self.end_nanos - self.start_nanos;
+        Duration::new(
+            (nanos / NANOS_PER_SECOND) as u64,
+            (nanos % NANOS_PER_SECOND) as u32,
+        )
+    }
+
+    /// 先頭から `window` ごとに区切る。最後の区間は `window` より短いことがある
+    pub fn split(&self, window: Duration) -> Vec<TimeRange> {
+        let step = (window.as_nanos() as i128).max(1);
+        let mut windows = Vec::new();
+        let mut start = self.start_nanos;
+        while start < self.end_nanos {
+            let end = (start + step).min(self.end_nanos);
+            windows.push(Self {
+                start_nanos: start,
+                end_nanos: end,
+            });
+            start = end;
+        }
+        windows
+    }
+
+    /// 重なる部分（なければ None）
+    pub fn intersection(&self, other: &TimeRange) -> Option<TimeRange> {
+        Self::from_nanos(
+            self.start_nanos.max(other.start_nanos),
+            self.end_nanos.min(other.end_nanos),
+        )
+        .ok()
+    }
+
+    /// `covered` のどれにも含まれない部分を、先頭から順に返す
+    pub fn gaps(&self, covered: &[TimeRange]) -> Vec<TimeRange> {
+        let mut covered: Vec<_> = covered
+            .iter()
+            .filter_map(|c| self.intersection(c))
+            .collect();
+        covered.sort_by_key(|c| c.start_nanos);
+
+        let mut gaps = Vec::new();
+        let mut cursor = self.start_nanos;
+        for c in covered {
+            if c.start_nanos > cursor {
+                gaps.push(Self {
+                    start_nanos: cursor,
+                    end_nanos: c.start_nanos,
+                });
+            }
+            cursor = cursor.max(c.end_nanos);
+        }
+        if cursor < self.end_nanos {
+            gaps.push(Self {
+                start_nanos: cursor,
+                end_nanos: self.end_nanos,
+            });
+        }
+        gaps
+    }
+
+    /// フロントエンドへ返すためにミリ秒に直す（期間を含むよう、開始は切り捨て・終了は切り上げ）
+    pub fn to_millis(self) -> TimeRangeInput {
+        let unit = TimeUnit::Milliseconds.nanos();
+        TimeRangeInput {
+            start: self.start_nanos.div_euclid(unit) as i64,
+            end: (self.end_nanos + unit - 1).div_euclid(unit) as i64,
+            unit: TimeUnit::Milliseconds,
+        }
+    }
+
+    pub fn start_timestamp(&self) -> Timestamp {
+        timestamp_from_nanos(self.start_nanos)
+    }
+
+    pub fn end_timestamp(&self) -> Timestamp {
+        timestamp_from_nanos(self.end_nanos)
+    }
+}
+
+/// protobuf の Timestamp は nanos を常に 0 以上で持つので、エポックより前は秒を 1 つ繰り下げる
+fn timestamp_from_nanos(nanos: i128) -> Timestamp {
+    Timestamp {
+        seconds: nanos.div_euclid(NANOS_PER_SECOND) as i64,
+        nanos: nanos.rem_euclid(NANOS_PER_SECOND) as i32,
+    }
+}
+
+fn nanos_since_epoch(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2023-11-14T22:13:20Z
+    const NOW_SECS: u64 = 1_700_000_000;
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(NOW_SECS)
+    }
+
+    fn input(start: i64, end: i64, unit: TimeUnit) -> TimeRangeInput {
+        TimeRangeInput { start, end, unit }
+    }
+
+    #[test]
+    fn milliseconds_are_not_mistaken_for_seconds() {
+        let start_ms = (NOW_SECS as i64 - 3600) * 1000 + 250;
+        let end_ms = NOW_SECS as i64 * 1000;
+        let range = TimeRange::new_at(input(start_ms, end_ms, TimeUnit::Milliseconds), now())
+            .expect("valid range");
+
+        assert_eq!(
+            range.start_timestamp(),
+            Timestamp {
+                seconds: NOW_SECS as i64 - 3600,
+                nanos: 250_000_000,
+            }
+        );
+        assert_eq!(
+            range.end_timestamp(),
+            Timestamp {
+                seconds: NOW_SECS as i64,
+                nanos: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_nanosecond_precision() {
+        let start = NOW_SECS as i64 * 1_000_000_000 + 1;
+        let range = TimeRange::new_at(input(start, start + 999, TimeUnit::Nanoseconds), now())
+            .expect("valid range");
+
+        assert_eq!(range.start_timestamp().nanos, 1);
+        assert_eq!(range.end_timestamp().nanos, 1_000);
+        assert_eq!(range.duration(), Duration::from_nanos(999));
+    }
+
+    #[test]
+    fn accepts_every_unit() {
+        let secs = NOW_SECS as i64;
+        for (unit, scale) in [
+            (TimeUnit::Seconds, 1),
+            (TimeUnit::Milliseconds, 1_000),
+            (TimeUnit::Microseconds, 1_000_000),
+            (TimeUnit::Nanoseconds, 1_000_000_000),
+        ] {
+            let range = TimeRange::new_at(input((secs - 60) * scale, secs * scale, unit), now())
+                .expect("valid range");
+            assert_eq!(range.start_timestamp().seconds, secs - 60, "{unit:?}");
+            assert_eq!(range.end_timestamp().seconds, secs, "{unit:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_inverted_ranges() {
+        assert_eq!(
+            TimeRange::new_at(input(10, 10, TimeUnit::Seconds), now()),
+            Err(TimeRangeError::Empty)
+        );
+        assert_eq!(
+            TimeRange::new_at(input(20, 10, TimeUnit::Seconds), now()),
+            Err(TimeRangeError::Inverted)
+        );
+    }
+
+    #[test]
+    fn rejects_ranges_ending_far_in_the_future() {
+        let skew = MAX_FUTURE_SKEW.as_secs() as i64;
+        let now_secs = NOW_SECS as i64;
+
+        assert!(
+            TimeRange::new_at(input(now_secs, now_secs + skew, TimeUnit::Seconds), now()).is_ok()
+        );
+        assert_eq!(
+            TimeRange::new_at(
+                input(now_secs, now_secs + skew + 1, TimeUnit::Seconds),
+                now()
+            ),
+            Err(TimeRangeError::FarFuture)
+        );
+    }
+
+    #[test]
+    fn timestamps_before_epoch_use_non_negative_nanos() {
+        let range = TimeRange::from_nanos(-1_500_000_000, 0).expect("valid range");
+        assert_eq!(
+            range.start_timestamp(),
+            Timestamp {
+                seconds: -2,
+                nanos: 500_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn splits_into_windows_covering_the_range() {
+        let range = TimeRange::from_nanos(0, 25).expect("valid range");
+        let windows = range.split(Duration::from_nanos(10));
+        assert_eq!(
+            windows,
+            vec![
+                TimeRange::from_nanos(0, 10).unwrap(),
+                TimeRange::from_nanos(10, 20).unwrap(),
+                TimeRange::from_nanos(20, 25).unwrap(),
+            ]
+        );
+        assert_eq!(range.split(Duration::from_secs(1)), vec![range]);
+    }
+
+    #[test]
+    fn finds_gaps_between_covered_spans() {
+        let range = TimeRange::from_nanos(0, 100).unwrap();
+        let span = |start, end| TimeRange::from_nanos(start, end).unwrap();
+
+        assert_eq!(range.gaps(&[]), vec![range]);
+        assert_eq!(
+            range.gaps(&[span(60, 80), span(-10, 20), span(10, 30), span(200, 300)]),
+            vec![span(30, 60), span(80, 100)]
+        );
+        assert!(range.gaps(&[span(-1, 50), span(50, 101)]).is_empty());
+    }
+
+    #[test]
+    fn intersects_overlapping_ranges_only() {
+        let a = TimeRange::from_millis(0, 10).unwrap();
+        let b = TimeRange::from_millis(5, 20).unwrap();
+        let c = TimeRange::from_millis(10, 20).unwrap();
+        assert_eq!(
+            a.intersection(&b),
+            Some(TimeRange::from_millis(5, 10).unwrap())
+        );
+        assert_eq!(a.intersection(&c), None);
+    }
+
+    #[test]
+    fn converts_to_millis_covering_the_range() {
+        let range = TimeRange::from_nanos(1_500_000, 2_000_001).expect("valid range");
+        assert_eq!(range.to_millis(), input(1, 3, TimeUnit::Milliseconds));
+    }
+
+    #[test]
+    fn deserializes_from_frontend_payload() {
+        let parsed: TimeRangeInput = serde_json::from_value(
+            serde_json::json!({ "start": 1, "end": 2, "unit": "milliseconds" }),
+        )
+        .unwrap();
+        assert_eq!(parsed, input(1, 2, TimeUnit::Milliseconds));
+    }
+}
@@ -0,0 +1,134 @@
+//! ライブ更新で届いた測定値を、届いた順にアラートの規則で判定するバックグラウンドのスレッド
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+
+use crate::controller::alert_controller::AlertController;
+use crate::domain::alert::{AlertFired, RuleState};
+use crate::domain::ambient::AmbientReading;
+use crate::infrastructure::db::DbPool;
+use crate::repository::diesel_alert_repository::DieselAlertRepository;
+
+/// 規則ごとの判定の途中経過。キーは規則の ID
+type AlertStates = Mutex<HashMap<i32, RuleState>>;
+
+/// 判定のスレッドへの入り口。
+/// 途中経過（しきい値を超え始めた時刻など）は届いた順に進める必要があるので、判定は 1 本のスレッドで順に行う
+#[derive(Clone)]
+pub struct AlertEvaluator {
+    sender: mpsc::Sender<Vec<AmbientReading>>,
+    states: Arc<AlertStates>,
+}
+
+impl AlertEvaluator {
+    /// 判定のスレッドを起動する。新たに発報するたびに `on_fired` を呼ぶ（アプリでは通知とイベントを送る）
+    pub fn spawn<F>(pool: DbPool, on_fired: F) -> Self
+    where
+        F: Fn(&AlertFired) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Vec<AmbientReading>>();
+        let states: Arc<AlertStates> = Default::default();
+        let thread_states = states.clone();
+        thread::spawn(move || {
+            // すべての送り手が破棄されたら終わる
+            for readings in receiver {
+                for fired in evaluate(&pool, &thread_states, &readings) {
+                    on_fired(&fired);
+                }
+            }
+        });
+        Self { sender, states }
+    }
+
+    /// 新しく届いた測定値を判定待ちに加える
+    pub fn submit(&self, readings: Vec<AmbientReading>) {
+        if self.sender.send(readings).is_err() {
+            eprintln!("alerts: evaluator has stopped");
+        }
+    }
+
+    /// 規則が変わったので、その規則の途中経過を捨てる
+    pub fn reset(&self, rule_id: i32) {
+        self.states.lock().unwrap().remove(&rule_id);
+    }
+}
+
+/// 測定値を判定し、新たに発報したものを返す。裏で動くので、失敗してもログに残すだけにする
+fn evaluate(pool: &DbPool, states: &AlertStates, readings: &[AmbientReading]) -> Vec<AlertFired> {
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("alerts: failed to get a connection: {e}");
+            return Vec::new();
+        }
+    };
+    let mut repo = DieselAlertRepository { conn };
+    let mut states = states.lock().unwrap();
+    AlertController::new(&mut repo)
+        .evaluate(&mut states, readings)
+        .unwrap_or_else(|e| {
+            eprintln!("alerts: failed to evaluate rules: {e}");
+            Vec::new()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+
+    use super::*;
+    use crate::domain::alert::{AlertRule, Comparator};
+    use crate::domain::ambient::AmbientMetric;
+    use crate::repository::diesel_alert_repository::AlertRepository as _;
+
+    fn reading(recorded_at_ms: i64, temperature: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 40.0,
+            illumination: 100.0,
+        }
+    }
+
+    #[test]
+    fn evaluates_batches_in_submission_order() {
+        let manager = ConnectionManager::<SqliteConnection>::new(
+            "file:memdb_alert_evaluator?mode=memory&cache=shared",
+        );
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        {
+            let mut repo = DieselAlertRepository {
+                conn: pool.get().expect("conn"),
+            };
+            repo.create_rule(&AlertRule {
+                id: 0,
+                name: "Too hot".into(),
+                metric: AmbientMetric::Temperature,
+                comparator: Comparator::Gt,
+                threshold: 28.0,
+                sustained_ms: 2_000,
+                quiet_hours: None,
+                enabled: true,
+            })
+            .expect("create");
+        }
+
+        let (sender, fired) = mpsc::channel();
+        let evaluator = AlertEvaluator::spawn(pool, move |alert: &AlertFired| {
+            sender.send(alert.event.fired_at_ms).unwrap();
+        });
+        // 1 件ずつ届いても、しきい値を超え続けた時間を順に数えて 1 回だけ発報する
+        for at in 0..5 {
+            evaluator.submit(vec![reading(at * 1_000, 30.0)]);
+        }
+
+        let first = fired.recv_timeout(Duration::from_secs(5)).expect("fired");
+        assert_eq!(first, 2_000);
+        assert!(fired.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! 長い期間を区間に分け、同時実行数を抑えながら `GetAmbientConditions` を呼ぶ。
+//! 1 回の RPC が大きくなりすぎて、メッセージサイズの上限やサーバーのタイムアウトに当たるのを避ける
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsRequest;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tonic::Status;
+
+use crate::domain::time_range::TimeRange;
+use crate::infrastructure::grpc_client::{self, AuthenticatedChannel};
+
+/// 1 回の RPC で取得する期間の既定の上限
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// 同時に投げる RPC の数の既定の上限
+pub const DEFAULT_CONCURRENCY: usize = 4;
+/// 間引かずに取得するときの 1 回の RPC の期間の上限（全件が返るので短くする）
+pub const UNSAMPLED_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 期間の分け方
+#[derive(Debug, Clone)]
+pub struct ChunkPlan {
+    pub window: Duration,
+    pub concurrency: usize,
+}
+
+impl Default for ChunkPlan {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// 区間ごとの応答（期間の順）と、取得できなかった区間
+#[derive(Debug, Default)]
+pub struct ChunkedResponse {
+    pub chunks: Vec<(TimeRange, Bytes)>,
+    pub failures: Vec<(TimeRange, Status)>,
+}
+
+/// `ranges` をそれぞれ `plan.window` ごとに分けて取得する。
+/// 各区間のサンプル数は、サンプルの間隔がおよそ `interval` になるように決める（`None` なら間引かせない）。
+/// 失敗した区間は `failures` に入れ、残りの区間の取得は続ける
+pub async fn fetch_chunked(
+    channel: AuthenticatedChannel,
+    ranges: &[TimeRange],
+    interval: Option<Duration>,
+    plan: &ChunkPlan,
+) -> ChunkedResponse {
+    let windows: Vec<_> = ranges
+        .iter()
+        .flat_map(|range| range.split(plan.window))
+        .collect();
+    let interval = interval.map(|interval| interval.as_nanos().max(1));
+    let semaphore = Arc::new(Semaphore::new(plan.concurrency.max(1)));
+
+    let mut tasks = JoinSet::new();
+    for (index, window) in windows.iter().enumerate() {
+        let samples = interval.map(|interval| {
+            let window_samples = window.duration().as_nanos().div_ceil(interval).max(1);
+            i32::try_from(window_samples).unwrap_or(i32::MAX)
+        });
+        let request = GetAmbientConditionsRequest {
+            start_time: Some(window.start_timestamp()),
+            end_time: Some(window.end_timestamp()),
+            samples,
+        };
+        let channel = channel.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = grpc_client::get_ambient_conditions_raw(channel, request).await;
+            (index, result)
+        });
+    }
+
+    let mut results: Vec<Option<Result<Bytes, Status>>> = vec![None; windows.len()];
+    while let Some(joined) = tasks.join_next().await {
+        // タスクが panic した区間は結果がないまま残り、失敗として扱う
+        if let Ok((index, result)) = joined {
+            results[index] = Some(result);
+        }
+    }
+
+    let mut response = ChunkedResponse::default();
+    for (window, result) in windows.into_iter().zip(results) {
+        match result.unwrap_or_else(|| Err(Status::internal("fetch task failed"))) {
+            Ok(bytes) => response.chunks.push((window, bytes)),
+            Err(status) => response.failures.push((window, status)),
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::settings::{AuthMode, Settings};
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd};
+    use prost::Message as _;
+    use std::collections::HashMap;
+    use std::time::Instant;
+    use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+
+    const DAY_NANOS: i128 = 24 * 60 * 60 * 1_000_000_000;
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// 区間の開始時刻（秒）をキーにした 1 件だけの応答を返す。`fail_from` 以降の区間は失敗させる
+    fn fake(fail_from: Option<i64>) -> FakeTempgrpcd {
+        FakeTempgrpcd::new(move |request| {
+            let request = request.into_inner();
+            let start = request.start_time.expect("start").seconds;
+            if fail_from.is_some_and(|from| start >= from) {
+                return Err(Status::internal("window failed"));
+            }
+            let mut ambient_conditions = HashMap::new();
+            ambient_conditions.insert(
+                format!("{start}-{}", request.samples.unwrap_or_default()),
+                AmbientCondition {
+                    temperature: 20.0,
+                    humidity: 50.0,
+                    illumination: 100.0,
+                },
+            );
+            Ok(GetAmbientConditionsResponse { ambient_conditions })
+        })
+    }
+
+    async fn channel_to(service: FakeTempgrpcd) -> AuthenticatedChannel {
+        let addr = test_server::spawn_plaintext(service).await;
+        let settings = Settings {
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+        grpc_client::connect_authenticated(&settings, None)
+            .await
+            .expect("connect")
+    }
+
+    fn days(n: i128) -> TimeRange {
+        TimeRange::from_nanos(0, n * DAY_NANOS).expect("valid range")
+    }
+
+    fn plan(concurrency: usize) -> ChunkPlan {
+        ChunkPlan {
+            window: DAY,
+            concurrency,
+        }
+    }
+
+    /// 4 日を 100 サンプルで取るときの間隔
+    const INTERVAL: Duration = Duration::from_secs(4 * 24 * 60 * 60 / 100);
+
+    fn merged(chunks: &[(TimeRange, Bytes)]) -> GetAmbientConditionsResponse {
+        let body: Vec<u8> = chunks
+            .iter()
+            .flat_map(|(_, bytes)| bytes.to_vec())
+            .collect();
+        GetAmbientConditionsResponse::decode(body.as_slice()).expect("decode")
+    }
+
+    #[tokio::test]
+    async fn merges_windows_and_splits_samples() {
+        let channel = channel_to(fake(None)).await;
+
+        let res = fetch_chunked(channel, &[days(4)], Some(INTERVAL), &plan(2)).await;
+        assert!(res.failures.is_empty());
+        assert_eq!(res.chunks.len(), 4);
+
+        let mut keys: Vec<_> = merged(&res.chunks).ambient_conditions.into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["0-25", "172800-25", "259200-25", "86400-25"]);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_windows_as_partial() {
+        // 3 日目以降の区間だけ失敗させる
+        let channel = channel_to(fake(Some(2 * 86_400))).await;
+
+        let res = fetch_chunked(channel, &[days(4)], Some(INTERVAL), &plan(4)).await;
+        assert_eq!(res.chunks.len(), 2);
+        let failed: Vec<_> = res.failures.iter().map(|(window, _)| *window).collect();
+        assert_eq!(failed, days(4).split(DAY)[2..]);
+        assert_eq!(merged(&res.chunks).ambient_conditions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fetches_only_the_given_ranges() {
+        let channel = channel_to(fake(None)).await;
+        let gaps = [
+            TimeRange::from_nanos(0, DAY_NANOS).unwrap(),
+            TimeRange::from_nanos(3 * DAY_NANOS, 4 * DAY_NANOS).unwrap(),
+        ];
+
+        let res = fetch_chunked(channel, &gaps, Some(INTERVAL), &plan(4)).await;
+        let windows: Vec<_> = res.chunks.iter().map(|(window, _)| *window).collect();
+        assert_eq!(windows, gaps);
+    }
+
+    #[tokio::test]
+    async fn leaves_samples_unset_without_interval() {
+        let channel = channel_to(fake(None)).await;
+
+        let res = fetch_chunked(channel, &[days(2)], None, &plan(2)).await;
+        let mut keys: Vec<_> = merged(&res.chunks).ambient_conditions.into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["0-0", "86400-0"]);
+    }
+
+    #[tokio::test]
+    async fn bounds_concurrent_requests() {
+        let delay = Duration::from_millis(200);
+        let channel = channel_to(fake(None).with_delay(delay)).await;
+
+        // 4 区間を 2 並列なら、少なくとも 2 往復分かかり、直列の 4 往復分よりは速い
+        let started = Instant::now();
+        fetch_chunked(channel, &[days(4)], Some(INTERVAL), &plan(2)).await;
+        let elapsed = started.elapsed();
+        assert!(elapsed >= delay * 2, "{elapsed:?}");
+        assert!(elapsed < delay * 4, "{elapsed:?}");
+    }
+}
@@ -0,0 +1,502 @@
+//! gRPC クライアントの接続状態を管理し、切断時にバックグラウンドで再接続する
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rand::Rng as _;
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinHandle;
+
+use crate::domain::client_certificate::ClientIdentity;
+use crate::domain::connection::{ConnectionState, ConnectionStatus};
+use crate::domain::settings::Settings;
+use crate::infrastructure::grpc_client::{self, AuthenticatedChannel, GrpcClientError};
+
+/// 再接続の待ち時間の決め方（指数バックオフ + ジッター）
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    /// 1 回目の再接続までの待ち時間の上限
+    pub initial_delay: Duration,
+    /// 待ち時間の上限
+    pub max_delay: Duration,
+    /// この回数だけ再接続に失敗したら `Failed` にする（None なら諦めない）
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: Some(20),
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// `attempt` 回目（1 始まり）の待ち時間。
+    /// 指数的に伸ばした上限の半分から上限までの間でランダムに揺らす (equal jitter)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let cap = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+        let half = cap / 2;
+        let jitter_ms = rand::rng().random_range(0..=(cap - half).as_millis() as u64);
+        half + Duration::from_millis(jitter_ms)
+    }
+}
+
+/// 設定ミスなど、再試行しても結果が変わらないエラーは再接続しない
+fn is_retryable(err: &GrpcClientError) -> bool {
+    matches!(
+        err,
+        GrpcClientError::Transport(_) | GrpcClientError::ConnectTimeout(_)
+    )
+}
+
+/// 接続先（再接続でも同じものを使う）
+struct Target {
+    settings: Settings,
+    identity: Option<ClientIdentity>,
+}
+
+struct Shared {
+    state: ConnectionState,
+    channel: Option<AuthenticatedChannel>,
+    target: Option<Arc<Target>>,
+    reconnect: Option<JoinHandle<()>>,
+    /// 接続が確立した時刻（Unix エポックからのミリ秒）
+    connected_at: Option<u64>,
+    last_error: Option<String>,
+}
+
+type StateListener = dyn Fn(&ConnectionState) + Send + Sync;
+
+struct Inner {
+    shared: Mutex<Shared>,
+    policy: BackoffPolicy,
+    listener: Box<StateListener>,
+    /// 状態の変化を待ちたいタスク（ライブ更新など）向け
+    states: watch::Sender<ConnectionState>,
+}
+
+/// 接続状態を持つ gRPC クライアントの入れ物。
+/// 状態が変わるたびに `listener` を呼ぶ（アプリでは Tauri のイベントとして送る）
+#[derive(Clone)]
+pub struct ConnectionManager {
+    inner: Arc<Inner>,
+}
+
+impl ConnectionManager {
+    pub fn new<F>(policy: BackoffPolicy, listener: F) -> Self
+    where
+        F: Fn(&ConnectionState) + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(Inner {
+                shared: Mutex::new(Shared {
+                    state: ConnectionState::Disconnected,
+                    channel: None,
+                    target: None,
+                    reconnect: None,
+                    connected_at: None,
+                    last_error: None,
+                }),
+                policy,
+                listener: Box::new(listener),
+                states: watch::Sender::new(ConnectionState::Disconnected),
+            }),
+        }
+    }
+
+    /// 現在の接続状態と接続先、直近のエラー
+    pub async fn status(&self) -> ConnectionStatus {
+        let shared = self.inner.shared.lock().await;
+        ConnectionStatus {
+            state: shared.state.clone(),
+            endpoint: shared.target.as_ref().map(|t| t.settings.url.clone()),
+            connected_at: shared.connected_at,
+            last_error: shared.last_error.clone(),
+        }
+    }
+
+    /// 接続状態の変化を受け取る
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionState> {
+        self.inner.states.subscribe()
+    }
+
+    /// 接続済みならチャネルを返す（クローン可能なのでロックはすぐ手放せる）
+    pub async fn channel(&self) -> Option<AuthenticatedChannel> {
+        self.inner.shared.lock().await.channel.clone()
+    }
+
+    /// 新しい設定で接続し直す。進行中の再接続は中止する。
+    /// 一時的な失敗ならエラーを返しつつバックグラウンドで再接続を続ける
+    pub async fn connect(
+        &self,
+        settings: Settings,
+        identity: Option<ClientIdentity>,
+    ) -> Result<(), GrpcClientError> {
+        let target = Arc::new(Target { settings, identity });
+        {
+            let mut shared = self.inner.shared.lock().await;
+            if let Some(task) = shared.reconnect.take() {
+                task.abort();
+            }
+            shared.channel = None;
+            shared.target = Some(target.clone());
+            self.set_state(&mut shared, ConnectionState::Connecting);
+        }
+
+        let result =
+            grpc_client::connect_authenticated(&target.settings, target.identity.as_ref()).await;
+
+        let mut shared = self.inner.shared.lock().await;
+        if !is_current(&shared, &target) {
+            // 接続中に別の connect が呼ばれたので、この結果は捨てる
+            return result.map(|_| ());
+        }
+        match result {
+            Ok(channel) => {
+                shared.channel = Some(channel);
+                self.set_state(&mut shared, ConnectionState::Connected);
+                Ok(())
+            }
+            Err(e) if is_retryable(&e) => {
+                shared.last_error = Some(e.to_string());
+                self.spawn_reconnect(&mut shared, target);
+                Err(e)
+            }
+            Err(e) => {
+                shared.last_error = Some(e.to_string());
+                self.set_state(
+                    &mut shared,
+                    ConnectionState::Failed {
+                        reason: e.to_string(),
+                    },
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// RPC がトランスポートエラーで失敗したことを知らせる。
+    /// 接続済みだった場合はチャネルを捨てて再接続を始める
+    pub async fn report_failure(&self, reason: String) {
+        let mut shared = self.inner.shared.lock().await;
+        // 既に再接続を始めていれば（channel を捨てていれば）何もしない
+        if shared.state != ConnectionState::Connected || shared.channel.is_none() {
+            return;
+        }
+        let Some(target) = shared.target.clone() else {
+            return;
+        };
+        eprintln!("gRPC connection lost: {reason}");
+        shared.channel = None;
+        shared.last_error = Some(reason);
+        self.spawn_reconnect(&mut shared, target);
+    }
+
+    fn set_state(&self, shared: &mut Shared, state: ConnectionState) {
+        if shared.state != state {
+            shared.connected_at = match state {
+                ConnectionState::Connected => Some(now_millis()),
+                _ => None,
+            };
+            shared.state = state;
+            (self.inner.listener)(&shared.state);
+            self.inner.states.send_replace(shared.state.clone());
+        }
+    }
+
+    /// 1 回目の待機 (`Backoff`) に入ってから再接続タスクを起動する
+    fn spawn_reconnect(&self, shared: &mut Shared, target: Arc<Target>) {
+        let delay = self.enter_backoff(shared, 1);
+        let this = self.clone();
+        shared.reconnect = Some(tokio::spawn(async move {
+            this.reconnect_loop(target, delay).await;
+        }));
+    }
+
+    fn enter_backoff(&self, shared: &mut Shared, attempt: u32) -> Duration {
+        let delay = self.inner.policy.delay(attempt);
+        self.set_state(
+            shared,
+            ConnectionState::Backoff {
+                attempt,
+                retry_in_ms: delay.as_millis() as u64,
+            },
+        );
+        delay
+    }
+
+    async fn reconnect_loop(&self, target: Arc<Target>, mut delay: Duration) {
+        let mut attempt = 1;
+        loop {
+            tokio::time::sleep(delay).await;
+            if !self.update(&target, ConnectionState::Connecting).await {
+                return;
+            }
+
+            let result =
+                grpc_client::connect_authenticated(&target.settings, target.identity.as_ref())
+                    .await;
+
+            let mut shared = self.inner.shared.lock().await;
+            if !is_current(&shared, &target) {
+                return;
+            }
+            let (retryable, last_error) = match result {
+                Ok(channel) => {
+                    shared.channel = Some(channel);
+                    shared.reconnect = None;
+                    self.set_state(&mut shared, ConnectionState::Connected);
+                    return;
+                }
+                Err(e) => (is_retryable(&e), e.to_string()),
+            };
+            shared.last_error = Some(last_error.clone());
+            let exhausted = self
+                .inner
+                .policy
+                .max_attempts
+                .is_some_and(|max| attempt >= max);
+            if !retryable || exhausted {
+                shared.reconnect = None;
+                self.set_state(&mut shared, ConnectionState::Failed { reason: last_error });
+                return;
+            }
+            attempt += 1;
+            delay = self.enter_backoff(&mut shared, attempt);
+        }
+    }
+
+    /// `target` がまだ現在の接続先であれば状態を更新して true を返す
+    async fn update(&self, target: &Arc<Target>, state: ConnectionState) -> bool {
+        let mut shared = self.inner.shared.lock().await;
+        if !is_current(&shared, target) {
+            return false;
+        }
+        self.set_state(&mut shared, state);
+        true
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn is_current(shared: &Shared, target: &Arc<Target>) -> bool {
+    shared
+        .target
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::settings::AuthMode;
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd};
+    use std::sync::Mutex as StdMutex;
+    use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsResponse;
+
+    fn fast_policy(max_attempts: Option<u32>) -> BackoffPolicy {
+        BackoffPolicy {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(40),
+            max_attempts,
+        }
+    }
+
+    fn recording_manager(
+        policy: BackoffPolicy,
+    ) -> (ConnectionManager, Arc<StdMutex<Vec<ConnectionState>>>) {
+        let states = Arc::new(StdMutex::new(Vec::new()));
+        let recorded = states.clone();
+        let manager = ConnectionManager::new(policy, move |state| {
+            recorded.lock().unwrap().push(state.clone());
+        });
+        (manager, states)
+    }
+
+    fn settings_for(url: String) -> Settings {
+        Settings {
+            id: 1,
+            url,
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        }
+    }
+
+    async fn wait_for_state(manager: &ConnectionManager, expected: fn(&ConnectionState) -> bool) {
+        for _ in 0..200 {
+            if expected(&manager.status().await.state) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("state not reached: {:?}", manager.status().await.state);
+    }
+
+    /// 接続を拒否するアドレス（bind してすぐ閉じたポート）
+    async fn closed_addr() -> std::net::SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[test]
+    fn backoff_delay_grows_exponentially_within_jitter_bounds() {
+        let policy = BackoffPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            max_attempts: None,
+        };
+        for (attempt, cap) in [
+            (1, 100),
+            (2, 200),
+            (3, 400),
+            (4, 800),
+            (5, 1000),
+            (40, 1000),
+        ] {
+            for _ in 0..20 {
+                let delay = policy.delay(attempt).as_millis() as u64;
+                assert!(
+                    delay >= cap / 2 && delay <= cap,
+                    "attempt {attempt}: {delay}ms not in [{}, {cap}]",
+                    cap / 2
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_emits_connecting_then_connected() {
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(|_| {
+            Ok(GetAmbientConditionsResponse::default())
+        }))
+        .await;
+        let (manager, states) = recording_manager(fast_policy(Some(3)));
+
+        manager
+            .connect(settings_for(format!("http://{addr}")), None)
+            .await
+            .expect("connect ok");
+
+        assert!(manager.channel().await.is_some());
+        assert_eq!(
+            states.lock().unwrap().as_slice(),
+            [ConnectionState::Connecting, ConnectionState::Connected]
+        );
+        let status = manager.status().await;
+        assert_eq!(status.endpoint, Some(format!("http://{addr}")));
+        assert!(status.connected_at.is_some());
+        assert_eq!(status.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn connect_retries_with_backoff_then_fails() {
+        let addr = closed_addr().await;
+        let (manager, states) = recording_manager(fast_policy(Some(2)));
+
+        let res = manager
+            .connect(settings_for(format!("http://{addr}")), None)
+            .await;
+        assert!(matches!(res, Err(GrpcClientError::Transport(_))));
+
+        wait_for_state(&manager, |s| matches!(s, ConnectionState::Failed { .. })).await;
+        let states = states.lock().unwrap().clone();
+        let attempts: Vec<u32> = states
+            .iter()
+            .filter_map(|s| match s {
+                ConnectionState::Backoff { attempt, .. } => Some(*attempt),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(attempts, [1, 2]);
+        assert_eq!(states.first(), Some(&ConnectionState::Connecting));
+        assert!(matches!(
+            states.last(),
+            Some(ConnectionState::Failed { .. })
+        ));
+        assert!(manager.channel().await.is_none());
+        let status = manager.status().await;
+        assert_eq!(status.connected_at, None);
+        assert!(status.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn connect_fails_without_retry_on_invalid_settings() {
+        let (manager, states) = recording_manager(fast_policy(None));
+
+        let res = manager
+            .connect(settings_for("ftp://example.com".into()), None)
+            .await;
+        assert!(matches!(res, Err(GrpcClientError::UnsupportedScheme(_))));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let states = states.lock().unwrap().clone();
+        assert_eq!(states.len(), 2);
+        assert!(matches!(states[1], ConnectionState::Failed { .. }));
+    }
+
+    #[tokio::test]
+    async fn report_failure_reconnects_in_background() {
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(|_| {
+            Ok(GetAmbientConditionsResponse::default())
+        }))
+        .await;
+        let (manager, states) = recording_manager(fast_policy(None));
+        manager
+            .connect(settings_for(format!("http://{addr}")), None)
+            .await
+            .expect("connect ok");
+
+        manager.report_failure("connection reset".into()).await;
+        manager.report_failure("connection reset".into()).await;
+        assert!(manager.channel().await.is_none());
+        wait_for_state(&manager, |s| *s == ConnectionState::Connected).await;
+
+        let states = states.lock().unwrap().clone();
+        assert!(matches!(
+            states[2],
+            ConnectionState::Backoff { attempt: 1, .. }
+        ));
+        assert_eq!(states[3], ConnectionState::Connecting);
+        assert_eq!(states[4], ConnectionState::Connected);
+        assert_eq!(states.len(), 5);
+        assert!(manager.channel().await.is_some());
+        let status = manager.status().await;
+        assert!(status.connected_at.is_some());
+        assert_eq!(status.last_error.as_deref(), Some("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn connect_cancels_pending_reconnect() {
+        let closed = closed_addr().await;
+        let (manager, _states) = recording_manager(BackoffPolicy {
+            initial_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        });
+        let _ = manager
+            .connect(settings_for(format!("http://{closed}")), None)
+            .await;
+        wait_for_state(&manager, |s| matches!(s, ConnectionState::Backoff { .. })).await;
+
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(|_| {
+            Ok(GetAmbientConditionsResponse::default())
+        }))
+        .await;
+        manager
+            .connect(settings_for(format!("http://{addr}")), None)
+            .await
+            .expect("connect ok");
+        assert_eq!(manager.status().await.state, ConnectionState::Connected);
+    }
+}
@@ -1,4 +1,5 @@
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::RunQueryDsl as _;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
 use diesel::sqlite::SqliteConnection;
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use std::path::Path;
@@ -9,6 +10,19 @@ pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("src/migration");
 
+/// 測定値のキャッシュは裏で書き込むので、ロック中の接続はすぐ失敗させずに待たせる
+#[derive(Debug)]
+struct BusyTimeout;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for BusyTimeout {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        diesel::sql_query("PRAGMA busy_timeout = 5000")
+            .execute(conn)
+            .map(|_| ())
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
 pub fn establish_connection_pool(app_handle: &AppHandle) -> DbPool {
     // AppHandle 経由で PathResolver の app_data_dir() を取得
     let app_data: PathBuf = app_handle
@@ -28,6 +42,7 @@ pub fn establish_connection_pool_at(db_path: &Path) -> DbPool {
 
     let manager = ConnectionManager::<SqliteConnection>::new(database_url);
     Pool::builder()
+        .connection_customizer(Box::new(BusyTimeout))
         .build(manager)
         .expect("Failed to create DB pool")
 }
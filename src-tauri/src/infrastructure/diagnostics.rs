@@ -0,0 +1,563 @@
+//! 保存前の設定で接続を段階ごとに試し、どこで失敗したかを時間とともに報告する
+use std::fmt::Display;
+use std::future::{Future, poll_fn};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use http::Uri;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioIo;
+use pbjson_types::Timestamp;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject as _};
+use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsRequest;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tonic::codegen::Service;
+use url::Url;
+
+use crate::domain::client_certificate::ClientIdentity;
+use crate::domain::diagnostics::{
+    ConnectionReport, DiagnosticStep, DiagnosticStepKind, StepOutcome,
+};
+use crate::domain::settings::Settings;
+use crate::infrastructure::grpc_client::{self, DEFAULT_SOCKS_PORT, GrpcClientError};
+
+/// 1 段階あたりの制限時間
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// プロキシの種類（`grpc_client` と同じくスキームで決める）
+enum ProxyKind {
+    Http,
+    Https,
+    Socks5 { local_dns: bool },
+}
+
+struct ProxyPlan {
+    uri: Uri,
+    kind: ProxyKind,
+    host: String,
+    port: u16,
+}
+
+/// URL から読み取った接続経路
+struct Plan {
+    /// 角括弧なしのホスト名（IPv6 アドレスを含む）
+    host: String,
+    port: u16,
+    /// トンネルの宛先として使う `scheme://host:port`
+    authority_uri: Uri,
+    tls: bool,
+    proxy: Option<ProxyPlan>,
+}
+
+impl Plan {
+    /// 最初に TCP で接続する相手（プロキシがあればプロキシ）
+    fn first_hop(&self) -> (&str, u16) {
+        match &self.proxy {
+            Some(proxy) => (&proxy.host, proxy.port),
+            None => (&self.host, self.port),
+        }
+    }
+}
+
+/// 設定 `settings` で接続を段階ごとに試す。
+/// 失敗した段階より後は実行せず `Skipped` として報告する。アプリの接続状態には影響しない
+pub async fn test_connection(
+    settings: &Settings,
+    identity: Option<&ClientIdentity>,
+) -> ConnectionReport {
+    let mut report = ReportBuilder::default();
+
+    let Some(plan) = report
+        .step(DiagnosticStepKind::Url, async { plan(settings) })
+        .await
+    else {
+        return report.finish();
+    };
+    let (hop_host, hop_port) = plan.first_hop();
+    let Some(addrs) = report
+        .step(DiagnosticStepKind::Dns, resolve(hop_host, hop_port))
+        .await
+    else {
+        return report.finish();
+    };
+    let Some(tcp) = report
+        .step(DiagnosticStepKind::Tcp, connect_tcp(&addrs))
+        .await
+    else {
+        return report.finish();
+    };
+
+    let stream: Box<dyn Io> = match &plan.proxy {
+        Some(proxy) => {
+            // トンネルはコネクタが張り直すので、到達確認に使った接続は閉じる
+            drop(tcp);
+            let Some(stream) = report
+                .step(DiagnosticStepKind::Proxy, tunnel(proxy, &plan, settings))
+                .await
+            else {
+                return report.finish();
+            };
+            stream
+        }
+        None => {
+            report.skip(DiagnosticStepKind::Proxy, "no proxy configured");
+            Box::new(tcp)
+        }
+    };
+
+    if plan.tls {
+        let handshake = tls_handshake(stream, &plan, settings, identity);
+        if report
+            .step(DiagnosticStepKind::Tls, handshake)
+            .await
+            .is_none()
+        {
+            return report.finish();
+        }
+    } else {
+        drop(stream);
+        report.skip(DiagnosticStepKind::Tls, "plaintext (h2c) endpoint");
+    }
+
+    report
+        .step(DiagnosticStepKind::Rpc, probe_rpc(settings, identity))
+        .await;
+    report.finish()
+}
+
+/// 段階ごとの結果を順に積み上げる
+#[derive(Default)]
+struct ReportBuilder {
+    steps: Vec<DiagnosticStep>,
+}
+
+impl ReportBuilder {
+    /// `future` を制限時間付きで実行し、所要時間と結果を記録する
+    async fn step<T, F>(&mut self, step: DiagnosticStepKind, future: F) -> Option<T>
+    where
+        F: Future<Output = Result<(T, String), String>>,
+    {
+        let started = Instant::now();
+        let result = tokio::time::timeout(STEP_TIMEOUT, future)
+            .await
+            .unwrap_or_else(|_| Err(format!("timed out after {}s", STEP_TIMEOUT.as_secs())));
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (value, outcome, detail) = match result {
+            Ok((value, detail)) => (Some(value), StepOutcome::Ok, detail),
+            Err(detail) => (None, StepOutcome::Failed, detail),
+        };
+        self.steps.push(DiagnosticStep {
+            step,
+            outcome,
+            duration_ms,
+            detail,
+        });
+        value
+    }
+
+    fn skip(&mut self, step: DiagnosticStepKind, reason: &str) {
+        self.steps.push(DiagnosticStep {
+            step,
+            outcome: StepOutcome::Skipped,
+            duration_ms: 0,
+            detail: reason.to_string(),
+        });
+    }
+
+    /// 実行しなかった段階を `Skipped` で埋めて報告にする
+    fn finish(mut self) -> ConnectionReport {
+        for step in DiagnosticStepKind::ALL {
+            if !self.steps.iter().any(|s| s.step == step) {
+                self.skip(step, "not run: a previous step failed");
+            }
+        }
+        ConnectionReport {
+            ok: self.steps.iter().all(|s| s.outcome != StepOutcome::Failed),
+            steps: self.steps,
+        }
+    }
+}
+
+fn plan(settings: &Settings) -> Result<(Plan, String), String> {
+    let url = Url::parse(&settings.url).map_err(|e| GrpcClientError::from(e).to_string())?;
+    let tls = match url.scheme() {
+        "http" => false,
+        "https" => true,
+        other => return Err(GrpcClientError::UnsupportedScheme(other.to_string()).to_string()),
+    };
+    let host = url
+        .host_str()
+        .ok_or_else(|| GrpcClientError::from(url::ParseError::EmptyHost).to_string())?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| "endpoint URL has no port".to_string())?;
+    let authority_uri: Uri = format!("{}://{host}:{port}", url.scheme())
+        .parse()
+        .map_err(|e| GrpcClientError::InvalidUri(e).to_string())?;
+    let mut detail = format!("{}://{host}:{port}", url.scheme());
+
+    let proxy = if settings.use_proxies {
+        let proxy = proxy_plan(settings)?;
+        detail.push_str(&format!(" via {}", proxy.uri));
+        Some(proxy)
+    } else {
+        None
+    };
+
+    let plan = Plan {
+        host: unbracket(host).to_string(),
+        port,
+        authority_uri,
+        tls,
+        proxy,
+    };
+    Ok((plan, detail))
+}
+
+fn proxy_plan(settings: &Settings) -> Result<ProxyPlan, String> {
+    let uri: Uri = settings
+        .proxy_url
+        .parse()
+        .map_err(|e| GrpcClientError::InvalidUri(e).to_string())?;
+    let (kind, default_port) = match uri.scheme_str() {
+        None | Some("http") => (ProxyKind::Http, 80),
+        Some("https") => (ProxyKind::Https, 443),
+        Some(scheme @ ("socks5" | "socks5h")) => (
+            ProxyKind::Socks5 {
+                local_dns: scheme == "socks5",
+            },
+            DEFAULT_SOCKS_PORT,
+        ),
+        Some(other) => {
+            return Err(GrpcClientError::UnsupportedProxyScheme(other.to_string()).to_string());
+        }
+    };
+    let host = uri
+        .host()
+        .ok_or_else(|| "proxy URL has no host".to_string())?;
+    Ok(ProxyPlan {
+        host: unbracket(host).to_string(),
+        port: uri.port_u16().unwrap_or(default_port),
+        kind,
+        uri,
+    })
+}
+
+/// `[::1]` のような IPv6 リテラルの角括弧を外す
+fn unbracket(host: &str) -> &str {
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+async fn resolve(host: &str, port: u16) -> Result<(Vec<SocketAddr>, String), String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("failed to resolve {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{host} did not resolve to any address"));
+    }
+    let detail = addrs
+        .iter()
+        .map(|addr| addr.ip().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok((addrs, format!("{host} -> {detail}")))
+}
+
+async fn connect_tcp(addrs: &[SocketAddr]) -> Result<(TcpStream, String), String> {
+    let stream = TcpStream::connect(addrs)
+        .await
+        .map_err(|e| format!("TCP connection failed: {e}"))?;
+    let detail = match stream.peer_addr() {
+        Ok(peer) => format!("connected to {peer}"),
+        Err(_) => "connected".to_string(),
+    };
+    Ok((stream, detail))
+}
+
+/// 本番と同じコネクタでプロキシ越しに接続先までのトンネルを張る
+async fn tunnel(
+    proxy: &ProxyPlan,
+    plan: &Plan,
+    settings: &Settings,
+) -> Result<(Box<dyn Io>, String), String> {
+    let dst = plan.authority_uri.clone();
+    let stream: Box<dyn Io> = match proxy.kind {
+        ProxyKind::Http => {
+            let connector = grpc_client::http_proxy_connector(
+                HttpConnector::new(),
+                proxy.uri.clone(),
+                settings,
+            );
+            Box::new(TokioIo::new(connect_with(connector, dst).await?))
+        }
+        ProxyKind::Https => {
+            let roots = grpc_client::root_store(settings).map_err(|e| e.to_string())?;
+            let transport = grpc_client::https_proxy_transport(roots).map_err(|e| e.to_string())?;
+            let connector =
+                grpc_client::http_proxy_connector(transport, proxy.uri.clone(), settings);
+            Box::new(TokioIo::new(connect_with(connector, dst).await?))
+        }
+        ProxyKind::Socks5 { local_dns } => {
+            let connector =
+                grpc_client::socks5_connector(settings, local_dns).map_err(|e| e.to_string())?;
+            Box::new(TokioIo::new(connect_with(connector, dst).await?))
+        }
+    };
+    let detail = format!(
+        "tunnel to {}:{} established through {}:{}",
+        plan.host, plan.port, proxy.host, proxy.port
+    );
+    Ok((stream, detail))
+}
+
+async fn connect_with<C>(mut connector: C, dst: Uri) -> Result<C::Response, String>
+where
+    C: Service<Uri>,
+    C::Error: Display,
+{
+    poll_fn(|cx| connector.poll_ready(cx))
+        .await
+        .map_err(|e| format!("proxy connection failed: {e}"))?;
+    connector
+        .call(dst)
+        .await
+        .map_err(|e| format!("proxy connection failed: {e}"))
+}
+
+/// tonic と同じ信頼アンカー・クライアント証明書・ALPN で TLS ハンドシェイクだけを行う
+async fn tls_handshake(
+    stream: Box<dyn Io>,
+    plan: &Plan,
+    settings: &Settings,
+    identity: Option<&ClientIdentity>,
+) -> Result<((), String), String> {
+    let roots = grpc_client::root_store(settings).map_err(|e| e.to_string())?;
+    if roots.is_empty() {
+        return Err(GrpcClientError::NoTrustAnchors.to_string());
+    }
+    let builder = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| e.to_string())?
+    .with_root_certificates(roots);
+    let mut config = match identity {
+        Some(identity) => {
+            let chain = CertificateDer::pem_slice_iter(identity.cert_pem.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("invalid client certificate: {e}"))?;
+            let key = PrivateKeyDer::from_pem_slice(identity.key_pem.as_bytes())
+                .map_err(|e| format!("invalid client private key: {e}"))?;
+            builder
+                .with_client_auth_cert(chain, key)
+                .map_err(|e| format!("invalid client certificate: {e}"))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"h2".to_vec()];
+
+    let server_name = ServerName::try_from(plan.host.clone())
+        .map_err(|e| GrpcClientError::InvalidTlsDomain(e).to_string())?;
+    let tls = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| format!("TLS handshake failed: {e}"))?;
+
+    let (_, connection) = tls.get_ref();
+    let version = connection
+        .protocol_version()
+        .map(|v| format!("{v:?}"))
+        .unwrap_or_else(|| "unknown version".to_string());
+    let alpn = match connection.alpn_protocol() {
+        Some(protocol) => format!("ALPN {}", String::from_utf8_lossy(protocol)),
+        None => "no ALPN negotiated".to_string(),
+    };
+    Ok(((), format!("{version}, {alpn}")))
+}
+
+/// 直近 1 分間のデータを 1 サンプルだけ要求し、認証が通ることを確かめる
+async fn probe_rpc(
+    settings: &Settings,
+    identity: Option<&ClientIdentity>,
+) -> Result<((), String), String> {
+    if settings.auth_mode.requires_token() && settings.access_token.is_empty() {
+        return Err("access token is empty".to_string());
+    }
+    let mut client = grpc_client::new(settings, identity)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    client
+        .get_ambient_conditions(tonic::Request::new(GetAmbientConditionsRequest {
+            start_time: Some(Timestamp {
+                seconds: now - 60,
+                nanos: 0,
+            }),
+            end_time: Some(Timestamp {
+                seconds: now,
+                nanos: 0,
+            }),
+            samples: Some(1),
+        }))
+        .await
+        .map_err(|status| format!("{:?}: {}", status.code(), status.message()))?;
+    Ok(((), "GetAmbientConditions succeeded".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::settings::AuthMode;
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd, TestPki};
+    use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsResponse;
+
+    fn outcomes(report: &ConnectionReport) -> Vec<(DiagnosticStepKind, StepOutcome)> {
+        report.steps.iter().map(|s| (s.step, s.outcome)).collect()
+    }
+
+    fn ok_server() -> FakeTempgrpcd {
+        FakeTempgrpcd::new(|req| {
+            if req.metadata().get("authorization").is_none() {
+                return Err(tonic::Status::unauthenticated("missing token"));
+            }
+            Ok(GetAmbientConditionsResponse::default())
+        })
+    }
+
+    #[tokio::test]
+    async fn reports_every_step_for_plaintext_endpoint() {
+        let addr = test_server::spawn_plaintext(ok_server()).await;
+        let s = Settings {
+            url: format!("http://{addr}"),
+            access_token: "t".into(),
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert!(report.ok, "{report:?}");
+        assert_eq!(
+            outcomes(&report),
+            [
+                (DiagnosticStepKind::Url, StepOutcome::Ok),
+                (DiagnosticStepKind::Dns, StepOutcome::Ok),
+                (DiagnosticStepKind::Tcp, StepOutcome::Ok),
+                (DiagnosticStepKind::Proxy, StepOutcome::Skipped),
+                (DiagnosticStepKind::Tls, StepOutcome::Skipped),
+                (DiagnosticStepKind::Rpc, StepOutcome::Ok),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_invalid_url() {
+        let s = Settings {
+            url: "ftp://example.com".into(),
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert!(!report.ok);
+        assert_eq!(report.steps[0].outcome, StepOutcome::Failed);
+        assert!(report.steps[0].detail.contains("ftp"));
+        assert!(
+            report.steps[1..]
+                .iter()
+                .all(|s| s.outcome == StepOutcome::Skipped)
+        );
+        assert_eq!(report.steps.len(), DiagnosticStepKind::ALL.len());
+    }
+
+    #[tokio::test]
+    async fn reports_refused_tcp_connection() {
+        let closed = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+        let s = Settings {
+            url: format!("http://{closed}"),
+            access_token: "t".into(),
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert_eq!(
+            outcomes(&report)[..3],
+            [
+                (DiagnosticStepKind::Url, StepOutcome::Ok),
+                (DiagnosticStepKind::Dns, StepOutcome::Ok),
+                (DiagnosticStepKind::Tcp, StepOutcome::Failed),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_credentials_at_rpc_step() {
+        let addr = test_server::spawn_plaintext(ok_server()).await;
+        let s = Settings {
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert!(!report.ok);
+        let rpc = report.steps.last().unwrap();
+        assert_eq!(rpc.step, DiagnosticStepKind::Rpc);
+        assert_eq!(rpc.outcome, StepOutcome::Failed);
+        assert!(rpc.detail.contains("Unauthenticated"), "{}", rpc.detail);
+    }
+
+    #[tokio::test]
+    async fn tunnels_through_proxy_and_checks_tls() {
+        let pki = TestPki::generate();
+        let addr =
+            test_server::spawn_tls(ok_server(), &pki.server_cert_pem, &pki.server_key_pem).await;
+        let proxy_addr = test_server::spawn_connect_proxy().await;
+        let s = Settings {
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".into(),
+            use_proxies: true,
+            proxy_url: format!("http://{proxy_addr}"),
+            ca_bundle_pem: pki.ca_pem.clone(),
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert!(report.ok, "{report:?}");
+        let tls = &report.steps[4];
+        assert_eq!(tls.step, DiagnosticStepKind::Tls);
+        assert!(tls.detail.contains("ALPN h2"), "{}", tls.detail);
+    }
+
+    #[tokio::test]
+    async fn reports_untrusted_server_certificate_at_tls_step() {
+        let pki = TestPki::generate();
+        let addr =
+            test_server::spawn_tls(ok_server(), &pki.server_cert_pem, &pki.server_key_pem).await;
+        let s = Settings {
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".into(),
+            ..Settings::default()
+        };
+
+        let report = test_connection(&s, None).await;
+        assert_eq!(
+            outcomes(&report)[4..],
+            [
+                (DiagnosticStepKind::Tls, StepOutcome::Failed),
+                (DiagnosticStepKind::Rpc, StepOutcome::Skipped),
+            ]
+        );
+    }
+}
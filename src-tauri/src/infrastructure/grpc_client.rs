@@ -1,19 +1,40 @@
+use std::error::Error as _;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use http::uri::InvalidUri;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use http::{
+    HeaderValue, Uri,
+    header::PROXY_AUTHORIZATION,
+    uri::{InvalidUri, PathAndQuery},
+};
 use hyper_http_proxy::{Intercept, Proxy, ProxyConnector};
-use hyper_util::client::legacy::connect::HttpConnector;
-use rustls::pki_types::InvalidDnsNameError;
-use tempgrpcd_protos::tempgrpcd::v1::tempgrpcd_service_client::TempgrpcdServiceClient;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::{HttpConnector, proxy::SocksV5};
+use rustls::{
+    RootCertStore,
+    pki_types::{CertificateDer, InvalidDnsNameError, pem::PemObject as _},
+};
+use tempgrpcd_protos::tempgrpcd::v1::{
+    GetAmbientConditionsRequest, tempgrpcd_service_client::TempgrpcdServiceClient,
+};
 use tonic::{
-    Request, Status,
-    metadata::{MetadataValue, errors::InvalidMetadataValue},
+    Request, Status, TimeoutExpired,
+    client::Grpc,
+    metadata::{
+        Ascii, MetadataKey, MetadataValue,
+        errors::{InvalidMetadataKey, InvalidMetadataValue},
+    },
     service::{Interceptor, interceptor::InterceptedService},
-    transport::{Channel, ClientTlsConfig, Error as TransportError},
+    transport::{Channel, ClientTlsConfig, Endpoint, Error as TransportError, Identity},
 };
 use url::Url;
 
-use crate::domain::settings::Settings;
+use crate::domain::client_certificate::ClientIdentity;
+use crate::domain::settings::{AuthMode, Settings};
+use crate::infrastructure::raw_codec::RawResponseCodec;
 
 #[derive(Debug, thiserror::Error)]
 pub enum GrpcClientError {
@@ -27,69 +48,398 @@ pub enum GrpcClientError {
     Transport(#[from] TransportError),
     #[error("invalid authorization token: {0}")]
     InvalidAuthToken(#[from] InvalidMetadataValue),
+    #[error("invalid authorization header name: {0}")]
+    InvalidAuthHeaderName(#[from] InvalidMetadataKey),
+    #[error("unsupported URL scheme: {0} (expected http or https)")]
+    UnsupportedScheme(String),
+    #[error("unsupported proxy scheme: {0} (expected http, https, socks5 or socks5h)")]
+    UnsupportedProxyScheme(String),
+    #[error("proxy TLS configuration error: {0}")]
+    ProxyTls(#[from] rustls::Error),
+    #[error("failed to read CA bundle {path}: {source}")]
+    CaBundleRead {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid CA bundle: {0}")]
+    InvalidCaBundle(String),
+    #[error("no trusted root certificates (webpki roots disabled and CA bundle empty)")]
+    NoTrustAnchors,
+    #[error("timed out connecting after {0:?}")]
+    ConnectTimeout(Duration),
 }
 
-/// トークンを保持するだけのシンプルな struct
+impl GrpcClientError {
+    /// `connect_timeout_ms` 以内に接続できなかったかどうか
+    pub fn is_timeout(&self) -> bool {
+        if matches!(self, GrpcClientError::ConnectTimeout(_)) {
+            return true;
+        }
+        let mut source = self.source();
+        while let Some(err) = source {
+            if let Some(io) = err.downcast_ref::<std::io::Error>()
+                && io.kind() == std::io::ErrorKind::TimedOut
+            {
+                return true;
+            }
+            source = err.source();
+        }
+        false
+    }
+}
+
+/// RPC が制限時間を超えたかどうか。
+/// クライアント側の `request_timeout_ms` は `Cancelled` として、サーバー側の判定は
+/// `DeadlineExceeded` として返ってくるので両方を見る
+pub fn is_deadline_exceeded(status: &Status) -> bool {
+    if status.code() == tonic::Code::DeadlineExceeded {
+        return true;
+    }
+    let mut source = status.source();
+    while let Some(err) = source {
+        if err.is::<TimeoutExpired>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// 認証ヘッダーを付けるチャネル。tempgrpcd 以外のサービス（リフレクションなど）にも使える
+pub type AuthenticatedChannel = InterceptedService<Channel, AuthInterceptor>;
+
+/// 認証ヘッダー付きの tempgrpcd クライアント
+pub type GrpcClient = TempgrpcdServiceClient<AuthenticatedChannel>;
+
+/// SOCKS プロキシ URL にポートがない場合の既定ポート
+pub const DEFAULT_SOCKS_PORT: u16 = 1080;
+
+/// 認証ヘッダーを保持するだけのシンプルな struct（認証不要の場合は何も付けない）
 #[derive(Clone)]
 pub struct AuthInterceptor {
-    token: MetadataValue<tonic::metadata::Ascii>,
+    header: Option<(MetadataKey<Ascii>, MetadataValue<Ascii>)>,
+}
+
+impl AuthInterceptor {
+    /// 設定の認証方式に従ってヘッダーを組み立てる
+    pub fn from_settings(settings: &Settings) -> Result<Self, GrpcClientError> {
+        let header = match settings.auth_mode {
+            AuthMode::None => None,
+            AuthMode::Bearer => Some((
+                MetadataKey::from_static("authorization"),
+                MetadataValue::from_str(&format!("Bearer {}", &settings.access_token))?,
+            )),
+            AuthMode::Custom => {
+                let key = MetadataKey::from_bytes(settings.auth_header_name.as_bytes())?;
+                let value = if settings.auth_header_prefix.is_empty() {
+                    settings.access_token.clone()
+                } else {
+                    format!("{} {}", settings.auth_header_prefix, settings.access_token)
+                };
+                Some((key, MetadataValue::from_str(&value)?))
+            }
+        };
+        Ok(Self { header })
+    }
 }
 
 impl Interceptor for AuthInterceptor {
     fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
-        req.metadata_mut()
-            .insert("authorization", self.token.clone());
+        if let Some((key, value)) = &self.header {
+            req.metadata_mut().insert(key.clone(), value.clone());
+        }
         Ok(req)
     }
 }
-// TODO: Handle HTTP URL scheme, not just HTTPS
-// TODO: Handle cases where authentication is not required
-// TODO: Handle non-HTTP proxy
-/// Creates a new gRPC client with authentication.
+/// Creates a new gRPC client, authenticating according to `settings.auth_mode`.
+///
+/// `http://` endpoints are reached over plaintext HTTP/2 (h2c), `https://` endpoints over TLS.
+/// When `identity` is given it is presented as the TLS client certificate (mTLS).
 pub async fn new(
     settings: &Settings,
-) -> Result<TempgrpcdServiceClient<InterceptedService<Channel, AuthInterceptor>>, GrpcClientError> {
+    identity: Option<&ClientIdentity>,
+) -> Result<GrpcClient, GrpcClientError> {
+    let channel = connect_authenticated(settings, identity).await?;
+    Ok(TempgrpcdServiceClient::new(channel))
+}
+
+/// `new` と同じ手順で接続し、クライアントに包む前のチャネルを返す
+pub async fn connect_authenticated(
+    settings: &Settings,
+    identity: Option<&ClientIdentity>,
+) -> Result<AuthenticatedChannel, GrpcClientError> {
     let url = Url::parse(&settings.url)?;
-    let tls_config = ClientTlsConfig::new()
-        .with_enabled_roots()
-        .domain_name(url.host_str().unwrap_or(""));
-
-    let endpoint = Channel::from_shared(url.to_string())?.tls_config(tls_config)?;
-
-    let channel = if settings.use_proxies {
-        let proxy = {
-            let proxy_uri = settings.proxy_url.parse()?;
-            let mut proxy = Proxy::new(Intercept::All, proxy_uri);
-            let connector = HttpConnector::new();
-            proxy.force_connect();
-            ProxyConnector::from_proxy_unsecured(connector, proxy)
-        };
-        endpoint.connect_with_connector(proxy).await.map_err(|e| {
-            eprintln!("Failed to connect via proxy: {e:?}");
-            GrpcClientError::Transport(e)
-        })?
+    // ネットワークに出る前に認証ヘッダーを検証しておく
+    let interceptor = AuthInterceptor::from_settings(settings)?;
+
+    let roots = root_store(settings)?;
+    let endpoint = endpoint_for(&url, settings, &roots, identity)?;
+
+    let connecting = async {
+        if settings.use_proxies {
+            connect_via_proxy(endpoint, settings, roots).await
+        } else {
+            Ok(endpoint.connect().await?)
+        }
+    };
+    // tonic の connect_timeout は直接接続だと TCP までしか効かないので、TLS を含めた全体を区切る
+    let channel = if settings.connect_timeout_ms > 0 {
+        let limit = millis(settings.connect_timeout_ms);
+        tokio::time::timeout(limit, connecting)
+            .await
+            .map_err(|_| GrpcClientError::ConnectTimeout(limit))??
     } else {
-        endpoint.connect().await?
+        connecting.await?
     };
 
-    let interceptor = AuthInterceptor {
-        token: MetadataValue::from_str(&format!("Bearer {}", &settings.access_token))?,
+    Ok(InterceptedService::new(channel, interceptor))
+}
+
+const GET_AMBIENT_CONDITIONS_PATH: &str = "/tempgrpcd.v1.TempgrpcdService/GetAmbientConditions";
+
+/// `GetAmbientConditions` を呼び、応答をデコードせずに protobuf のバイト列のまま返す。
+/// 応答をそのままフロントエンドへ渡すときに、デコードと再エンコードを省くために使う
+pub async fn get_ambient_conditions_raw(
+    channel: AuthenticatedChannel,
+    request: GetAmbientConditionsRequest,
+) -> Result<Bytes, Status> {
+    let mut grpc = Grpc::new(channel);
+    grpc.ready()
+        .await
+        .map_err(|e| Status::unknown(format!("Service was not ready: {e}")))?;
+    let response = grpc
+        .unary(
+            Request::new(request),
+            PathAndQuery::from_static(GET_AMBIENT_CONDITIONS_PATH),
+            RawResponseCodec::default(),
+        )
+        .await?;
+    Ok(response.into_inner())
+}
+
+/// URL のスキームに応じて平文 (h2c) または TLS のエンドポイントを組み立てる。
+/// TLS の場合は `roots` に含まれる証明書だけを信頼し、`identity` があればクライアント証明書として提示する
+fn endpoint_for(
+    url: &Url,
+    settings: &Settings,
+    roots: &RootCertStore,
+    identity: Option<&ClientIdentity>,
+) -> Result<Endpoint, GrpcClientError> {
+    let endpoint = apply_timeouts(Channel::from_shared(url.to_string())?, settings);
+    match url.scheme() {
+        "http" => Ok(endpoint),
+        "https" => {
+            if roots.is_empty() {
+                return Err(GrpcClientError::NoTrustAnchors);
+            }
+            let mut tls_config = ClientTlsConfig::new()
+                .trust_anchors(roots.roots.clone())
+                .domain_name(url.host_str().unwrap_or(""));
+            if let Some(identity) = identity {
+                tls_config =
+                    tls_config.identity(Identity::from_pem(&identity.cert_pem, &identity.key_pem));
+            }
+            Ok(endpoint.tls_config(tls_config)?)
+        }
+        other => Err(GrpcClientError::UnsupportedScheme(other.to_string())),
+    }
+}
+
+/// 設定のタイムアウトと HTTP/2 keepalive を適用する。0 の項目は tonic の既定（無制限・無効）のまま
+fn apply_timeouts(mut endpoint: Endpoint, settings: &Settings) -> Endpoint {
+    if settings.connect_timeout_ms > 0 {
+        endpoint = endpoint.connect_timeout(millis(settings.connect_timeout_ms));
+    }
+    if settings.request_timeout_ms > 0 {
+        endpoint = endpoint.timeout(millis(settings.request_timeout_ms));
+    }
+    if settings.keepalive_interval_ms > 0 {
+        endpoint = endpoint
+            .http2_keep_alive_interval(millis(settings.keepalive_interval_ms))
+            .keep_alive_while_idle(settings.keepalive_while_idle);
+        if settings.keepalive_timeout_ms > 0 {
+            endpoint = endpoint.keep_alive_timeout(millis(settings.keepalive_timeout_ms));
+        }
+    }
+    endpoint
+}
+
+fn millis(ms: u32) -> Duration {
+    Duration::from_millis(ms.into())
+}
+
+/// プロキシ URL のスキームに応じたコネクタ（HTTP/HTTPS CONNECT・SOCKS5）で接続する。
+/// `proxy_roots` は HTTPS プロキシの証明書検証に使う
+async fn connect_via_proxy(
+    endpoint: Endpoint,
+    settings: &Settings,
+    proxy_roots: RootCertStore,
+) -> Result<Channel, GrpcClientError> {
+    let proxy_uri: Uri = settings.proxy_url.parse()?;
+    let channel = match proxy_uri.scheme_str() {
+        None | Some("http") => {
+            let connector = http_proxy_connector(HttpConnector::new(), proxy_uri, settings);
+            endpoint.connect_with_connector(connector).await
+        }
+        Some("https") => {
+            // プロキシまでの区間を TLS で保護する（宛先との TLS は tonic が別途行う）
+            let connector =
+                http_proxy_connector(https_proxy_transport(proxy_roots)?, proxy_uri, settings);
+            endpoint.connect_with_connector(connector).await
+        }
+        Some(scheme @ ("socks5" | "socks5h")) => {
+            // socks5 は名前解決をローカルで、socks5h はプロキシ側で行う
+            let connector = socks5_connector(settings, scheme == "socks5")?;
+            endpoint.connect_with_connector(connector).await
+        }
+        Some(other) => return Err(GrpcClientError::UnsupportedProxyScheme(other.to_string())),
+    };
+    channel.map_err(|e| {
+        eprintln!("Failed to connect via proxy: {e:?}");
+        GrpcClientError::Transport(e)
+    })
+}
+
+/// 常に CONNECT でトンネルを張る HTTP プロキシコネクタ。
+/// ユーザー名が設定されていれば `Proxy-Authorization: Basic` を付ける
+pub fn http_proxy_connector<C>(
+    connector: C,
+    proxy_uri: Uri,
+    settings: &Settings,
+) -> ProxyConnector<C> {
+    let mut proxy = Proxy::new(Intercept::All, proxy_uri);
+    proxy.force_connect();
+    if !settings.proxy_username.is_empty() {
+        let credentials = format!(
+            "{}:{}",
+            settings.proxy_username,
+            settings.proxy_password.as_deref().unwrap_or("")
+        );
+        let mut value = HeaderValue::from_str(&format!("Basic {}", STANDARD.encode(credentials)))
+            .expect("base64 is always a valid header value");
+        value.set_sensitive(true);
+        proxy.set_header(PROXY_AUTHORIZATION, value);
+    }
+    ProxyConnector::from_proxy_unsecured(connector, proxy)
+}
+
+/// HTTPS プロキシまでの TLS 接続に使うコネクタ
+pub fn https_proxy_transport(
+    proxy_roots: RootCertStore,
+) -> Result<HttpsConnector<HttpConnector>, GrpcClientError> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    Ok(HttpsConnectorBuilder::new()
+        .with_tls_config(proxy_tls_config(proxy_roots)?)
+        .https_only()
+        .enable_http1()
+        .wrap_connector(http))
+}
+
+/// HTTPS プロキシとの TLS 接続に使うクライアント設定
+fn proxy_tls_config(roots: RootCertStore) -> Result<rustls::ClientConfig, GrpcClientError> {
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(config)
+}
+
+/// webpki の既定ルート証明書だけを含むストア
+fn webpki_root_store() -> RootCertStore {
+    RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    }
+}
+
+/// 設定に応じた信頼アンカー。webpki の既定ルート（無効化されていなければ）に
+/// CA バンドルの証明書を加える。サーバーと HTTPS プロキシの検証の両方に使う
+pub fn root_store(settings: &Settings) -> Result<RootCertStore, GrpcClientError> {
+    let mut roots = if settings.disable_webpki_roots {
+        RootCertStore::empty()
+    } else {
+        webpki_root_store()
     };
-    let client = TempgrpcdServiceClient::with_interceptor(channel, interceptor);
+    for cert in ca_bundle_certificates(settings)? {
+        roots
+            .add(cert)
+            .map_err(|e| GrpcClientError::InvalidCaBundle(e.to_string()))?;
+    }
+    Ok(roots)
+}
+
+/// 貼り付けられた PEM とファイルの PEM から証明書を取り出す。
+/// どちらも空なら空の Vec を返し、中身があるのに証明書が一つもなければエラーにする
+fn ca_bundle_certificates(
+    settings: &Settings,
+) -> Result<Vec<CertificateDer<'static>>, GrpcClientError> {
+    let mut pem = settings.ca_bundle_pem.clone();
+    if !settings.ca_bundle_path.is_empty() {
+        let from_file = std::fs::read_to_string(&settings.ca_bundle_path).map_err(|source| {
+            GrpcClientError::CaBundleRead {
+                path: settings.ca_bundle_path.clone(),
+                source,
+            }
+        })?;
+        pem.push('\n');
+        pem.push_str(&from_file);
+    }
+    if pem.trim().is_empty() {
+        return Ok(Vec::new());
+    }
 
-    Ok(client)
+    let certs = CertificateDer::pem_slice_iter(pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| GrpcClientError::InvalidCaBundle(e.to_string()))?;
+    if certs.is_empty() {
+        return Err(GrpcClientError::InvalidCaBundle(
+            "no PEM certificates found".to_string(),
+        ));
+    }
+    Ok(certs)
+}
+
+/// `socks5://host[:port]` から SOCKS5 コネクタを組み立てる。
+/// 資格情報は HTTP プロキシと同じく、設定に（暗号化して）保存されたプロキシの資格情報だけを使う
+pub fn socks5_connector(
+    settings: &Settings,
+    local_dns: bool,
+) -> Result<SocksV5<HttpConnector>, GrpcClientError> {
+    let url = Url::parse(&settings.proxy_url)?;
+    let host = url.host_str().ok_or(url::ParseError::EmptyHost)?;
+    let port = url.port().unwrap_or(DEFAULT_SOCKS_PORT);
+    // 内側の HttpConnector はプロキシまでの TCP 接続にだけ使う
+    let proxy_dst: Uri = format!("http://{host}:{port}").parse()?;
+
+    let connector = SocksV5::new(proxy_dst, HttpConnector::new()).local_dns(local_dns);
+    if settings.proxy_username.is_empty() {
+        return Ok(connector);
+    }
+    Ok(connector.with_auth(
+        settings.proxy_username.clone(),
+        settings.proxy_password.clone().unwrap_or_default(),
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::domain::settings::Settings;
-    use tonic::Request;
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd, TestPki};
+    use prost::Message as _;
+    use std::collections::HashMap;
+    use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
 
     #[test]
     fn auth_interceptor_inserts_header() {
-        let token = MetadataValue::from_str("Bearer abc").expect("ok");
-        let mut interceptor = AuthInterceptor { token };
+        let s = Settings {
+            access_token: "abc".to_string(),
+            ..Settings::default()
+        };
+        let mut interceptor = AuthInterceptor::from_settings(&s).expect("ok");
         let req = Request::new(());
         let req = interceptor.call(req).expect("call ok");
         let meta = req.metadata();
@@ -98,6 +448,51 @@ mod tests {
         assert_eq!(v, "Bearer abc");
     }
 
+    #[test]
+    fn auth_interceptor_sends_nothing_when_auth_disabled() {
+        let s = Settings {
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+        let mut interceptor = AuthInterceptor::from_settings(&s).expect("ok");
+        let req = interceptor.call(Request::new(())).expect("call ok");
+        assert!(req.metadata().is_empty());
+    }
+
+    #[test]
+    fn auth_interceptor_uses_custom_header_and_prefix() {
+        let s = Settings {
+            access_token: "abc".to_string(),
+            auth_mode: AuthMode::Custom,
+            auth_header_name: "X-Api-Key".to_string(),
+            auth_header_prefix: "Token".to_string(),
+            ..Settings::default()
+        };
+        let mut interceptor = AuthInterceptor::from_settings(&s).expect("ok");
+        let req = interceptor.call(Request::new(())).expect("call ok");
+        let meta = req.metadata();
+        assert!(meta.get("authorization").is_none());
+        assert_eq!(
+            meta.get("x-api-key").unwrap().to_str().unwrap(),
+            "Token abc"
+        );
+    }
+
+    #[test]
+    fn auth_interceptor_rejects_invalid_custom_header_name() {
+        let s = Settings {
+            access_token: "abc".to_string(),
+            auth_mode: AuthMode::Custom,
+            auth_header_name: "bad header".to_string(),
+            ..Settings::default()
+        };
+        let res = AuthInterceptor::from_settings(&s);
+        assert!(matches!(
+            res,
+            Err(GrpcClientError::InvalidAuthHeaderName(_))
+        ));
+    }
+
     #[tokio::test]
     async fn new_rejects_invalid_url() {
         let s = Settings {
@@ -106,9 +501,10 @@ mod tests {
             access_token: "t".to_string(),
             use_proxies: false,
             proxy_url: "".to_string(),
+            ..Settings::default()
         };
 
-        let res = new(&s).await;
+        let res = new(&s, None).await;
         assert!(matches!(res, Err(GrpcClientError::InvalidUrl(_))));
     }
 
@@ -121,9 +517,10 @@ mod tests {
             access_token: "bad\0token".to_string(),
             use_proxies: false,
             proxy_url: "".to_string(),
+            ..Settings::default()
         };
 
-        let res = new(&s).await;
+        let res = new(&s, None).await;
         assert!(matches!(res, Err(GrpcClientError::InvalidAuthToken(_))));
     }
 
@@ -135,13 +532,480 @@ mod tests {
             access_token: "t".to_string(),
             use_proxies: true,
             proxy_url: "not-a-uri".to_string(),
+            ..Settings::default()
         };
 
-        let res = new(&s).await;
+        let res = new(&s, None).await;
         // proxy parse/connect should fail; accept either InvalidUri or Transport
         assert!(
             matches!(res, Err(GrpcClientError::InvalidUri(_)))
                 || matches!(res, Err(GrpcClientError::Transport(_)))
         );
     }
+
+    #[tokio::test]
+    async fn new_rejects_unsupported_scheme() {
+        let s = Settings {
+            id: 1,
+            url: "ftp://example.com".to_string(),
+            access_token: "t".to_string(),
+            use_proxies: false,
+            proxy_url: "".to_string(),
+            ..Settings::default()
+        };
+
+        let res = new(&s, None).await;
+        assert!(matches!(res, Err(GrpcClientError::UnsupportedScheme(ref s)) if s == "ftp"));
+    }
+
+    fn sample_response() -> GetAmbientConditionsResponse {
+        let mut ambient_conditions = HashMap::new();
+        ambient_conditions.insert(
+            "1700000000000-0".to_string(),
+            AmbientCondition {
+                temperature: 21.5,
+                humidity: 40.0,
+                illumination: 120.0,
+            },
+        );
+        GetAmbientConditionsResponse { ambient_conditions }
+    }
+
+    async fn fetch_once(client: &mut GrpcClient) -> GetAmbientConditionsResponse {
+        client
+            .get_ambient_conditions(Request::new(GetAmbientConditionsRequest {
+                start_time: None,
+                end_time: None,
+                samples: Some(10),
+            }))
+            .await
+            .expect("rpc ok")
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn new_connects_over_plaintext_http() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            use_proxies: false,
+            proxy_url: "".to_string(),
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect over h2c");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn raw_call_returns_undecoded_response_bytes() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let s = Settings {
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+
+        let channel = connect_authenticated(&s, None).await.expect("connect");
+        let bytes = get_ambient_conditions_raw(
+            channel,
+            GetAmbientConditionsRequest {
+                start_time: None,
+                end_time: None,
+                samples: Some(10),
+            },
+        )
+        .await
+        .expect("rpc ok");
+        assert_eq!(bytes, sample_response().encode_to_vec());
+        assert_eq!(
+            GetAmbientConditionsResponse::decode(bytes).expect("decode"),
+            sample_response()
+        );
+    }
+
+    #[tokio::test]
+    async fn new_connects_over_plaintext_http_through_proxy() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let proxy_addr = test_server::spawn_connect_proxy().await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("http://{proxy_addr}"),
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect over h2c via proxy");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn new_sends_no_auth_header_when_auth_disabled() {
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(|req| {
+            if req.metadata().get("authorization").is_some() {
+                return Err(tonic::Status::invalid_argument("unexpected auth header"));
+            }
+            Ok(sample_response())
+        }))
+        .await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect without auth");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn new_connects_through_socks5_proxy() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let proxy = test_server::spawn_socks5_proxy(None).await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("socks5://{}", proxy.addr),
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect via socks5");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn new_connects_through_socks5h_proxy_with_credentials() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let proxy = test_server::spawn_socks5_proxy(Some(("alice", "p@ss"))).await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("socks5h://{}", proxy.addr),
+            proxy_username: "alice".to_string(),
+            proxy_password: Some("p@ss".to_string()),
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect via socks5h");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+        // socks5h では名前解決をプロキシに任せるのでホスト名がそのまま届く
+        assert_eq!(proxy.targets.lock().unwrap().as_slice(), ["localhost"]);
+    }
+
+    #[tokio::test]
+    async fn new_fails_when_socks5_credentials_are_rejected() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let proxy = test_server::spawn_socks5_proxy(Some(("alice", "secret"))).await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("socks5://{}", proxy.addr),
+            proxy_username: "alice".to_string(),
+            proxy_password: Some("wrong".to_string()),
+            ..Settings::default()
+        };
+
+        let res = new(&s, None).await;
+        assert!(matches!(res, Err(GrpcClientError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn new_rejects_unsupported_proxy_scheme() {
+        let s = Settings {
+            id: 1,
+            url: "http://127.0.0.1:1".to_string(),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: "ftp://proxy.example.com".to_string(),
+            ..Settings::default()
+        };
+
+        let res = new(&s, None).await;
+        assert!(matches!(res, Err(GrpcClientError::UnsupportedProxyScheme(ref s)) if s == "ftp"));
+    }
+
+    #[tokio::test]
+    async fn connect_via_https_proxy_sends_basic_credentials() {
+        let addr =
+            test_server::spawn_plaintext(FakeTempgrpcd::new(|_| Ok(sample_response()))).await;
+        let pki = TestPki::generate();
+        let proxy_addr =
+            test_server::spawn_tls_connect_proxy(pki.server_config(), Some("alice:s3cret")).await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("https://localhost:{}", proxy_addr.port()),
+            proxy_username: "alice".to_string(),
+            proxy_password: Some("s3cret".to_string()),
+            ..Settings::default()
+        };
+
+        let endpoint = endpoint_for(&Url::parse(&s.url).unwrap(), &s, &pki.root_store(), None)
+            .expect("endpoint");
+        let channel = connect_via_proxy(endpoint, &s, pki.root_store())
+            .await
+            .expect("connect via https proxy");
+        let mut client = TempgrpcdServiceClient::with_interceptor(
+            channel,
+            AuthInterceptor::from_settings(&s).unwrap(),
+        );
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn connect_via_https_proxy_fails_with_wrong_credentials() {
+        let pki = TestPki::generate();
+        let proxy_addr =
+            test_server::spawn_tls_connect_proxy(pki.server_config(), Some("alice:s3cret")).await;
+        let s = Settings {
+            id: 1,
+            url: "http://127.0.0.1:1".to_string(),
+            use_proxies: true,
+            proxy_url: format!("https://localhost:{}", proxy_addr.port()),
+            proxy_username: "alice".to_string(),
+            proxy_password: Some("wrong".to_string()),
+            ..Settings::default()
+        };
+
+        let endpoint = endpoint_for(&Url::parse(&s.url).unwrap(), &s, &pki.root_store(), None)
+            .expect("endpoint");
+        let res = connect_via_proxy(endpoint, &s, pki.root_store()).await;
+        assert!(matches!(res, Err(GrpcClientError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn new_rejects_https_proxy_with_untrusted_certificate() {
+        let pki = TestPki::generate();
+        let proxy_addr = test_server::spawn_tls_connect_proxy(pki.server_config(), None).await;
+        let s = Settings {
+            id: 1,
+            url: "http://127.0.0.1:1".to_string(),
+            access_token: "t".to_string(),
+            use_proxies: true,
+            proxy_url: format!("https://localhost:{}", proxy_addr.port()),
+            ..Settings::default()
+        };
+
+        // テスト用 CA は webpki のルートに含まれないので TLS ハンドシェイクで失敗する
+        let res = new(&s, None).await;
+        assert!(matches!(res, Err(GrpcClientError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn new_trusts_custom_ca_bundle() {
+        let pki = TestPki::generate();
+        let addr = test_server::spawn_tls(
+            FakeTempgrpcd::new(|_| Ok(sample_response())),
+            &pki.server_cert_pem,
+            &pki.server_key_pem,
+        )
+        .await;
+        let s = Settings {
+            id: 1,
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            ca_bundle_pem: pki.ca_pem.clone(),
+            disable_webpki_roots: true,
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect with custom CA");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn new_loads_ca_bundle_from_file_and_trusts_self_signed_certificate() {
+        let (cert_pem, key_pem) = TestPki::self_signed_localhost();
+        let addr = test_server::spawn_tls(
+            FakeTempgrpcd::new(|_| Ok(sample_response())),
+            &cert_pem,
+            &key_pem,
+        )
+        .await;
+        let bundle = tempfile::NamedTempFile::new().expect("temp file");
+        std::fs::write(bundle.path(), &cert_pem).expect("write bundle");
+        let s = Settings {
+            id: 1,
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            ca_bundle_path: bundle.path().to_str().unwrap().to_string(),
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect with self-signed cert");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+    }
+
+    #[tokio::test]
+    async fn new_rejects_server_signed_by_unknown_ca() {
+        let pki = TestPki::generate();
+        let addr = test_server::spawn_tls(
+            FakeTempgrpcd::new(|_| Ok(sample_response())),
+            &pki.server_cert_pem,
+            &pki.server_key_pem,
+        )
+        .await;
+        let s = Settings {
+            id: 1,
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            ..Settings::default()
+        };
+
+        let res = new(&s, None).await;
+        assert!(matches!(res, Err(GrpcClientError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn new_rejects_missing_or_invalid_ca_bundle() {
+        let base = Settings {
+            id: 1,
+            url: "https://localhost:1".to_string(),
+            access_token: "t".to_string(),
+            ..Settings::default()
+        };
+
+        let res = new(
+            &Settings {
+                ca_bundle_path: "/nonexistent/roomtemp-ca.pem".to_string(),
+                ..base.clone()
+            },
+            None,
+        )
+        .await;
+        assert!(matches!(res, Err(GrpcClientError::CaBundleRead { .. })));
+
+        let res = new(
+            &Settings {
+                ca_bundle_pem: "not a certificate".to_string(),
+                ..base.clone()
+            },
+            None,
+        )
+        .await;
+        assert!(matches!(res, Err(GrpcClientError::InvalidCaBundle(_))));
+
+        let res = new(
+            &Settings {
+                disable_webpki_roots: true,
+                ..base
+            },
+            None,
+        )
+        .await;
+        assert!(matches!(res, Err(GrpcClientError::NoTrustAnchors)));
+    }
+
+    #[tokio::test]
+    async fn new_times_out_when_tls_handshake_stalls() {
+        let addr = test_server::spawn_silent().await;
+        let s = Settings {
+            id: 1,
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            connect_timeout_ms: 100,
+            ..Settings::default()
+        };
+
+        let res = tokio::time::timeout(std::time::Duration::from_secs(5), new(&s, None))
+            .await
+            .expect("connect timeout should fire before the test timeout");
+        match res {
+            Err(e) => assert!(e.is_timeout(), "{e:?}"),
+            Ok(_) => panic!("connected to a silent server"),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_timeout_surfaces_as_deadline_exceeded() {
+        let addr = test_server::spawn_plaintext(
+            FakeTempgrpcd::new(|_| Ok(sample_response()))
+                .with_delay(std::time::Duration::from_millis(500)),
+        )
+        .await;
+        let s = Settings {
+            id: 1,
+            url: format!("http://{addr}"),
+            access_token: "t".to_string(),
+            request_timeout_ms: 50,
+            ..Settings::default()
+        };
+
+        let mut client = new(&s, None).await.expect("connect");
+        let status = client
+            .get_ambient_conditions(Request::new(GetAmbientConditionsRequest {
+                start_time: None,
+                end_time: None,
+                samples: Some(10),
+            }))
+            .await
+            .expect_err("request should time out");
+        assert!(is_deadline_exceeded(&status), "{status:?}");
+        assert!(!is_deadline_exceeded(&tonic::Status::unavailable("down")));
+    }
+
+    #[tokio::test]
+    async fn new_presents_client_certificate_for_mtls() {
+        let pki = TestPki::generate();
+        let addr =
+            test_server::spawn_mtls(FakeTempgrpcd::new(|_| Ok(sample_response())), &pki).await;
+        let s = Settings {
+            id: 1,
+            url: format!("https://localhost:{}", addr.port()),
+            access_token: "t".to_string(),
+            ca_bundle_pem: pki.ca_pem.clone(),
+            ..Settings::default()
+        };
+        let identity = ClientIdentity {
+            cert_pem: pki.client_cert_pem.clone(),
+            key_pem: pki.client_key_pem.clone(),
+        };
+
+        let mut client = new(&s, Some(&identity))
+            .await
+            .expect("connect with client certificate");
+        let resp = fetch_once(&mut client).await;
+        assert_eq!(resp, sample_response());
+
+        // クライアント証明書がなければハンドシェイクか最初の RPC で拒否される
+        let rejected = match new(&s, None).await {
+            Err(_) => true,
+            Ok(mut client) => client
+                .get_ambient_conditions(Request::new(GetAmbientConditionsRequest {
+                    start_time: None,
+                    end_time: None,
+                    samples: Some(10),
+                }))
+                .await
+                .is_err(),
+        };
+        assert!(rejected);
+    }
 }
@@ -0,0 +1,256 @@
+//! 接続中はサーバーに新しい測定値を定期的に問い合わせ、前回から増えた分だけを知らせる
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost::Message as _;
+use tempgrpcd_protos::tempgrpcd::v1::{GetAmbientConditionsRequest, GetAmbientConditionsResponse};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tonic::Status;
+
+use crate::domain::ambient::{self, AmbientReading, AmbientUpdate};
+use crate::domain::connection::ConnectionState;
+use crate::domain::time_range::TimeRange;
+use crate::infrastructure::connection::ConnectionManager;
+use crate::infrastructure::grpc_client::{self, AuthenticatedChannel};
+
+type UpdateListener = dyn Fn(&AmbientUpdate) + Send + Sync;
+
+struct Inner {
+    connection: ConnectionManager,
+    listener: Arc<UpdateListener>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// ライブ更新のタスクを 1 つだけ持つ入れ物。
+/// 新しい測定値が届くたびに `listener` を呼ぶ（アプリでは Tauri のイベントとして送る）
+#[derive(Clone)]
+pub struct LivePoller {
+    inner: Arc<Inner>,
+}
+
+impl LivePoller {
+    pub fn new<F>(connection: ConnectionManager, listener: F) -> Self
+    where
+        F: Fn(&AmbientUpdate) + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(Inner {
+                connection,
+                listener: Arc::new(listener),
+                task: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// 実行中のタスクを止め、`interval` ごとに問い合わせるタスクを起動し直す（ゼロなら止めるだけ）。
+    /// 問い合わせるのは接続中だけで、切断されたら再接続を待ってから前回の続きを取得する
+    pub fn restart(&self, interval: Duration) {
+        let mut task = self.inner.task.lock().unwrap();
+        if let Some(task) = task.take() {
+            task.abort();
+        }
+        if interval.is_zero() {
+            return;
+        }
+        let connection = self.inner.connection.clone();
+        let listener = self.inner.listener.clone();
+        *task = Some(tokio::spawn(async move {
+            poll_loop(connection, listener, interval).await;
+        }));
+    }
+}
+
+async fn poll_loop(
+    connection: ConnectionManager,
+    listener: Arc<UpdateListener>,
+    interval: Duration,
+) {
+    let mut states = connection.subscribe();
+    // 起動より前の測定値は get_graph_data で取得済みとみなす
+    let mut last_seen_ms = now_millis();
+    loop {
+        if states
+            .wait_for(|state| *state == ConnectionState::Connected)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        // 再接続した直後は待たずに、切断中に増えた分を取得する
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                changed = states.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    if *states.borrow_and_update() != ConnectionState::Connected {
+                        break;
+                    }
+                    continue;
+                }
+            }
+            let Some(channel) = connection.channel().await else {
+                break;
+            };
+            match poll_once(channel, last_seen_ms, now_millis()).await {
+                Ok(readings) => {
+                    if let Some(last) = readings.last() {
+                        last_seen_ms = last.recorded_at_ms;
+                        listener(&AmbientUpdate { readings });
+                    }
+                }
+                Err(status) if status.code() == tonic::Code::Unavailable => {
+                    // 再接続は ConnectionManager に任せ、接続し直すまで待つ
+                    connection
+                        .report_failure(status.message().to_string())
+                        .await;
+                    break;
+                }
+                Err(status) => eprintln!("live update: failed to poll: {status}"),
+            }
+        }
+    }
+}
+
+/// `since_ms` より後に記録された測定値を古い順に返す。
+/// 短い期間なので、サンプル数は指定せずに（間引かせずに）取得する
+async fn poll_once(
+    channel: AuthenticatedChannel,
+    since_ms: i64,
+    now_ms: i64,
+) -> Result<Vec<AmbientReading>, Status> {
+    let Ok(range) = TimeRange::from_millis(since_ms + 1, now_ms + 1) else {
+        return Ok(Vec::new());
+    };
+    let request = GetAmbientConditionsRequest {
+        start_time: Some(range.start_timestamp()),
+        end_time: Some(range.end_timestamp()),
+        samples: None,
+    };
+    let body = grpc_client::get_ambient_conditions_raw(channel, request).await?;
+    let response = GetAmbientConditionsResponse::decode(body)
+        .map_err(|e| Status::internal(format!("invalid GetAmbientConditionsResponse: {e}")))?;
+    Ok(ambient::readings_from_response(response)
+        .into_iter()
+        .filter(|reading| reading.recorded_at_ms > since_ms)
+        .collect())
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::settings::{AuthMode, Settings};
+    use crate::infrastructure::connection::BackoffPolicy;
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd};
+    use std::collections::HashMap;
+    use tempgrpcd_protos::tempgrpcd::v1::AmbientCondition;
+    use tokio::sync::mpsc;
+
+    /// 問い合わせのたびに現在時刻の測定値と、古い測定値を 1 つずつ返すサーバー
+    async fn spawn_live_server() -> String {
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(|_| {
+            let condition = AmbientCondition {
+                temperature: 21.5,
+                humidity: 40.0,
+                illumination: 120.0,
+            };
+            let mut ambient_conditions = HashMap::new();
+            ambient_conditions.insert(format!("{}-0", now_millis()), condition);
+            ambient_conditions.insert("1000-0".to_string(), condition);
+            Ok(GetAmbientConditionsResponse { ambient_conditions })
+        }))
+        .await;
+        format!("http://{addr}")
+    }
+
+    fn recording_poller(
+        connection: &ConnectionManager,
+    ) -> (LivePoller, mpsc::UnboundedReceiver<AmbientUpdate>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let poller = LivePoller::new(connection.clone(), move |update| {
+            let _ = tx.send(update.clone());
+        });
+        (poller, rx)
+    }
+
+    async fn next_update(rx: &mut mpsc::UnboundedReceiver<AmbientUpdate>) -> AmbientUpdate {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("update in time")
+            .expect("poller alive")
+    }
+
+    #[tokio::test]
+    async fn emits_only_new_readings_once_connected() {
+        let url = spawn_live_server().await;
+        let connection = ConnectionManager::new(BackoffPolicy::default(), |_| {});
+        let (poller, mut rx) = recording_poller(&connection);
+        let started_ms = now_millis();
+        poller.restart(Duration::from_millis(20));
+
+        // 接続するまでは問い合わせない
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(rx.try_recv().is_err());
+
+        connection
+            .connect(
+                Settings {
+                    url,
+                    auth_mode: AuthMode::None,
+                    ..Settings::default()
+                },
+                None,
+            )
+            .await
+            .expect("connect ok");
+
+        let mut last_seen_ms = started_ms;
+        for _ in 0..3 {
+            let update = next_update(&mut rx).await;
+            for reading in &update.readings {
+                assert!(reading.recorded_at_ms > last_seen_ms, "{reading:?}");
+                last_seen_ms = reading.recorded_at_ms;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_with_zero_interval_stops_polling() {
+        let url = spawn_live_server().await;
+        let connection = ConnectionManager::new(BackoffPolicy::default(), |_| {});
+        connection
+            .connect(
+                Settings {
+                    url,
+                    auth_mode: AuthMode::None,
+                    ..Settings::default()
+                },
+                None,
+            )
+            .await
+            .expect("connect ok");
+        let (poller, mut rx) = recording_poller(&connection);
+
+        poller.restart(Duration::from_millis(20));
+        next_update(&mut rx).await;
+        poller.restart(Duration::ZERO);
+        // 止める前に送り出された分を読み捨てる
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        while rx.try_recv().is_ok() {}
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+    }
+}
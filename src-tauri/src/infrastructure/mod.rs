@@ -1,4 +1,16 @@
+pub mod alert_evaluator;
+pub mod chunked_fetch;
+pub mod client_identity;
+pub mod connection;
 pub mod crypto;
 pub mod db;
+pub mod diagnostics;
 pub mod grpc_client;
 pub mod keystore;
+pub mod live_poller;
+pub mod raw_codec;
+pub mod reflection;
+pub mod request_registry;
+pub mod single_flight;
+#[cfg(test)]
+pub mod test_server;
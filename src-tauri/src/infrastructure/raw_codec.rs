@@ -0,0 +1,74 @@
+//! 応答をデコードせずにバイト列のまま受け取る gRPC コーデック。
+//! フロントエンドへ protobuf のまま渡す応答では、デコードして再エンコードする手間を省ける
+//!
+//! ベンチマークからも `#[path]` で読み込むので、このクレートの他のモジュールには依存しない
+use std::marker::PhantomData;
+
+use bytes::{Buf, Bytes};
+use prost::Message;
+use tonic::Status;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+
+/// リクエストは prost でエンコードし、応答は受け取ったバイト列をそのまま返すコーデック
+pub struct RawResponseCodec<T> {
+    _request: PhantomData<T>,
+}
+
+impl<T> Default for RawResponseCodec<T> {
+    fn default() -> Self {
+        Self {
+            _request: PhantomData,
+        }
+    }
+}
+
+impl<T> Codec for RawResponseCodec<T>
+where
+    T: Message + Send + 'static,
+{
+    type Encode = T;
+    type Decode = Bytes;
+    type Encoder = RequestEncoder<T>;
+    type Decoder = RawBytesDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RequestEncoder {
+            _request: PhantomData,
+        }
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawBytesDecoder
+    }
+}
+
+pub struct RequestEncoder<T> {
+    _request: PhantomData<T>,
+}
+
+impl<T: Message> Encoder for RequestEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(format!("failed to encode request: {e}")))
+    }
+}
+
+pub struct RawBytesDecoder;
+
+impl Decoder for RawBytesDecoder {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(take_message(src)))
+    }
+}
+
+/// バッファに残っている 1 メッセージ分を取り出す。
+/// tonic の受信バッファ (`BytesMut`) からは切り出すだけでコピーしない
+pub fn take_message(src: &mut impl Buf) -> Bytes {
+    src.copy_to_bytes(src.remaining())
+}
@@ -0,0 +1,228 @@
+//! gRPC サーバーリフレクションで、接続先が提供するサービスとメソッドを調べる
+use http::uri::PathAndQuery;
+use prost::Message as _;
+use prost_types::FileDescriptorProto;
+use tonic::client::Grpc;
+use tonic::{Code, Status};
+use tonic_prost::ProstCodec;
+use tonic_reflection::pb::v1::{
+    ServerReflectionRequest, ServerReflectionResponse, server_reflection_request::MessageRequest,
+    server_reflection_response::MessageResponse,
+};
+
+use crate::domain::client_certificate::ClientIdentity;
+use crate::domain::server_info::{MethodDescription, ServerDescription, ServiceDescription};
+use crate::domain::settings::Settings;
+use crate::infrastructure::grpc_client::{self, AuthenticatedChannel, GrpcClientError};
+
+/// v1 を優先し、未対応のサーバーには v1alpha で問い合わせる。
+/// 両者のメッセージは同じ形式なので v1 の型をそのまま使う
+const REFLECTION_PATHS: [&str; 2] = [
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// 一覧から除くリフレクション自身のサービス
+const REFLECTION_PACKAGE_PREFIX: &str = "grpc.reflection.";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReflectionError {
+    #[error(transparent)]
+    Client(#[from] GrpcClientError),
+    #[error("server does not offer gRPC reflection")]
+    Unsupported,
+    #[error("reflection request failed: {0}")]
+    Status(Box<Status>),
+    #[error("reflection error from server: {0}")]
+    Server(String),
+    #[error("invalid file descriptor: {0}")]
+    InvalidDescriptor(#[from] prost::DecodeError),
+    #[error("unexpected reflection response")]
+    UnexpectedResponse,
+}
+
+impl From<Status> for ReflectionError {
+    fn from(status: Status) -> Self {
+        ReflectionError::Status(Box::new(status))
+    }
+}
+
+/// 設定の接続先に問い合わせ、提供されているサービスとメソッドを返す。
+/// リフレクションが提供されていなければ `ReflectionError::Unsupported`
+pub async fn describe_server(
+    settings: &Settings,
+    identity: Option<&ClientIdentity>,
+) -> Result<ServerDescription, ReflectionError> {
+    let channel = grpc_client::connect_authenticated(settings, identity).await?;
+    let mut grpc = Grpc::new(channel);
+    for path in REFLECTION_PATHS {
+        match describe_with(&mut grpc, path).await {
+            Err(ReflectionError::Status(status)) if status.code() == Code::Unimplemented => {
+                continue;
+            }
+            result => return result,
+        }
+    }
+    Err(ReflectionError::Unsupported)
+}
+
+async fn describe_with(
+    grpc: &mut Grpc<AuthenticatedChannel>,
+    path: &'static str,
+) -> Result<ServerDescription, ReflectionError> {
+    let names = match query(grpc, path, MessageRequest::ListServices(String::new())).await? {
+        MessageResponse::ListServicesResponse(list) => list
+            .service
+            .into_iter()
+            .map(|service| service.name)
+            .filter(|name| !name.starts_with(REFLECTION_PACKAGE_PREFIX))
+            .collect::<Vec<_>>(),
+        _ => return Err(ReflectionError::UnexpectedResponse),
+    };
+
+    let mut services = Vec::with_capacity(names.len());
+    for name in names {
+        let request = MessageRequest::FileContainingSymbol(name.clone());
+        let files = match query(grpc, path, request).await? {
+            MessageResponse::FileDescriptorResponse(response) => response.file_descriptor_proto,
+            _ => return Err(ReflectionError::UnexpectedResponse),
+        };
+        services.push(describe_service(name, &files)?);
+    }
+    Ok(ServerDescription::new(services))
+}
+
+/// リクエストを 1 つだけ流すストリームで問い合わせ、最初の応答を返す
+async fn query(
+    grpc: &mut Grpc<AuthenticatedChannel>,
+    path: &'static str,
+    request: MessageRequest,
+) -> Result<MessageResponse, ReflectionError> {
+    grpc.ready()
+        .await
+        .map_err(|e| Status::unknown(format!("Service was not ready: {e}")))?;
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+    let mut responses = grpc
+        .streaming(
+            tonic::Request::new(tokio_stream::once(request)),
+            PathAndQuery::from_static(path),
+            ProstCodec::<ServerReflectionRequest, ServerReflectionResponse>::default(),
+        )
+        .await?
+        .into_inner();
+    let response = responses
+        .message()
+        .await?
+        .ok_or(ReflectionError::UnexpectedResponse)?;
+    match response.message_response {
+        Some(MessageResponse::ErrorResponse(error)) => {
+            Err(ReflectionError::Server(error.error_message))
+        }
+        Some(message) => Ok(message),
+        None => Err(ReflectionError::UnexpectedResponse),
+    }
+}
+
+/// `name` を定義しているファイル記述子からメソッドの一覧を取り出す
+fn describe_service(
+    name: String,
+    files: &[Vec<u8>],
+) -> Result<ServiceDescription, ReflectionError> {
+    for encoded in files {
+        let file = FileDescriptorProto::decode(encoded.as_slice())?;
+        let package = file.package();
+        for service in &file.service {
+            let full_name = if package.is_empty() {
+                service.name().to_string()
+            } else {
+                format!("{package}.{}", service.name())
+            };
+            if full_name != name {
+                continue;
+            }
+            let methods = service
+                .method
+                .iter()
+                .map(|method| MethodDescription {
+                    name: method.name().to_string(),
+                    input_type: method.input_type().trim_start_matches('.').to_string(),
+                    output_type: method.output_type().trim_start_matches('.').to_string(),
+                    client_streaming: method.client_streaming(),
+                    server_streaming: method.server_streaming(),
+                })
+                .collect();
+            return Ok(ServiceDescription { name, methods });
+        }
+    }
+    // 記述子が見つからなくてもサービスがあることは分かっているので、メソッドなしで返す
+    Ok(ServiceDescription {
+        name,
+        methods: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::server_info::EXPECTED_API_VERSION;
+    use crate::domain::settings::AuthMode;
+    use crate::infrastructure::test_server::{self, FakeTempgrpcd, ReflectionVersion};
+    use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsResponse;
+
+    fn fake() -> FakeTempgrpcd {
+        FakeTempgrpcd::new(|_| Ok(GetAmbientConditionsResponse::default()))
+    }
+
+    fn settings_for(addr: std::net::SocketAddr) -> Settings {
+        Settings {
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        }
+    }
+
+    fn expected_services() -> Vec<ServiceDescription> {
+        vec![ServiceDescription {
+            name: "tempgrpcd.v1.TempgrpcdService".into(),
+            methods: vec![MethodDescription {
+                name: "GetAmbientConditions".into(),
+                input_type: "tempgrpcd.v1.GetAmbientConditionsRequest".into(),
+                output_type: "tempgrpcd.v1.GetAmbientConditionsResponse".into(),
+                client_streaming: false,
+                server_streaming: false,
+            }],
+        }]
+    }
+
+    #[tokio::test]
+    async fn describes_services_via_reflection_v1() {
+        let addr = test_server::spawn_with_reflection(fake(), ReflectionVersion::V1).await;
+
+        let desc = describe_server(&settings_for(addr), None)
+            .await
+            .expect("describe");
+        assert_eq!(desc.services, expected_services());
+        assert_eq!(desc.api_version.as_deref(), Some(EXPECTED_API_VERSION));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_reflection_v1alpha() {
+        let addr = test_server::spawn_with_reflection(fake(), ReflectionVersion::V1Alpha).await;
+
+        let desc = describe_server(&settings_for(addr), None)
+            .await
+            .expect("describe");
+        assert_eq!(desc.services, expected_services());
+    }
+
+    #[tokio::test]
+    async fn reports_unsupported_without_reflection_service() {
+        let addr = test_server::spawn_plaintext(fake()).await;
+
+        let res = describe_server(&settings_for(addr), None).await;
+        assert!(matches!(res, Err(ReflectionError::Unsupported)), "{res:?}");
+    }
+}
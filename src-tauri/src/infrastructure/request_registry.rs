@@ -0,0 +1,167 @@
+//! 実行中の要求を ID と画面（ビュー）ごとに覚えておき、途中で取り消せるようにする
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use tokio::sync::oneshot;
+
+/// 要求が取り消されたことを表すエラー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("request was cancelled")]
+pub struct Cancelled;
+
+struct Pending {
+    request_id: Option<String>,
+    view: Option<String>,
+    cancel: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct Requests {
+    next_key: u64,
+    pending: HashMap<u64, Pending>,
+}
+
+/// 実行中の要求の一覧
+#[derive(Default)]
+pub struct RequestRegistry {
+    requests: Mutex<Requests>,
+}
+
+impl RequestRegistry {
+    /// `fut` を取り消し可能な要求として実行する。
+    /// 同じ `view` の要求が実行中なら、そちらは古いものとして取り消す。
+    /// 取り消されると `fut` を途中で破棄し（実行中の RPC も止まる）、`Cancelled` を返す
+    pub async fn run<T>(
+        &self,
+        request_id: Option<String>,
+        view: Option<String>,
+        fut: impl Future<Output = T>,
+    ) -> Result<T, Cancelled> {
+        let (key, cancelled) = self.register(request_id, view);
+        let _finished = Finished {
+            registry: self,
+            key,
+        };
+        tokio::select! {
+            value = fut => Ok(value),
+            _ = cancelled => Err(Cancelled),
+        }
+    }
+
+    /// `request_id` の要求を取り消す。実行中の要求がなければ false
+    pub fn cancel(&self, request_id: &str) -> bool {
+        let mut requests = self.requests.lock().unwrap();
+        let keys: Vec<u64> = requests
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.request_id.as_deref() == Some(request_id))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &keys {
+            if let Some(pending) = requests.pending.remove(key) {
+                let _ = pending.cancel.send(());
+            }
+        }
+        !keys.is_empty()
+    }
+
+    fn register(
+        &self,
+        request_id: Option<String>,
+        view: Option<String>,
+    ) -> (u64, oneshot::Receiver<()>) {
+        let mut requests = self.requests.lock().unwrap();
+        if view.is_some() {
+            let stale: Vec<u64> = requests
+                .pending
+                .iter()
+                .filter(|(_, pending)| pending.view == view)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in stale {
+                if let Some(pending) = requests.pending.remove(&key) {
+                    let _ = pending.cancel.send(());
+                }
+            }
+        }
+
+        let (cancel, cancelled) = oneshot::channel();
+        let key = requests.next_key;
+        requests.next_key += 1;
+        requests.pending.insert(
+            key,
+            Pending {
+                request_id,
+                view,
+                cancel,
+            },
+        );
+        (key, cancelled)
+    }
+}
+
+/// 要求が終わったら（呼び出し元が Future を捨てた場合も）一覧から外す
+struct Finished<'a> {
+    registry: &'a RequestRegistry,
+    key: u64,
+}
+
+impl Drop for Finished<'_> {
+    fn drop(&mut self) {
+        self.registry
+            .requests
+            .lock()
+            .unwrap()
+            .pending
+            .remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    async fn slow(value: u32) -> u32 {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        value
+    }
+
+    #[tokio::test]
+    async fn cancel_aborts_request_by_id() {
+        let registry = Arc::new(RequestRegistry::default());
+        let running = {
+            let registry = registry.clone();
+            tokio::spawn(async move { registry.run(Some("a".into()), None, slow(1)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert!(!registry.cancel("unknown"));
+        assert!(registry.cancel("a"));
+        assert_eq!(running.await.unwrap(), Err(Cancelled));
+        // 終わった要求はもう取り消せない
+        assert!(!registry.cancel("a"));
+    }
+
+    #[tokio::test]
+    async fn newer_request_for_same_view_cancels_older_one() {
+        let registry = Arc::new(RequestRegistry::default());
+        let spawn = |id: &str, view: &str, value| {
+            let registry = registry.clone();
+            let (id, view) = (id.to_string(), view.to_string());
+            tokio::spawn(async move { registry.run(Some(id), Some(view), slow(value)).await })
+        };
+        let older = spawn("a", "dashboard", 1);
+        let other_view = spawn("b", "report", 2);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let newer = registry.run(Some("c".into()), Some("dashboard".into()), async { 3 });
+        assert_eq!(newer.await, Ok(3));
+        assert_eq!(older.await.unwrap(), Err(Cancelled));
+
+        assert!(registry.cancel("b"));
+        assert_eq!(other_view.await.unwrap(), Err(Cancelled));
+    }
+}
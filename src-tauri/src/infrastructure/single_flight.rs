@@ -0,0 +1,107 @@
+//! 同じキーの処理が実行中なら新しく始めず、その結果を待っている全員で共有する (single-flight)
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+/// キーごとに実行中の処理を 1 つにまとめる
+pub struct SingleFlight<K, V> {
+    calls: Mutex<HashMap<K, Arc<OnceCell<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// `key` の処理が実行中ならその結果を待ち、なければ `f` を実行する。
+    /// 実行していた呼び出し元が途中で取り消された場合は、待っていた呼び出し元のどれかが代わりに実行する
+    pub async fn run<F, Fut>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let value = cell.get_or_init(f).await.clone();
+
+        // 終わった処理は外し、次の呼び出しでは新しく実行する
+        let mut calls = self.calls.lock().unwrap();
+        if calls
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            calls.remove(&key);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    async fn slow_count(calls: &AtomicUsize, value: u32) -> u32 {
+        calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        value
+    }
+
+    #[tokio::test]
+    async fn concurrent_calls_with_same_key_share_one_run() {
+        let flights = SingleFlight::default();
+        let calls = AtomicUsize::new(0);
+
+        let (a, b, c) = tokio::join!(
+            flights.run("range", || slow_count(&calls, 1)),
+            flights.run("range", || slow_count(&calls, 2)),
+            flights.run("other", || slow_count(&calls, 3)),
+        );
+        assert_eq!((a, b, c), (1, 1, 3));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // 終わった後の呼び出しは新しく実行する
+        assert_eq!(flights.run("range", || slow_count(&calls, 4)).await, 4);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn waiter_takes_over_when_leader_is_cancelled() {
+        let flights = Arc::new(SingleFlight::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let leader = {
+            let flights = flights.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { flights.run("range", || slow_count(&calls, 1)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let waiter = {
+            let flights = flights.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { flights.run("range", || slow_count(&calls, 2)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        leader.abort();
+
+        assert_eq!(waiter.await.unwrap(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
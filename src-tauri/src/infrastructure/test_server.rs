@@ -0,0 +1,498 @@
+//! テスト用のローカル tempgrpcd サーバーとプロキシのスタンドイン
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use prost_types::{
+    DescriptorProto, FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto,
+    ServiceDescriptorProto,
+};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
+use rustls::RootCertStore;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tempgrpcd_protos::tempgrpcd::v1::{GetAmbientConditionsRequest, GetAmbientConditionsResponse};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use tokio::net::{TcpListener, TcpStream};
+use tonic::codegen::{Body, BoxFuture, Context, Poll, Service, StdError, http};
+use tonic::server::{Grpc, NamedService, UnaryService};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+
+type Handler = dyn Fn(Request<GetAmbientConditionsRequest>) -> Result<GetAmbientConditionsResponse, Status>
+    + Send
+    + Sync;
+
+/// `GetAmbientConditions` だけを実装した偽の tempgrpcd サービス
+#[derive(Clone)]
+pub struct FakeTempgrpcd {
+    handler: Arc<Handler>,
+    /// 応答を返すまで待つ時間（タイムアウトの検証用）
+    delay: Duration,
+}
+
+impl FakeTempgrpcd {
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(Request<GetAmbientConditionsRequest>) -> Result<GetAmbientConditionsResponse, Status>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            handler: Arc::new(handler),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// 各応答を `delay` だけ遅らせる
+    pub fn with_delay(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+}
+
+struct GetAmbientConditionsSvc(Arc<Handler>, Duration);
+
+impl UnaryService<GetAmbientConditionsRequest> for GetAmbientConditionsSvc {
+    type Response = GetAmbientConditionsResponse;
+    type Future = BoxFuture<Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: Request<GetAmbientConditionsRequest>) -> Self::Future {
+        let handler = self.0.clone();
+        let delay = self.1;
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            handler(request).map(Response::new)
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for FakeTempgrpcd
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        match req.uri().path() {
+            "/tempgrpcd.v1.TempgrpcdService/GetAmbientConditions" => {
+                let handler = self.handler.clone();
+                let delay = self.delay;
+                Box::pin(async move {
+                    let mut grpc = Grpc::new(tonic_prost::ProstCodec::default());
+                    Ok(grpc
+                        .unary(GetAmbientConditionsSvc(handler, delay), req)
+                        .await)
+                })
+            }
+            _ => Box::pin(async move { Ok(Status::unimplemented("").into_http()) }),
+        }
+    }
+}
+
+impl NamedService for FakeTempgrpcd {
+    const NAME: &'static str = "tempgrpcd.v1.TempgrpcdService";
+}
+
+/// 平文 (h2c) の偽 tempgrpcd サーバーを起動し、待ち受けアドレスを返す
+pub async fn spawn_plaintext(service: FakeTempgrpcd) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .expect("serve");
+    });
+    addr
+}
+
+/// リフレクションサービスのどちらの版を載せるか
+pub enum ReflectionVersion {
+    V1,
+    V1Alpha,
+}
+
+/// 平文の偽 tempgrpcd サーバーをリフレクションサービス付きで起動する
+pub async fn spawn_with_reflection(
+    service: FakeTempgrpcd,
+    version: ReflectionVersion,
+) -> SocketAddr {
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_file_descriptor_set(tempgrpcd_file_descriptor_set());
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let router = match version {
+        ReflectionVersion::V1 => Server::builder()
+            .add_service(service)
+            .add_service(reflection.build_v1().expect("reflection v1")),
+        ReflectionVersion::V1Alpha => Server::builder()
+            .add_service(service)
+            .add_service(reflection.build_v1alpha().expect("reflection v1alpha")),
+    };
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .expect("serve");
+    });
+    addr
+}
+
+/// `tempgrpcd.v1.TempgrpcdService` の記述子（リフレクションで返す分だけ手で組み立てる）
+fn tempgrpcd_file_descriptor_set() -> FileDescriptorSet {
+    let message = |name: &str| DescriptorProto {
+        name: Some(name.to_string()),
+        ..DescriptorProto::default()
+    };
+    FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("tempgrpcd/v1/tempgrpcd.proto".into()),
+            package: Some("tempgrpcd.v1".into()),
+            message_type: vec![
+                message("GetAmbientConditionsRequest"),
+                message("GetAmbientConditionsResponse"),
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("TempgrpcdService".into()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("GetAmbientConditions".into()),
+                    input_type: Some(".tempgrpcd.v1.GetAmbientConditionsRequest".into()),
+                    output_type: Some(".tempgrpcd.v1.GetAmbientConditionsResponse".into()),
+                    ..MethodDescriptorProto::default()
+                }],
+                ..ServiceDescriptorProto::default()
+            }],
+            syntax: Some("proto3".into()),
+            ..FileDescriptorProto::default()
+        }],
+    }
+}
+
+/// TLS で待ち受ける偽 tempgrpcd サーバーを起動し、待ち受けアドレスを返す
+pub async fn spawn_tls(service: FakeTempgrpcd, cert_pem: &str, key_pem: &str) -> SocketAddr {
+    let tls = ServerTlsConfig::new().identity(Identity::from_pem(cert_pem, key_pem));
+    serve_tls(service, tls).await
+}
+
+/// テスト用 CA が発行したクライアント証明書を要求する (mTLS) 偽 tempgrpcd サーバーを起動する
+pub async fn spawn_mtls(service: FakeTempgrpcd, pki: &TestPki) -> SocketAddr {
+    let tls = ServerTlsConfig::new()
+        .identity(Identity::from_pem(
+            &pki.server_cert_pem,
+            &pki.server_key_pem,
+        ))
+        .client_ca_root(Certificate::from_pem(&pki.ca_pem));
+    serve_tls(service, tls).await
+}
+
+async fn serve_tls(service: FakeTempgrpcd, tls: ServerTlsConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        Server::builder()
+            .tls_config(tls)
+            .expect("tls config")
+            .add_service(service)
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .expect("serve");
+    });
+    addr
+}
+
+/// TCP 接続を受け付けるだけで何も応答しないサーバーを起動する（接続タイムアウトの検証用）
+pub async fn spawn_silent() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+    addr
+}
+
+/// HTTP CONNECT だけを理解する最小限のプロキシを起動し、待ち受けアドレスを返す
+pub async fn spawn_connect_proxy() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connect(stream, None));
+        }
+    });
+    addr
+}
+
+/// TLS で待ち受ける HTTP CONNECT プロキシを起動する。
+/// `basic_auth` に `user:password` を指定すると `Proxy-Authorization: Basic` を要求する
+pub async fn spawn_tls_connect_proxy(
+    tls: Arc<rustls::ServerConfig>,
+    basic_auth: Option<&str>,
+) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let acceptor = tokio_rustls::TlsAcceptor::from(tls);
+    let expected = basic_auth.map(|c| format!("Basic {}", STANDARD.encode(c)));
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            let expected = expected.clone();
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    handle_connect(stream, expected).await;
+                }
+            });
+        }
+    });
+    addr
+}
+
+async fn handle_connect<S>(mut client: S, expected_auth: Option<String>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        match client.read(&mut buf).await {
+            Ok(1) => head.push(buf[0]),
+            _ => return,
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.split("\r\n");
+    let mut parts = lines.next().unwrap_or("").split_whitespace();
+    if parts.next() != Some("CONNECT") {
+        let _ = client
+            .write_all(b"HTTP/1.1 405 Method Not Allowed\r\n\r\n")
+            .await;
+        return;
+    }
+    let Some(target) = parts.next() else { return };
+    if let Some(expected) = expected_auth {
+        let authorized = lines.any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.eq_ignore_ascii_case("proxy-authorization") && value.trim() == expected
+            })
+        });
+        if !authorized {
+            let _ = client
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await;
+            return;
+        }
+    }
+    let Ok(mut upstream) = TcpStream::connect(target).await else {
+        let _ = client.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await;
+        return;
+    };
+    if client
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+}
+
+/// テスト用の CA と、その CA が発行した `localhost` 向けのサーバー証明書・クライアント証明書
+pub struct TestPki {
+    pub ca_der: CertificateDer<'static>,
+    pub ca_pem: String,
+    pub server_cert_der: CertificateDer<'static>,
+    pub server_cert_pem: String,
+    pub server_key_der: Vec<u8>,
+    pub server_key_pem: String,
+    pub client_cert_pem: String,
+    pub client_key_pem: String,
+}
+
+impl TestPki {
+    pub fn generate() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "roomtemp test CA");
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().expect("ca key"))
+            .expect("ca cert");
+
+        let server_key = KeyPair::generate().expect("server key");
+        let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+            .expect("server params")
+            .signed_by(&server_key, &ca)
+            .expect("server cert");
+
+        let client_key = KeyPair::generate().expect("client key");
+        let mut client_params =
+            CertificateParams::new(Vec::<String>::new()).expect("client params");
+        client_params
+            .distinguished_name
+            .push(DnType::CommonName, "roomtemp test client");
+        client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client_cert = client_params
+            .signed_by(&client_key, &ca)
+            .expect("client cert");
+
+        Self {
+            ca_der: ca.der().clone(),
+            ca_pem: ca.pem(),
+            server_cert_der: server_cert.der().clone(),
+            server_cert_pem: server_cert.pem(),
+            server_key_der: server_key.serialize_der(),
+            server_key_pem: server_key.serialize_pem(),
+            client_cert_pem: client_cert.pem(),
+            client_key_pem: client_key.serialize_pem(),
+        }
+    }
+
+    /// CA を介さない `localhost` 向けの自己署名証明書と秘密鍵 (PEM)
+    pub fn self_signed_localhost() -> (String, String) {
+        let key = KeyPair::generate().expect("key");
+        let cert = CertificateParams::new(vec!["localhost".to_string()])
+            .expect("params")
+            .self_signed(&key)
+            .expect("self-signed cert");
+        (cert.pem(), key.serialize_pem())
+    }
+
+    /// サーバー証明書で待ち受ける rustls のサーバー設定
+    pub fn server_config(&self) -> Arc<rustls::ServerConfig> {
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .expect("protocol versions")
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![self.server_cert_der.clone()],
+            PrivateKeyDer::Pkcs8(self.server_key_der.clone().into()),
+        )
+        .expect("server config");
+        Arc::new(config)
+    }
+
+    /// CA 証明書だけを含むルートストア
+    pub fn root_store(&self) -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca_der.clone()).expect("add ca");
+        roots
+    }
+}
+
+/// SOCKS5 プロキシのスタンドイン。CONNECT のみ対応し、受け取った宛先を記録する
+pub struct Socks5Proxy {
+    pub addr: SocketAddr,
+    /// CONNECT で指定された宛先（ドメイン名ならそのまま、IP ならアドレス文字列）
+    pub targets: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+/// SOCKS5 プロキシを起動する。`credentials` を指定すると RFC 1929 のユーザー名/パスワード認証を要求する
+pub async fn spawn_socks5_proxy(credentials: Option<(&str, &str)>) -> Socks5Proxy {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let credentials = credentials.map(|(u, p)| (u.to_string(), p.to_string()));
+    let targets = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = targets.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let credentials = credentials.clone();
+            let recorded = recorded.clone();
+            tokio::spawn(async move {
+                let _ = handle_socks5(stream, credentials, recorded).await;
+            });
+        }
+    });
+    Socks5Proxy { addr, targets }
+}
+
+async fn handle_socks5(
+    mut client: TcpStream,
+    credentials: Option<(String, String)>,
+    recorded: Arc<std::sync::Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+    // greeting: VER NMETHODS METHODS...
+    let mut head = [0u8; 2];
+    client.read_exact(&mut head).await?;
+    let mut methods = vec![0u8; head[1] as usize];
+    client.read_exact(&mut methods).await?;
+    let wanted = if credentials.is_some() { 0x02 } else { 0x00 };
+    if !methods.contains(&wanted) {
+        client.write_all(&[0x05, 0xff]).await?;
+        return Ok(());
+    }
+    client.write_all(&[0x05, wanted]).await?;
+
+    if let Some((user, pass)) = credentials {
+        // VER ULEN UNAME PLEN PASSWD
+        let mut ver_len = [0u8; 2];
+        client.read_exact(&mut ver_len).await?;
+        let mut got_user = vec![0u8; ver_len[1] as usize];
+        client.read_exact(&mut got_user).await?;
+        let mut plen = [0u8; 1];
+        client.read_exact(&mut plen).await?;
+        let mut got_pass = vec![0u8; plen[0] as usize];
+        client.read_exact(&mut got_pass).await?;
+        if got_user != user.as_bytes() || got_pass != pass.as_bytes() {
+            client.write_all(&[0x01, 0x01]).await?;
+            return Ok(());
+        }
+        client.write_all(&[0x01, 0x00]).await?;
+    }
+
+    // request: VER CMD RSV ATYP DST.ADDR DST.PORT
+    let mut req = [0u8; 4];
+    client.read_exact(&mut req).await?;
+    let host = match req[3] {
+        0x01 => {
+            let mut ip = [0u8; 4];
+            client.read_exact(&mut ip).await?;
+            std::net::Ipv4Addr::from(ip).to_string()
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            client.read_exact(&mut len).await?;
+            let mut name = vec![0u8; len[0] as usize];
+            client.read_exact(&mut name).await?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        0x04 => {
+            let mut ip = [0u8; 16];
+            client.read_exact(&mut ip).await?;
+            std::net::Ipv6Addr::from(ip).to_string()
+        }
+        _ => return Ok(()),
+    };
+    let mut port = [0u8; 2];
+    client.read_exact(&mut port).await?;
+    let port = u16::from_be_bytes(port);
+    recorded.lock().unwrap().push(host.clone());
+
+    let Ok(mut upstream) = TcpStream::connect((host.as_str(), port)).await else {
+        client
+            .write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+            .await?;
+        return Ok(());
+    };
+    client
+        .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+        .await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
@@ -1,3 +1,14 @@
+// 既存のテストの書き方（unit を `let _ =` で受ける、bool を assert_eq! で比べる、テストの後に impl を置く）は
+// 新しい clippy で警告になるので、テストでだけ許す
+#![cfg_attr(
+    test,
+    allow(
+        clippy::let_unit_value,
+        clippy::bool_assert_comparison,
+        clippy::items_after_test_module
+    )
+)]
+
 mod app_state;
 mod controller;
 mod domain;
@@ -6,16 +17,30 @@ mod presentation;
 mod repository;
 mod usecase;
 
-use std::sync::Arc;
-
 use app_state::AppState;
+use infrastructure::alert_evaluator::AlertEvaluator;
+use infrastructure::connection::{BackoffPolicy, ConnectionManager};
 use infrastructure::db::{establish_connection_pool, run_migrations};
-use presentation::commands::{connect_to_grpc_server, get_graph_data, get_settings, set_settings};
-use tauri::Manager as _;
-use tokio::sync::Mutex;
+use infrastructure::live_poller::LivePoller;
+use presentation::commands::{
+    cancel_request, connect_to_grpc_server, create_alert_rule, delete_alert_rule,
+    delete_client_certificate, describe_server, detect_anomalies, get_aggregates,
+    get_anomaly_thresholds, get_client_certificate, get_connection_status, get_graph_data,
+    get_settings, get_statistics, import_client_certificate, list_alert_events, list_alert_rules,
+    set_anomaly_thresholds, set_settings, test_connection, update_alert_rule,
+};
+use tauri::{Emitter as _, Manager as _};
+use tauri_plugin_notification::NotificationExt as _;
 
 use crate::infrastructure::keystore;
 
+/// 接続状態が変わるたびに webview へ送るイベント名
+pub const CONNECTION_STATE_EVENT: &str = "connection://state";
+/// ライブ更新で増えた測定値を webview へ送るイベント名
+pub const AMBIENT_UPDATE_EVENT: &str = "ambient://update";
+/// アラートの規則が発報するたびに webview へ送るイベント名（デスクトップ通知はバックエンドで出す）
+pub const ALERT_FIRED_EVENT: &str = "alert://fired";
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     #[cfg(target_os = "android")]
@@ -23,6 +48,7 @@ pub fn run() {
         .expect("android-keyring init failed");
 
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let identifier = app.config().identifier.as_str();
             println!("App Identifier: {}", identifier);
@@ -31,10 +57,46 @@ pub fn run() {
             let pool = establish_connection_pool(app.handle());
             // マイグレーションの実行
             run_migrations(&pool);
+            // 接続状態の変化はそのまま webview に通知する
+            let handle = app.handle().clone();
+            let connection = ConnectionManager::new(BackoffPolicy::default(), move |state| {
+                if let Err(e) = handle.emit(CONNECTION_STATE_EVENT, state) {
+                    eprintln!("Failed to emit connection state: {e:?}");
+                }
+            });
+            // 発報したらデスクトップ通知を出し、webview にも知らせる
+            let handle = app.handle().clone();
+            let alerts = AlertEvaluator::spawn(pool.clone(), move |fired| {
+                let notification = handle
+                    .notification()
+                    .builder()
+                    .title(&fired.rule.name)
+                    .body(fired.message())
+                    .show();
+                if let Err(e) = notification {
+                    eprintln!("Failed to show alert notification: {e:?}");
+                }
+                if let Err(e) = handle.emit(ALERT_FIRED_EVENT, fired) {
+                    eprintln!("Failed to emit alert: {e:?}");
+                }
+            });
+            // ライブ更新で増えた測定値も webview に送り、届いた順にアラートの規則で判定する
+            let handle = app.handle().clone();
+            let live_alerts = alerts.clone();
+            let live = LivePoller::new(connection.clone(), move |update| {
+                if let Err(e) = handle.emit(AMBIENT_UPDATE_EVENT, update) {
+                    eprintln!("Failed to emit ambient update: {e:?}");
+                }
+                live_alerts.submit(update.readings.clone());
+            });
             // アプリ全体で共有する状態として登録
             let state = AppState {
                 pool,
-                grpc_connection: Arc::new(Mutex::new(None)),
+                connection,
+                live,
+                graph_data_flights: Default::default(),
+                requests: Default::default(),
+                alerts,
             };
             app.manage(state);
 
@@ -51,7 +113,24 @@ pub fn run() {
             get_settings,
             set_settings,
             connect_to_grpc_server,
-            get_graph_data
+            get_connection_status,
+            test_connection,
+            describe_server,
+            get_graph_data,
+            cancel_request,
+            get_statistics,
+            get_aggregates,
+            detect_anomalies,
+            get_anomaly_thresholds,
+            set_anomaly_thresholds,
+            list_alert_rules,
+            create_alert_rule,
+            update_alert_rule,
+            delete_alert_rule,
+            list_alert_events,
+            import_client_certificate,
+            get_client_certificate,
+            delete_client_certificate
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,3 @@
+ALTER TABLE settings DROP COLUMN auth_header_prefix;
+ALTER TABLE settings DROP COLUMN auth_header_name;
+ALTER TABLE settings DROP COLUMN auth_mode;
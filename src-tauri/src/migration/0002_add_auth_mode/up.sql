@@ -0,0 +1,3 @@
+ALTER TABLE settings ADD COLUMN auth_mode TEXT NOT NULL DEFAULT 'bearer';
+ALTER TABLE settings ADD COLUMN auth_header_name TEXT NOT NULL DEFAULT '';
+ALTER TABLE settings ADD COLUMN auth_header_prefix TEXT NOT NULL DEFAULT '';
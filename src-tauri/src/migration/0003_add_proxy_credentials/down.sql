@@ -0,0 +1,3 @@
+ALTER TABLE settings DROP COLUMN encrypted_proxy_password_nonce;
+ALTER TABLE settings DROP COLUMN encrypted_proxy_password;
+ALTER TABLE settings DROP COLUMN proxy_username;
@@ -0,0 +1,3 @@
+ALTER TABLE settings ADD COLUMN proxy_username TEXT NOT NULL DEFAULT '';
+ALTER TABLE settings ADD COLUMN encrypted_proxy_password BLOB;
+ALTER TABLE settings ADD COLUMN encrypted_proxy_password_nonce BLOB;
@@ -0,0 +1,3 @@
+ALTER TABLE settings DROP COLUMN disable_webpki_roots;
+ALTER TABLE settings DROP COLUMN ca_bundle_path;
+ALTER TABLE settings DROP COLUMN ca_bundle_pem;
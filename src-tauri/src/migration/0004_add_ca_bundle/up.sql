@@ -0,0 +1,3 @@
+ALTER TABLE settings ADD COLUMN ca_bundle_pem TEXT NOT NULL DEFAULT '';
+ALTER TABLE settings ADD COLUMN ca_bundle_path TEXT NOT NULL DEFAULT '';
+ALTER TABLE settings ADD COLUMN disable_webpki_roots BOOLEAN NOT NULL DEFAULT 0;
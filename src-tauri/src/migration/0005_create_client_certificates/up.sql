@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS client_certificates (
+    id INTEGER PRIMARY KEY,
+    cert_pem TEXT NOT NULL,
+    encrypted_private_key BLOB NOT NULL,
+    encrypted_private_key_nonce BLOB NOT NULL
+);
@@ -0,0 +1,5 @@
+ALTER TABLE settings DROP COLUMN keepalive_while_idle;
+ALTER TABLE settings DROP COLUMN keepalive_timeout_ms;
+ALTER TABLE settings DROP COLUMN keepalive_interval_ms;
+ALTER TABLE settings DROP COLUMN request_timeout_ms;
+ALTER TABLE settings DROP COLUMN connect_timeout_ms;
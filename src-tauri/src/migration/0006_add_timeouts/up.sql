@@ -0,0 +1,5 @@
+ALTER TABLE settings ADD COLUMN connect_timeout_ms INTEGER NOT NULL DEFAULT 10000;
+ALTER TABLE settings ADD COLUMN request_timeout_ms INTEGER NOT NULL DEFAULT 30000;
+ALTER TABLE settings ADD COLUMN keepalive_interval_ms INTEGER NOT NULL DEFAULT 30000;
+ALTER TABLE settings ADD COLUMN keepalive_timeout_ms INTEGER NOT NULL DEFAULT 10000;
+ALTER TABLE settings ADD COLUMN keepalive_while_idle BOOLEAN NOT NULL DEFAULT 0;
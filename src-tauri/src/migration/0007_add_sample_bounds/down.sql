@@ -0,0 +1,2 @@
+ALTER TABLE settings DROP COLUMN max_samples;
+ALTER TABLE settings DROP COLUMN min_samples;
@@ -0,0 +1,2 @@
+ALTER TABLE settings ADD COLUMN min_samples INTEGER NOT NULL DEFAULT 100;
+ALTER TABLE settings ADD COLUMN max_samples INTEGER NOT NULL DEFAULT 5000;
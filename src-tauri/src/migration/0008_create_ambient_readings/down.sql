@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS ambient_cached_spans;
+DROP TABLE IF EXISTS ambient_readings;
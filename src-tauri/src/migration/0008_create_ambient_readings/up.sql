@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS ambient_readings (
+    endpoint TEXT NOT NULL,
+    id TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL,
+    temperature REAL NOT NULL,
+    humidity REAL NOT NULL,
+    illumination REAL NOT NULL,
+    PRIMARY KEY (endpoint, id)
+);
+CREATE INDEX IF NOT EXISTS ambient_readings_recorded_at
+    ON ambient_readings (endpoint, recorded_at);
+
+CREATE TABLE IF NOT EXISTS ambient_cached_spans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    endpoint TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL,
+    interval_ns INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS ambient_cached_spans_start
+    ON ambient_cached_spans (endpoint, start_ms);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS ambient_sync (
+    endpoint TEXT PRIMARY KEY NOT NULL,
+    last_synced_at INTEGER NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE settings ADD COLUMN poll_interval_ms INTEGER NOT NULL DEFAULT 10000;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS anomaly_thresholds (
+    metric TEXT PRIMARY KEY NOT NULL,
+    window_size INTEGER NOT NULL,
+    z_score REAL NOT NULL,
+    iqr_multiplier REAL NOT NULL,
+    rate_of_change REAL NOT NULL
+);
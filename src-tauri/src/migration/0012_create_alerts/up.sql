@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    comparator TEXT NOT NULL,
+    threshold REAL NOT NULL,
+    sustained_ms INTEGER NOT NULL DEFAULT 0,
+    quiet_start_minute INTEGER,
+    quiet_end_minute INTEGER,
+    enabled BOOLEAN NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS alert_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id INTEGER NOT NULL,
+    fired_at INTEGER NOT NULL,
+    cleared_at INTEGER,
+    value REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS alert_events_fired_at
+    ON alert_events (fired_at);
+CREATE UNIQUE INDEX IF NOT EXISTS alert_events_active
+    ON alert_events (rule_id) WHERE cleared_at IS NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS aggregate_metrics;
+DROP TABLE IF EXISTS aggregate_buckets;
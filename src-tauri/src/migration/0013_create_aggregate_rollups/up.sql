@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS aggregate_buckets (
+    endpoint TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    time_zone TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL,
+    PRIMARY KEY (endpoint, interval, time_zone, start_ms)
+);
+
+CREATE TABLE IF NOT EXISTS aggregate_metrics (
+    endpoint TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    time_zone TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    metric TEXT NOT NULL,
+    sample_count INTEGER NOT NULL,
+    min_value REAL NOT NULL,
+    mean_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    PRIMARY KEY (endpoint, interval, time_zone, start_ms, metric)
+);
@@ -0,0 +1,16 @@
+CREATE TABLE ambient_readings_old (
+    endpoint TEXT NOT NULL,
+    id TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL,
+    temperature REAL NOT NULL,
+    humidity REAL NOT NULL,
+    illumination REAL NOT NULL,
+    PRIMARY KEY (endpoint, id)
+);
+INSERT INTO ambient_readings_old
+    SELECT endpoint, id, recorded_at, temperature, humidity, illumination FROM ambient_readings
+    WHERE temperature IS NOT NULL AND humidity IS NOT NULL AND illumination IS NOT NULL;
+DROP TABLE ambient_readings;
+ALTER TABLE ambient_readings_old RENAME TO ambient_readings;
+CREATE INDEX IF NOT EXISTS ambient_readings_recorded_at
+    ON ambient_readings (endpoint, recorded_at);
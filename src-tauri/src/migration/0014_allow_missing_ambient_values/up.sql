@@ -0,0 +1,16 @@
+-- SQLite は NaN を NULL として保存するので、値の列は NULL を許す（NULL は NaN として読み出す）
+CREATE TABLE ambient_readings_new (
+    endpoint TEXT NOT NULL,
+    id TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL,
+    temperature REAL,
+    humidity REAL,
+    illumination REAL,
+    PRIMARY KEY (endpoint, id)
+);
+INSERT INTO ambient_readings_new
+    SELECT endpoint, id, recorded_at, temperature, humidity, illumination FROM ambient_readings;
+DROP TABLE ambient_readings;
+ALTER TABLE ambient_readings_new RENAME TO ambient_readings;
+CREATE INDEX IF NOT EXISTS ambient_readings_recorded_at
+    ON ambient_readings (endpoint, recorded_at);
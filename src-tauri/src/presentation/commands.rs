@@ -1,14 +1,42 @@
-use pbjson_types::Timestamp;
-use prost::Message;
+use std::time::Duration;
+
+use bytes::Bytes;
 use tauri::State;
 use tauri::ipc::Response;
-use tempgrpcd_protos::tempgrpcd::v1::GetAmbientConditionsRequest;
 
 use crate::app_state::AppState;
+use crate::controller::aggregate_controller::AggregateController;
+use crate::controller::alert_controller::AlertController;
+use crate::controller::ambient_cache_controller::AmbientCacheController;
+use crate::controller::anomaly_controller::AnomalyController;
+use crate::controller::client_certificate_controller::ClientCertificateController;
 use crate::controller::settings_controller::SettingsController;
-use crate::domain::settings::Settings;
-use crate::infrastructure::grpc_client;
+use crate::domain::aggregation::{AggregateInterval, AggregateReport};
+use crate::domain::alert::{AlertEvent, AlertRule};
+use crate::domain::ambient::AmbientMetric;
+use crate::domain::anomaly::{AnomalyReport, AnomalyThresholds};
+use crate::domain::client_certificate::{
+    ClientCertificateImport, ClientCertificateInfo, ClientIdentity,
+};
+use crate::domain::connection::ConnectionStatus;
+use crate::domain::diagnostics::ConnectionReport;
+use crate::domain::downsampling::DownsampleOptions;
+use crate::domain::graph_data::{self, FailedWindow, GraphData, GraphDataMeta};
+use crate::domain::psychrometrics::{self, DerivedMetric};
+use crate::domain::sampling::{self, Resolution, SampleBounds};
+use crate::domain::server_info::ServerDescription;
+use crate::domain::settings::{AuthMode, Settings};
+use crate::domain::statistics::{self, StatisticsReport};
+use crate::domain::time_range::{TimeRange, TimeRangeInput};
+use crate::infrastructure::chunked_fetch::{self, ChunkPlan, ChunkedResponse};
+use crate::infrastructure::db::DbPool;
+use crate::infrastructure::{diagnostics, grpc_client, reflection};
 use crate::presentation::ui_error::{self, UIError};
+use crate::repository::diesel_aggregate_repository::DieselAggregateRepository;
+use crate::repository::diesel_alert_repository::DieselAlertRepository;
+use crate::repository::diesel_ambient_reading_repository::DieselAmbientReadingRepository;
+use crate::repository::diesel_anomaly_threshold_repository::DieselAnomalyThresholdRepository;
+use crate::repository::diesel_client_certificate_repository::DieselClientCertificateRepository;
 use crate::repository::diesel_settings_repository::DieselSettingsRepository;
 
 #[tauri::command]
@@ -22,74 +50,521 @@ pub fn get_settings(state: State<AppState>) -> Result<Settings, UIError> {
     }
 }
 
+/// 設定を保存し、ライブ更新を新しい間隔で起動し直す
+#[tauri::command]
+pub async fn set_settings(state: State<'_, AppState>, settings: Settings) -> Result<(), UIError> {
+    let poll_interval = Duration::from_millis(settings.poll_interval_ms.into());
+    {
+        let conn = state.pool.get()?;
+        let mut repo = DieselSettingsRepository { conn };
+        let mut controller = SettingsController::new(&mut repo);
+        controller.set(settings)?;
+    }
+    state.live.restart(poll_interval);
+    Ok(())
+}
+
+/// 接続に必要な設定が揃っているかを認証方式に応じて検証する
+fn validate_connection_settings(settings: &Settings) -> Result<(), UIError> {
+    if settings.url.is_empty()
+        || (settings.auth_mode.requires_token() && settings.access_token.is_empty())
+    {
+        return Err(ui_error::url_access_token_empty_error());
+    }
+    if settings.auth_mode == AuthMode::Custom && settings.auth_header_name.is_empty() {
+        return Err(ui_error::auth_header_name_empty_error());
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub fn set_settings(
+pub fn import_client_certificate(
     state: State<AppState>,
-    url: String,
-    access_token: String,
-    use_proxies: bool,
-    proxy_url: String,
-) -> Result<(), UIError> {
+    import: ClientCertificateImport,
+) -> Result<ClientCertificateInfo, UIError> {
     let conn = state.pool.get()?;
-    let mut repo = DieselSettingsRepository { conn };
-    let mut controller = SettingsController::new(&mut repo);
-    controller.set(url, access_token, use_proxies, proxy_url)?;
+    let mut repo = DieselClientCertificateRepository { conn };
+    let mut controller = ClientCertificateController::new(&mut repo);
+    Ok(controller.import(import)?)
+}
+
+#[tauri::command]
+pub fn get_client_certificate(
+    state: State<AppState>,
+) -> Result<Option<ClientCertificateInfo>, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselClientCertificateRepository { conn };
+    let mut controller = ClientCertificateController::new(&mut repo);
+    Ok(controller.info()?)
+}
+
+#[tauri::command]
+pub fn delete_client_certificate(state: State<AppState>) -> Result<(), UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselClientCertificateRepository { conn };
+    let mut controller = ClientCertificateController::new(&mut repo);
+    controller.delete()?;
     Ok(())
 }
 
+/// 接続時に提示する mTLS クライアント証明書（未登録なら None）
+fn load_client_identity(state: &AppState) -> Result<Option<ClientIdentity>, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselClientCertificateRepository { conn };
+    let mut controller = ClientCertificateController::new(&mut repo);
+    Ok(controller.identity()?)
+}
+
 #[tauri::command]
 pub async fn connect_to_grpc_server(state: State<'_, AppState>) -> Result<String, UIError> {
     let settings = get_settings(state.clone())?;
 
-    if settings.url.is_empty() || settings.access_token.is_empty() {
-        return Err(ui_error::url_access_token_empty_error());
+    validate_connection_settings(&settings)?;
+
+    let identity = load_client_identity(&state)?;
+    let poll_interval = Duration::from_millis(settings.poll_interval_ms.into());
+    // 接続先が変わりうるので、ライブ更新は接続し直した時点から数える
+    state.live.restart(poll_interval);
+    state.connection.connect(settings, identity).await?;
+
+    Ok("Connected to gRPC server".into())
+}
+
+#[tauri::command]
+pub async fn get_connection_status(
+    state: State<'_, AppState>,
+) -> Result<ConnectionStatus, UIError> {
+    Ok(state.connection.status().await)
+}
+
+/// 保存前の設定で接続を段階ごとに試す。現在の接続には影響しない
+#[tauri::command]
+pub async fn test_connection(
+    state: State<'_, AppState>,
+    settings: Settings,
+) -> Result<ConnectionReport, UIError> {
+    let mut settings = settings;
+    // 画面でパスワードを入力し直していなければ保存済みのものを使う
+    if settings.proxy_password.is_none() {
+        settings.proxy_password = get_settings(state.clone())?.proxy_password;
     }
+    let identity = load_client_identity(&state)?;
+    Ok(diagnostics::test_connection(&settings, identity.as_ref()).await)
+}
 
-    let client = grpc_client::new(&settings).await?;
+/// 保存済みの接続先にリフレクションで問い合わせ、提供されているサービスと API バージョンを返す
+#[tauri::command]
+pub async fn describe_server(state: State<'_, AppState>) -> Result<ServerDescription, UIError> {
+    let settings = get_settings(state.clone())?;
 
-    let mut guard = state.grpc_connection.lock().await;
-    *guard = Some(client);
+    validate_connection_settings(&settings)?;
 
-    Ok("Connected to gRPC server".into())
+    let identity = load_client_identity(&state)?;
+    Ok(reflection::describe_server(&settings, identity.as_ref()).await?)
 }
 
+/// `range` の測定値を返す（形式は `domain::graph_data` を参照）。
+/// 取得済みの期間は手元のキャッシュから返し、足りない期間だけサーバーに問い合わせる。
+/// 一部の区間を取得できなかった場合はメタデータの `partial` で知らせ、その区間は解像度が粗くても
+/// キャッシュにある測定値で埋める。サーバーに届かないときはキャッシュだけで応答し、`servedFromCache` を立てる。
+/// サンプル数は `resolution`（グラフの描画幅など）から、設定の上下限の範囲で決める。
+/// 接続先・期間・サンプル数が同じ要求が実行中なら、新しく取得せずにその結果を共有する。
+/// `request_id` を付けると `cancel_request` で取り消せる。同じ `view` の新しい要求が来たら古いものは取り消す。
+/// サーバーがサンプル数を守らなかった場合やキャッシュから返す場合に備え、測定値が `downsample` の点数
+/// （省略時はサンプル数）より多ければ、指定の方法（省略時は LTTB）で指標ごとに間引く。
+/// 間引く必要も `derived` もなければ、本体はデコードせずにそのまま返す
 #[tauri::command]
 pub async fn get_graph_data(
     state: State<'_, AppState>,
-    start_time: u64,
-    end_time: u64,
-) -> Result<Response, String> {
-    let mut client = {
-        let guard = state.grpc_connection.lock().await;
-        // クローン可能なので clone してガードをすぐ手放す
-        guard
-            .as_ref()
-            .cloned()
-            .ok_or_else(|| "gRPC client is not connected".to_string())?
+    range: TimeRangeInput,
+    resolution: Option<Resolution>,
+    request_id: Option<String>,
+    view: Option<String>,
+    derived: Option<Vec<DerivedMetric>>,
+    downsample: Option<DownsampleOptions>,
+) -> Result<Response, UIError> {
+    let range = TimeRange::new(range)?;
+    let settings = get_settings(state.clone())?;
+    let samples = sampling::sample_count(&range, resolution, SampleBounds::from(&settings));
+    let endpoint = settings.url;
+
+    let load = shared_graph_data(&state, endpoint, range, samples);
+    let mut data = state.requests.run(request_id, view, load).await??;
+    let downsample = downsample.unwrap_or_default();
+    let target = downsample.target_points.unwrap_or(samples) as usize;
+    let kept = data.downsample(target, downsample.method)?;
+    if let Some(metrics) = derived.filter(|metrics| !metrics.is_empty()) {
+        let readings = match kept {
+            Some(readings) => readings,
+            None => data.readings()?,
+        };
+        data.meta.derived_series = psychrometrics::derive_series(&readings, &metrics);
+    }
+    Ok(Response::new(graph_data::frame(&data.meta, &data.chunks)))
+}
+
+/// `range` の温度・湿度・照度それぞれの統計量を返す。
+/// 測定値は `get_graph_data` と同じくキャッシュとサーバーから、設定の上限のサンプル数で集める。
+/// `derived` で指定した追加の系列（露点など）の統計量も返す
+#[tauri::command]
+pub async fn get_statistics(
+    state: State<'_, AppState>,
+    range: TimeRangeInput,
+    derived: Option<Vec<DerivedMetric>>,
+) -> Result<StatisticsReport, UIError> {
+    let range = TimeRange::new(range)?;
+    let settings = get_settings(state.clone())?;
+    let samples = SampleBounds::from(&settings).max();
+
+    let data = shared_graph_data(&state, settings.url, range, samples).await?;
+    let readings = data.readings()?;
+    Ok(StatisticsReport {
+        statistics: statistics::summarize(&readings),
+        derived: statistics::summarize_derived(&readings, &derived.unwrap_or_default()),
+        meta: data.meta,
+    })
+}
+
+/// `range` の測定値から異常（z スコア・IQR・変化率がしきい値を超えたもの）を探す。
+/// 測定値は `get_statistics` と同じく設定の上限のサンプル数で集める。`metrics` を省くと全指標を調べる
+#[tauri::command]
+pub async fn detect_anomalies(
+    state: State<'_, AppState>,
+    range: TimeRangeInput,
+    metrics: Option<Vec<AmbientMetric>>,
+) -> Result<AnomalyReport, UIError> {
+    let range = TimeRange::new(range)?;
+    let settings = get_settings(state.clone())?;
+    let samples = SampleBounds::from(&settings).max();
+
+    let data = shared_graph_data(&state, settings.url, range, samples).await?;
+    let readings = data.readings()?;
+    let conn = state.pool.get()?;
+    let mut repo = DieselAnomalyThresholdRepository { conn };
+    let anomalies =
+        AnomalyController::new(&mut repo).detect(&readings, &metrics.unwrap_or_default())?;
+    Ok(AnomalyReport {
+        anomalies,
+        meta: data.meta,
+    })
+}
+
+/// `range` の測定値を `interval`（1 時間・1 日・1 週間）ごとに `time_zone`（IANA 名）の暦で区切り、
+/// 区切りごとに指標の最小・平均・最大と件数を返す。最初と最後の区切りも暦どおりの長さで集計する。
+/// 集計済みの区切りは保存したものを返し、足りない区切りは間引いていない測定値をサーバーから集めて集計する
+#[tauri::command]
+pub async fn get_aggregates(
+    state: State<'_, AppState>,
+    range: TimeRangeInput,
+    interval: AggregateInterval,
+    time_zone: String,
+) -> Result<AggregateReport, UIError> {
+    let range = TimeRange::new(range)?;
+    let settings = get_settings(state.clone())?;
+
+    let plan = {
+        let conn = state.pool.get()?;
+        let mut repo = DieselAggregateRepository { conn };
+        AggregateController::new(&mut repo).plan(&settings.url, &range, interval, &time_zone)?
     };
-    let start_timestamp = Timestamp {
-        seconds: start_time as i64,
-        nanos: 0,
+    let cached_buckets = plan.cached.len();
+    let (data, complete) = match plan.missing_range() {
+        Some(missing) => {
+            let unsampled = match state.connection.channel().await {
+                Some(channel) => fetch_unsampled(&state, channel, &plan.missing_ranges()).await,
+                None => None,
+            };
+            match unsampled {
+                // 取得できなかった区間があった集計は保存しない
+                Some(data) => {
+                    let complete = !data.meta.partial;
+                    (data, complete)
+                }
+                // サーバーから取得できなければキャッシュの測定値で集計する。間引かれているので保存しない
+                None => {
+                    let samples = SampleBounds::from(&settings).max();
+                    let data =
+                        shared_graph_data(&state, settings.url.clone(), missing, samples).await?;
+                    (data, false)
+                }
+            }
+        }
+        None => (GraphData::default(), true),
     };
-    let end_timestamp = Timestamp {
-        seconds: end_time as i64,
-        nanos: 0,
+    let readings = data.readings()?;
+    let buckets = {
+        let conn = state.pool.get()?;
+        let mut repo = DieselAggregateRepository { conn };
+        AggregateController::new(&mut repo).complete(&settings.url, plan, readings, complete)?
     };
+    Ok(AggregateReport {
+        interval,
+        time_zone,
+        buckets,
+        cached_buckets,
+        meta: data.meta,
+    })
+}
 
-    let resp = client
-        .get_ambient_conditions(tonic::Request::new(GetAmbientConditionsRequest {
-            start_time: Some(start_timestamp),
-            end_time: Some(end_timestamp),
-            samples: Some(1000),
-        }))
+/// `ranges` の測定値を間引かせずに取得する。どの区間も取得できなければ `None`。
+/// 区切りごとの件数がサンプル数の上限で切れないように、`samples` を指定せず短い区間に分けて取得する
+async fn fetch_unsampled(
+    state: &AppState,
+    channel: grpc_client::AuthenticatedChannel,
+    ranges: &[TimeRange],
+) -> Option<GraphData> {
+    let plan = ChunkPlan {
+        window: chunked_fetch::UNSAMPLED_WINDOW,
+        ..ChunkPlan::default()
+    };
+    let fetched = chunked_fetch::fetch_chunked(channel, ranges, None, &plan).await;
+    report_unreachable(state, &fetched.failures).await;
+    if fetched.chunks.is_empty() && !fetched.failures.is_empty() {
+        return None;
+    }
+    let meta = GraphDataMeta {
+        partial: !fetched.failures.is_empty(),
+        failed_windows: fetched
+            .failures
+            .into_iter()
+            .map(|(window, status)| FailedWindow {
+                range: window.to_millis(),
+                error: UIError::from(status).to_string(),
+            })
+            .collect(),
+        ..GraphDataMeta::default()
+    };
+    let chunks = fetched.chunks.into_iter().map(|(_, bytes)| bytes).collect();
+    Some(GraphData { meta, chunks })
+}
+
+/// 指標ごとの異常検出のしきい値（保存していない指標は既定値）
+#[tauri::command]
+pub fn get_anomaly_thresholds(state: State<AppState>) -> Result<Vec<AnomalyThresholds>, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAnomalyThresholdRepository { conn };
+    Ok(AnomalyController::new(&mut repo).thresholds()?)
+}
+
+/// 異常検出のしきい値を保存する（含まれない指標は変えない）
+#[tauri::command]
+pub fn set_anomaly_thresholds(
+    state: State<AppState>,
+    thresholds: Vec<AnomalyThresholds>,
+) -> Result<(), UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAnomalyThresholdRepository { conn };
+    Ok(AnomalyController::new(&mut repo).set_thresholds(&thresholds)?)
+}
+
+/// アラートの規則の一覧
+#[tauri::command]
+pub fn list_alert_rules(state: State<AppState>) -> Result<Vec<AlertRule>, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAlertRepository { conn };
+    Ok(AlertController::new(&mut repo).rules()?)
+}
+
+/// アラートの規則を追加し、ID を振った規則を返す
+#[tauri::command]
+pub fn create_alert_rule(state: State<AppState>, rule: AlertRule) -> Result<AlertRule, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAlertRepository { conn };
+    Ok(AlertController::new(&mut repo).create_rule(rule)?)
+}
+
+/// アラートの規則を置き換える。しきい値などが変わるので、判定の途中経過はやり直す
+#[tauri::command]
+pub fn update_alert_rule(state: State<AppState>, rule: AlertRule) -> Result<(), UIError> {
+    let id = rule.id;
+    let conn = state.pool.get()?;
+    let mut repo = DieselAlertRepository { conn };
+    AlertController::new(&mut repo).update_rule(rule)?;
+    state.alerts.reset(id);
+    Ok(())
+}
+
+/// アラートの規則とその発報履歴を削除する。規則がなければ false
+#[tauri::command]
+pub fn delete_alert_rule(state: State<AppState>, id: i32) -> Result<bool, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAlertRepository { conn };
+    Ok(AlertController::new(&mut repo).delete_rule(id)?)
+}
+
+/// 新しい順の発報履歴
+#[tauri::command]
+pub fn list_alert_events(
+    state: State<AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<AlertEvent>, UIError> {
+    let conn = state.pool.get()?;
+    let mut repo = DieselAlertRepository { conn };
+    Ok(AlertController::new(&mut repo).events(limit)?)
+}
+
+/// 接続先・期間・サンプル数が同じ取得が実行中なら、その結果を共有する
+async fn shared_graph_data(
+    state: &AppState,
+    endpoint: String,
+    range: TimeRange,
+    samples: u32,
+) -> Result<GraphData, UIError> {
+    let key = (endpoint.clone(), range, samples);
+    state
+        .graph_data_flights
+        .run(key, || load_graph_data(state, endpoint, range, samples))
         .await
-        .map_err(|e| e.to_string())?;
+}
+
+/// 実行中の要求を取り消す。取り消した要求があれば true
+#[tauri::command]
+pub fn cancel_request(state: State<AppState>, request_id: String) -> Result<bool, UIError> {
+    Ok(state.requests.cancel(&request_id))
+}
+
+/// `get_graph_data` の本体。キャッシュとサーバーから測定値を集める
+async fn load_graph_data(
+    state: &AppState,
+    endpoint: String,
+    range: TimeRange,
+    samples: u32,
+) -> Result<GraphData, UIError> {
+    let interval = range.duration() / samples;
+    let gaps = {
+        let conn = state.pool.get()?;
+        let mut repo = DieselAmbientReadingRepository { conn };
+        AmbientCacheController::new(&mut repo).missing_spans(&endpoint, &range, interval)?
+    };
+    let covered = range.gaps(&gaps);
+
+    // 長い期間は区間に分けて取得する。応答はデコードせず、protobuf のバイト列のまま連結して渡す
+    let channel = if gaps.is_empty() {
+        None
+    } else {
+        state.connection.channel().await
+    };
+    let not_connected = !gaps.is_empty() && channel.is_none();
+    let fetched = match channel {
+        Some(channel) => {
+            chunked_fetch::fetch_chunked(channel, &gaps, Some(interval), &ChunkPlan::default())
+                .await
+        }
+        None => ChunkedResponse::default(),
+    };
 
-    // TODO: gRPCコールによって受け取ったバイナリデータをデコードしたものをまたエンコードしているはずで無駄な処理をしているはず
-    // できそうなら、受け取ったバイナリデータをそのままフロントエンドに渡したい
-    let binarized_ambient_condition = resp.into_inner().encode_to_vec();
+    report_unreachable(state, &fetched.failures).await;
+
+    // サーバーに届かなかった（または接続していない）ので、キャッシュだけで応答する
+    let unreachable = not_connected
+        || (fetched.chunks.is_empty()
+            && !fetched.failures.is_empty()
+            && fetched
+                .failures
+                .iter()
+                .all(|(_, status)| is_unreachable(status)));
+    let mut failures: Vec<(TimeRange, UIError)> = if not_connected {
+        gaps.iter()
+            .map(|gap| (*gap, ui_error::not_connected_error()))
+            .collect()
+    } else {
+        fetched
+            .failures
+            .into_iter()
+            .map(|(window, status)| (window, UIError::from(status)))
+            .collect()
+    };
 
-    Ok(Response::new(binarized_ambient_condition))
+    let (cached, last_sync) = {
+        let conn = state.pool.get()?;
+        let mut repo = DieselAmbientReadingRepository { conn };
+        let mut cache = AmbientCacheController::new(&mut repo);
+        let ranges: Vec<TimeRange> = covered
+            .iter()
+            .copied()
+            .chain(failures.iter().map(|(window, _)| *window))
+            .collect();
+        (
+            cache.cached_response(&endpoint, &ranges, interval)?,
+            cache.last_synced_at(&endpoint)?,
+        )
+    };
+    // 何も返せないときだけエラーにする
+    if fetched.chunks.is_empty()
+        && !failures.is_empty()
+        && covered.is_empty()
+        && cached.covered.is_none()
+    {
+        return Err(failures.swap_remove(0).1);
+    }
+
+    if !fetched.chunks.is_empty() {
+        let pool = state.pool.clone();
+        let chunks = fetched.chunks.clone();
+        tokio::task::spawn_blocking(move || {
+            store_fetched_chunks(&pool, &endpoint, &chunks, interval)
+        });
+    }
+
+    let meta = GraphDataMeta {
+        partial: !failures.is_empty(),
+        failed_windows: failures
+            .into_iter()
+            .map(|(window, error)| FailedWindow {
+                range: window.to_millis(),
+                error: error.to_string(),
+            })
+            .collect(),
+        served_from_cache: unreachable,
+        last_sync,
+        covered_range: cached.covered.map(TimeRange::to_millis),
+        ..GraphDataMeta::default()
+    };
+    let chunks: Vec<Bytes> = std::iter::once(Bytes::from(cached.body))
+        .chain(fetched.chunks.into_iter().map(|(_, bytes)| bytes))
+        .collect();
+    Ok(GraphData { meta, chunks })
+}
+
+/// 取得に失敗した区間のうちサーバーに届かなかったものがあれば、バックグラウンドで再接続を始める
+async fn report_unreachable(state: &AppState, failures: &[(TimeRange, tonic::Status)]) {
+    if let Some((_, status)) = failures
+        .iter()
+        .find(|(_, status)| status.code() == tonic::Code::Unavailable)
+    {
+        state
+            .connection
+            .report_failure(status.message().to_string())
+            .await;
+    }
+}
+
+/// サーバーに届かなかったことを示すステータスかどうか
+fn is_unreachable(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::Unavailable || grpc_client::is_deadline_exceeded(status)
+}
+
+/// サーバーから取得した区間をキャッシュに保存する。応答を返した後に裏で実行するので、失敗はログに残すだけにする
+fn store_fetched_chunks(
+    pool: &DbPool,
+    endpoint: &str,
+    chunks: &[(TimeRange, Bytes)],
+    interval: Duration,
+) {
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("ambient cache: failed to get a connection: {e}");
+            return;
+        }
+    };
+    let mut repo = DieselAmbientReadingRepository { conn };
+    let mut cache = AmbientCacheController::new(&mut repo);
+    for (window, body) in chunks {
+        if let Err(e) = cache.store_fetched(endpoint, window, interval, body) {
+            eprintln!("ambient cache: failed to store fetched readings: {e}");
+        }
+    }
 }
 
 #[cfg(test)]
@@ -99,8 +574,25 @@ pub mod __tests {
     use diesel::r2d2::ConnectionManager;
     use diesel::r2d2::Pool;
     use diesel::sqlite::SqliteConnection;
-    use std::sync::Arc;
-    use tokio::sync::Mutex as TokioMutex;
+
+    // App state whose connection events and live updates go nowhere (no webview in tests)
+    fn detached_state(pool: DbPool) -> AppState {
+        let connection = crate::infrastructure::connection::ConnectionManager::new(
+            crate::infrastructure::connection::BackoffPolicy::default(),
+            |_| {},
+        );
+        let live = crate::infrastructure::live_poller::LivePoller::new(connection.clone(), |_| {});
+        let alerts =
+            crate::infrastructure::alert_evaluator::AlertEvaluator::spawn(pool.clone(), |_| {});
+        AppState {
+            pool,
+            connection,
+            live,
+            graph_data_flights: Default::default(),
+            requests: Default::default(),
+            alerts,
+        }
+    }
 
     // Test helper for calling get_settings without tauri State wrapper
     pub fn get_settings_from_state(state: &AppState) -> Result<Settings, UIError> {
@@ -126,29 +618,28 @@ pub mod __tests {
         let conn = state.pool.get().unwrap();
         let mut repo = DieselSettingsRepository { conn };
         let mut controller = SettingsController::new(&mut repo);
-        controller.set(
-            url.to_string(),
-            access_token.to_string(),
+        controller.set(Settings {
+            id: 1,
+            url: url.to_string(),
+            access_token: access_token.to_string(),
             use_proxies,
-            proxy_url.to_string(),
-        )?;
+            proxy_url: proxy_url.to_string(),
+            ..Settings::default()
+        })?;
         Ok(())
     }
 
     pub async fn test_get_graph_data_from_state(
         state: &AppState,
-        _start_time: u64,
-        _end_time: u64,
+        _range: TimeRangeInput,
     ) -> Result<Response, String> {
-        let client = {
-            let guard = state.grpc_connection.lock().await;
-            guard
-                .as_ref()
-                .cloned()
-                .ok_or_else(|| "gRPC client is not connected".to_string())?
-        };
+        let channel = state
+            .connection
+            .channel()
+            .await
+            .ok_or_else(|| "gRPC client is not connected".to_string())?;
         // Not actually used further for this negative test
-        let _ = client;
+        let _ = channel;
         Err("not implemented".to_string())
     }
 
@@ -157,12 +648,17 @@ pub mod __tests {
         let manager =
             ConnectionManager::<SqliteConnection>::new("file:memdb_test2?mode=memory&cache=shared");
         let pool = Pool::builder().build(manager).expect("pool");
-        let state = AppState {
-            pool,
-            grpc_connection: Arc::new(TokioMutex::new(None)),
-        };
-
-        let res = test_get_graph_data_from_state(&state, 0, 1).await;
+        let state = detached_state(pool);
+
+        let res = test_get_graph_data_from_state(
+            &state,
+            TimeRangeInput {
+                start: 0,
+                end: 1,
+                unit: crate::domain::time_range::TimeUnit::Milliseconds,
+            },
+        )
+        .await;
         assert!(res.is_err());
         assert_eq!(
             res.err().unwrap(),
@@ -175,10 +671,7 @@ pub mod __tests {
         let manager =
             ConnectionManager::<SqliteConnection>::new("file:memdb_test3?mode=memory&cache=shared");
         let pool = Pool::builder().build(manager).expect("pool");
-        let state = AppState {
-            pool,
-            grpc_connection: Arc::new(TokioMutex::new(None)),
-        };
+        let state = detached_state(pool);
 
         // Initially, get should insert defaults
         let got = get_settings_from_state(&state).expect("get ok");
@@ -194,14 +687,10 @@ pub mod __tests {
     pub async fn connect_to_grpc_server_from_state(state: &AppState) -> Result<String, UIError> {
         let settings = get_settings_from_state(state)?;
 
-        if settings.url.is_empty() || settings.access_token.is_empty() {
-            return Err(ui_error::url_access_token_empty_error());
-        }
-
-        let client = grpc_client::new(&settings).await?;
+        validate_connection_settings(&settings)?;
 
-        let mut guard = state.grpc_connection.lock().await;
-        *guard = Some(client);
+        let identity = load_client_identity(state)?;
+        state.connection.connect(settings, identity).await?;
 
         Ok("Connected to gRPC server".into())
     }
@@ -211,16 +700,42 @@ pub mod __tests {
         let manager =
             ConnectionManager::<SqliteConnection>::new("file:memdb_test4?mode=memory&cache=shared");
         let pool = Pool::builder().build(manager).expect("pool");
-        let state = AppState {
-            pool,
-            grpc_connection: Arc::new(TokioMutex::new(None)),
-        };
+        let state = detached_state(pool);
 
         // default settings are empty, so connection should error
         let err = connect_to_grpc_server_from_state(&state).await;
         assert!(err.is_err());
     }
 
+    #[test]
+    fn validate_connection_settings_respects_auth_mode() {
+        let no_auth = Settings {
+            url: "http://lab:50051".into(),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+        assert!(validate_connection_settings(&no_auth).is_ok());
+
+        let bearer_without_token = Settings {
+            url: "https://x".into(),
+            ..Settings::default()
+        };
+        assert!(validate_connection_settings(&bearer_without_token).is_err());
+
+        let custom_without_header = Settings {
+            url: "https://x".into(),
+            access_token: "tok".into(),
+            auth_mode: AuthMode::Custom,
+            ..Settings::default()
+        };
+        assert_eq!(
+            validate_connection_settings(&custom_without_header)
+                .unwrap_err()
+                .to_string(),
+            ui_error::auth_header_name_empty_error().to_string()
+        );
+    }
+
     // Helper to construct a `tauri::State<AppState>` from a plain reference for tests.
     pub fn make_state_ref(state: &AppState) -> State<'_, AppState> {
         // Safe in tests: `State<'_, T>` is a thin wrapper around `&T` and has the same representation.
@@ -232,10 +747,7 @@ pub mod __tests {
         let manager =
             ConnectionManager::<SqliteConnection>::new("file:memdb_test5?mode=memory&cache=shared");
         let pool = Pool::builder().build(manager).expect("pool");
-        let state = AppState {
-            pool,
-            grpc_connection: Arc::new(TokioMutex::new(None)),
-        };
+        let state = detached_state(pool);
 
         // Ensure DB schema exists
         crate::infrastructure::db::run_migrations(&state.pool);
@@ -245,15 +757,12 @@ pub mod __tests {
         assert_eq!(s.id, 1);
     }
 
-    #[test]
-    fn set_settings_command_wrapper_works() {
+    #[tokio::test]
+    async fn set_settings_command_wrapper_works() {
         let manager =
             ConnectionManager::<SqliteConnection>::new("file:memdb_test6?mode=memory&cache=shared");
         let pool = Pool::builder().build(manager).expect("pool");
-        let state = AppState {
-            pool,
-            grpc_connection: Arc::new(TokioMutex::new(None)),
-        };
+        let state = detached_state(pool);
 
         // Ensure DB schema exists
         crate::infrastructure::db::run_migrations(&state.pool);
@@ -261,14 +770,187 @@ pub mod __tests {
         // set should succeed
         set_settings(
             make_state_ref(&state),
-            "https://y".into(),
-            "tok2".into(),
-            false,
-            "".into(),
+            Settings {
+                url: "https://y".into(),
+                access_token: "tok2".into(),
+                ..Settings::default()
+            },
         )
+        .await
         .expect("set ok");
 
         let s = get_settings(make_state_ref(&state)).expect("get settings");
         assert_eq!(s.url, "https://y");
     }
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+    const OFFLINE_ENDPOINT: &str = "http://offline.example";
+
+    // Two weeks, so the fetch is split into two week-long windows
+    fn two_weeks() -> TimeRange {
+        TimeRange::from_millis(0, 14 * DAY_MS).unwrap()
+    }
+
+    fn day_reading(day: i64) -> crate::domain::ambient::AmbientReading {
+        crate::domain::ambient::AmbientReading {
+            id: format!("{}-0", day * DAY_MS + 1),
+            recorded_at_ms: day * DAY_MS + 1,
+            temperature: 20.0 + day as f64,
+            humidity: 40.0,
+            illumination: 100.0,
+        }
+    }
+
+    fn cached_state(name: &str, cached_days: &[i64]) -> AppState {
+        let manager = ConnectionManager::<SqliteConnection>::new(format!(
+            "file:{name}?mode=memory&cache=shared"
+        ));
+        let pool = Pool::builder().build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        if !cached_days.is_empty() {
+            use crate::repository::diesel_ambient_reading_repository::AmbientReadingRepository as _;
+
+            let readings: Vec<_> = cached_days.iter().copied().map(day_reading).collect();
+            // No cached span, so these days are still fetched from the server when possible
+            let mut repo = DieselAmbientReadingRepository {
+                conn: pool.get().unwrap(),
+            };
+            repo.store(OFFLINE_ENDPOINT, &readings, None, 100)
+                .expect("seed cache");
+        }
+        detached_state(pool)
+    }
+
+    // Connects to a fake server that answers each window with one reading on its first day,
+    // or with the status returned by `fail` for that window's first day
+    async fn connect_to_fake(
+        state: &AppState,
+        fail: impl Fn(i64) -> Option<tonic::Status> + Send + Sync + 'static,
+    ) {
+        use crate::infrastructure::test_server::{self, FakeTempgrpcd};
+        use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+
+        let addr = test_server::spawn_plaintext(FakeTempgrpcd::new(move |request| {
+            let start_ms = request.into_inner().start_time.expect("start").seconds * 1000;
+            let day = start_ms / DAY_MS;
+            if let Some(status) = fail(day) {
+                return Err(status);
+            }
+            let reading = day_reading(day);
+            let ambient_conditions = std::collections::HashMap::from([(
+                reading.id,
+                AmbientCondition {
+                    temperature: reading.temperature as f32,
+                    humidity: reading.humidity as f32,
+                    illumination: reading.illumination as f32,
+                },
+            )]);
+            Ok(GetAmbientConditionsResponse { ambient_conditions })
+        }))
+        .await;
+        let settings = Settings {
+            url: format!("http://{addr}"),
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        };
+        state
+            .connection
+            .connect(settings, None)
+            .await
+            .expect("connect");
+    }
+
+    async fn load(state: &AppState) -> Result<GraphData, UIError> {
+        load_graph_data(state, OFFLINE_ENDPOINT.to_string(), two_weeks(), 14).await
+    }
+
+    // The metadata header exactly as the webview receives it
+    fn framed_meta(data: &GraphData) -> serde_json::Value {
+        let framed = graph_data::frame(&data.meta, &data.chunks);
+        let header_len = u32::from_le_bytes(framed[..4].try_into().unwrap()) as usize;
+        serde_json::from_slice(&framed[4..4 + header_len]).unwrap()
+    }
+
+    fn recorded_days(data: &GraphData) -> Vec<i64> {
+        data.readings()
+            .unwrap()
+            .iter()
+            .map(|reading| reading.recorded_at_ms / DAY_MS)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn load_graph_data_serves_cache_only_when_not_connected() {
+        let state = cached_state("memdb_offline_cache_only", &[3, 10]);
+
+        let data = load(&state).await.expect("served from cache");
+        assert_eq!(recorded_days(&data), [3, 10]);
+        let meta = framed_meta(&data);
+        assert_eq!(meta["servedFromCache"], true);
+        assert_eq!(meta["partial"], true);
+        assert_eq!(meta["lastSync"], 100);
+        assert_eq!(
+            meta["coveredRange"],
+            serde_json::json!({ "start": 3 * DAY_MS + 1, "end": 10 * DAY_MS + 2, "unit": "milliseconds" })
+        );
+        let failed = meta["failedWindows"].as_array().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["start"], 0);
+        assert_eq!(failed[0]["end"], 14 * DAY_MS);
+        assert_eq!(failed[0]["error"], "gRPC client is not connected");
+    }
+
+    #[tokio::test]
+    async fn load_graph_data_errors_without_cache_or_server() {
+        let state = cached_state("memdb_offline_empty", &[]);
+
+        let err = load(&state).await.expect_err("nothing to serve");
+        assert_eq!(err.to_string(), ui_error::not_connected_error().to_string());
+    }
+
+    #[tokio::test]
+    async fn load_graph_data_fills_failed_windows_from_cache() {
+        let state = cached_state("memdb_offline_partial", &[10]);
+        connect_to_fake(&state, |day| {
+            (day >= 7).then(|| tonic::Status::internal("window failed"))
+        })
+        .await;
+
+        let data = load(&state).await.expect("partial data");
+        // Day 0 comes from the server, day 10 from the cache
+        assert_eq!(recorded_days(&data), [0, 10]);
+        let meta = framed_meta(&data);
+        assert_eq!(meta["partial"], true);
+        assert_eq!(meta["servedFromCache"], false);
+        let failed = meta["failedWindows"].as_array().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["start"], 7 * DAY_MS);
+        assert_eq!(failed[0]["end"], 14 * DAY_MS);
+    }
+
+    #[tokio::test]
+    async fn load_graph_data_is_served_from_cache_only_when_every_window_is_unreachable() {
+        let state = cached_state("memdb_offline_unreachable", &[3]);
+        connect_to_fake(&state, |_| Some(tonic::Status::unavailable("down"))).await;
+
+        let data = load(&state).await.expect("served from cache");
+        assert_eq!(recorded_days(&data), [3]);
+        let meta = framed_meta(&data);
+        assert_eq!(meta["servedFromCache"], true);
+        assert_eq!(meta["failedWindows"].as_array().unwrap().len(), 2);
+
+        // One window still answers, so the response is partial but not cache-only
+        let state = cached_state("memdb_offline_half_unreachable", &[3]);
+        connect_to_fake(&state, |day| {
+            (day >= 7).then(|| tonic::Status::unavailable("down"))
+        })
+        .await;
+
+        let data = load(&state).await.expect("partial data");
+        assert_eq!(recorded_days(&data), [0]);
+        let meta = framed_meta(&data);
+        assert_eq!(meta["servedFromCache"], false);
+        assert_eq!(meta["partial"], true);
+        assert_eq!(meta["failedWindows"].as_array().unwrap().len(), 1);
+    }
 }
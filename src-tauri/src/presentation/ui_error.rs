@@ -1,10 +1,24 @@
 use crate::{
-    infrastructure::{crypto::CryptoError, grpc_client::GrpcClientError, keystore::KeystoreError},
-    repository::diesel_settings_repository::DieselSettingsRepositoryError,
-    usecase::settings::SettingsError,
+    domain::time_range::TimeRangeError,
+    infrastructure::{
+        client_identity::ClientIdentityError,
+        crypto::CryptoError,
+        grpc_client::{self, GrpcClientError},
+        keystore::KeystoreError,
+        reflection::ReflectionError,
+        request_registry::Cancelled,
+    },
+    repository::{
+        diesel_client_certificate_repository::DieselClientCertificateRepositoryError,
+        diesel_settings_repository::DieselSettingsRepositoryError,
+    },
+    usecase::{
+        aggregate::AggregateError, alert::AlertError, ambient_cache::AmbientCacheError,
+        anomaly::AnomalyError, client_certificate::ClientCertificateError, settings::SettingsError,
+    },
 };
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 #[error("{message}")]
 pub struct UIError {
     message: String,
@@ -26,6 +40,30 @@ pub fn url_access_token_empty_error() -> UIError {
     }
 }
 
+pub fn not_connected_error() -> UIError {
+    UIError {
+        message: "gRPC client is not connected".into(),
+    }
+}
+
+pub fn deadline_exceeded_error() -> UIError {
+    UIError {
+        message: "grpc: request deadline exceeded".into(),
+    }
+}
+
+pub fn connect_timeout_error() -> UIError {
+    UIError {
+        message: "grpc: connection timed out".into(),
+    }
+}
+
+pub fn auth_header_name_empty_error() -> UIError {
+    UIError {
+        message: "Custom authentication requires a header name".into(),
+    }
+}
+
 impl From<SettingsError> for UIError {
     fn from(err: SettingsError) -> Self {
         match err {
@@ -35,55 +73,188 @@ impl From<SettingsError> for UIError {
                 }
             }
             SettingsError::DieselSettingsRepository(DieselSettingsRepositoryError::Crypto(e)) => {
-                match e {
-                    CryptoError::InvalidKey => UIError {
-                        message: "Crypto: invalid key detected".into(),
-                    },
-                    CryptoError::InvalidNonceLen(_) => UIError {
-                        message: "Crypto: invalid nonce length detected".into(),
-                    },
-                    CryptoError::Encrypt => UIError {
-                        message: "Crypto: encryption failed".into(),
-                    },
-                    CryptoError::Decrypt => UIError {
-                        message: "Crypto: decryption failed".into(),
-                    },
-                }
+                UIError::from(e)
             }
             SettingsError::DieselSettingsRepository(DieselSettingsRepositoryError::Keystore(e)) => {
-                match e {
-                    KeystoreError::PlatformFailure(_) => UIError {
-                        message: "Keystore: platform failure".into(),
-                    },
-                    KeystoreError::NoStorageAccess(_) => UIError {
-                        message: "Keystore: storage unavailable".into(),
-                    },
-                    KeystoreError::NoEntry => UIError {
-                        message: "Keystore: entry not found".into(),
-                    },
-                    KeystoreError::BadEncoding(_) => UIError {
-                        message: "Keystore: invalid encoding".into(),
-                    },
-                    KeystoreError::BadDataFormat(_, _) => UIError {
-                        message: "Keystore: invalid data format".into(),
-                    },
-                    KeystoreError::TooLong(_, _) => UIError {
-                        message: "Keystore: attribute too long".into(),
-                    },
-                    KeystoreError::Invalid(_, _) => UIError {
-                        message: "Keystore: invalid attribute".into(),
-                    },
-                    KeystoreError::Ambiguous(_) => UIError {
-                        message: "Keystore: ambiguous credentials".into(),
-                    },
-                    KeystoreError::InvalidLength => UIError {
-                        message: "Keystore: invalid length detected".into(),
-                    },
-                    KeystoreError::Other => UIError {
-                        message: "Keystore: unknown error occurred".into(),
-                    },
-                }
+                UIError::from(e)
             }
+            SettingsError::DieselSettingsRepository(
+                DieselSettingsRepositoryError::UnknownAuthMode(mode),
+            ) => UIError {
+                message: format!("Settings: unknown authentication mode stored ({mode})"),
+            },
+        }
+    }
+}
+
+impl From<CryptoError> for UIError {
+    fn from(err: CryptoError) -> Self {
+        match err {
+            CryptoError::InvalidKey => UIError {
+                message: "Crypto: invalid key detected".into(),
+            },
+            CryptoError::InvalidNonceLen(_) => UIError {
+                message: "Crypto: invalid nonce length detected".into(),
+            },
+            CryptoError::Encrypt => UIError {
+                message: "Crypto: encryption failed".into(),
+            },
+            CryptoError::Decrypt => UIError {
+                message: "Crypto: decryption failed".into(),
+            },
+        }
+    }
+}
+
+impl From<KeystoreError> for UIError {
+    fn from(err: KeystoreError) -> Self {
+        match err {
+            KeystoreError::PlatformFailure(_) => UIError {
+                message: "Keystore: platform failure".into(),
+            },
+            KeystoreError::NoStorageAccess(_) => UIError {
+                message: "Keystore: storage unavailable".into(),
+            },
+            KeystoreError::NoEntry => UIError {
+                message: "Keystore: entry not found".into(),
+            },
+            KeystoreError::BadEncoding(_) => UIError {
+                message: "Keystore: invalid encoding".into(),
+            },
+            KeystoreError::BadDataFormat(_, _) => UIError {
+                message: "Keystore: invalid data format".into(),
+            },
+            KeystoreError::TooLong(_, _) => UIError {
+                message: "Keystore: attribute too long".into(),
+            },
+            KeystoreError::Invalid(_, _) => UIError {
+                message: "Keystore: invalid attribute".into(),
+            },
+            KeystoreError::Ambiguous(_) => UIError {
+                message: "Keystore: ambiguous credentials".into(),
+            },
+            KeystoreError::InvalidLength => UIError {
+                message: "Keystore: invalid length detected".into(),
+            },
+            KeystoreError::Other => UIError {
+                message: "Keystore: unknown error occurred".into(),
+            },
+        }
+    }
+}
+
+impl From<ClientCertificateError> for UIError {
+    fn from(err: ClientCertificateError) -> Self {
+        match err {
+            ClientCertificateError::DieselClientCertificateRepository(e) => match e {
+                DieselClientCertificateRepositoryError::Database(_) => UIError {
+                    message: "Database error occurred".into(),
+                },
+                DieselClientCertificateRepositoryError::Crypto(e) => UIError::from(e),
+                DieselClientCertificateRepositoryError::Keystore(e) => UIError::from(e),
+            },
+            ClientCertificateError::ClientIdentity(e) => match e {
+                ClientIdentityError::Read { path, .. } => UIError {
+                    message: format!("Client certificate: failed to read file: {path}"),
+                },
+                ClientIdentityError::Pkcs12(_) => UIError {
+                    message: "Client certificate: invalid PKCS#12 file or wrong password".into(),
+                },
+                ClientIdentityError::Pem(_) => UIError {
+                    message: "Client certificate: invalid PEM data".into(),
+                },
+                ClientIdentityError::MissingCertificate => UIError {
+                    message: "Client certificate: no certificate found".into(),
+                },
+                ClientIdentityError::MissingPrivateKey => UIError {
+                    message: "Client certificate: no private key found".into(),
+                },
+                ClientIdentityError::InvalidCertificate(_) => UIError {
+                    message: "Client certificate: invalid certificate".into(),
+                },
+                ClientIdentityError::KeyMismatch(_) => UIError {
+                    message: "Client certificate: private key does not match the certificate"
+                        .into(),
+                },
+            },
+        }
+    }
+}
+
+impl From<AmbientCacheError> for UIError {
+    fn from(err: AmbientCacheError) -> Self {
+        match err {
+            AmbientCacheError::DieselAmbientReadingRepository(_) => UIError {
+                message: "Database error occurred".into(),
+            },
+            AmbientCacheError::Decode(_) => UIError {
+                message: "grpc: server returned an invalid response".into(),
+            },
+        }
+    }
+}
+
+impl From<AnomalyError> for UIError {
+    fn from(err: AnomalyError) -> Self {
+        match err {
+            AnomalyError::DieselAnomalyThresholdRepository(_) => UIError {
+                message: "Database error occurred".into(),
+            },
+            AnomalyError::InvalidThresholds(metric) => UIError {
+                message: format!(
+                    "Anomaly thresholds for {}: window must be at least 2 and thresholds must be positive",
+                    metric.as_str()
+                ),
+            },
+        }
+    }
+}
+
+impl From<AggregateError> for UIError {
+    fn from(err: AggregateError) -> Self {
+        match err {
+            AggregateError::DieselAggregateRepository(_) => UIError {
+                message: "Database error occurred".into(),
+            },
+            AggregateError::UnknownTimeZone(name) => UIError {
+                message: format!("Unknown time zone: {name}"),
+            },
+            AggregateError::Aggregation(err) => UIError {
+                message: format!("Cannot aggregate readings: {err}"),
+            },
+        }
+    }
+}
+
+impl From<AlertError> for UIError {
+    fn from(err: AlertError) -> Self {
+        match err {
+            AlertError::DieselAlertRepository(_) => UIError {
+                message: "Database error occurred".into(),
+            },
+            AlertError::InvalidRule => UIError {
+                message: "Alert rule needs a name, a finite threshold, a non-negative duration and quiet hours within a day".into(),
+            },
+            AlertError::RuleNotFound(_) => UIError {
+                message: "Alert rule not found".into(),
+            },
+        }
+    }
+}
+
+/// 取り消された要求はこのメッセージで返す（画面側はエラー表示せずに捨てる）
+impl From<Cancelled> for UIError {
+    fn from(_: Cancelled) -> Self {
+        UIError {
+            message: "Request was cancelled".into(),
+        }
+    }
+}
+
+impl From<prost::DecodeError> for UIError {
+    fn from(_: prost::DecodeError) -> Self {
+        UIError {
+            message: "grpc: server returned an invalid response".into(),
         }
     }
 }
@@ -96,6 +267,58 @@ impl From<r2d2::Error> for UIError {
     }
 }
 
+impl From<ReflectionError> for UIError {
+    fn from(err: ReflectionError) -> Self {
+        match err {
+            ReflectionError::Client(e) => UIError::from(e),
+            ReflectionError::Status(status) => UIError::from(*status),
+            ReflectionError::Unsupported => UIError {
+                message: "grpc: server does not support reflection".into(),
+            },
+            ReflectionError::Server(message) => UIError {
+                message: format!("grpc: reflection failed: {message}"),
+            },
+            ReflectionError::InvalidDescriptor(_) | ReflectionError::UnexpectedResponse => {
+                UIError {
+                    message: "grpc: server returned an invalid reflection response".into(),
+                }
+            }
+        }
+    }
+}
+
+impl From<TimeRangeError> for UIError {
+    fn from(err: TimeRangeError) -> Self {
+        let message = match err {
+            TimeRangeError::Empty => "Time range: start and end are the same",
+            TimeRangeError::Inverted => "Time range: end is before start",
+            TimeRangeError::FarFuture => "Time range: end is too far in the future",
+        };
+        UIError {
+            message: message.into(),
+        }
+    }
+}
+
+impl From<tonic::Status> for UIError {
+    fn from(status: tonic::Status) -> Self {
+        if grpc_client::is_deadline_exceeded(&status) {
+            return deadline_exceeded_error();
+        }
+        match status.code() {
+            tonic::Code::Unavailable => UIError {
+                message: "grpc: server unavailable, reconnecting".into(),
+            },
+            tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => UIError {
+                message: "grpc: authentication failed".into(),
+            },
+            code => UIError {
+                message: format!("grpc: request failed ({code:?}): {}", status.message()),
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +336,11 @@ mod tests {
         assert!(ui.message.contains("Crypto"));
     }
 
+    #[test]
+    fn from_cancelled_has_distinct_message() {
+        assert_eq!(UIError::from(Cancelled).message, "Request was cancelled");
+    }
+
     #[test]
     fn from_grpc_client_error_has_message() {
         let grpc_err = crate::infrastructure::grpc_client::GrpcClientError::InvalidUrl(
@@ -122,6 +350,41 @@ mod tests {
         assert!(ui.message.contains("grpc"));
     }
 
+    #[test]
+    fn from_grpc_client_error_maps_unsupported_scheme() {
+        let grpc_err =
+            crate::infrastructure::grpc_client::GrpcClientError::UnsupportedScheme("ftp".into());
+        let ui = UIError::from(grpc_err);
+        assert!(ui.message.contains("unsupported URL scheme"));
+    }
+
+    #[test]
+    fn from_status_maps_unavailable() {
+        let ui = UIError::from(tonic::Status::unavailable("connection refused"));
+        assert!(ui.message.contains("unavailable"));
+    }
+
+    #[test]
+    fn from_status_maps_deadline_exceeded() {
+        let ui = UIError::from(tonic::Status::deadline_exceeded("too slow"));
+        assert_eq!(ui.message, deadline_exceeded_error().message);
+    }
+
+    #[test]
+    fn from_time_range_error_names_the_problem() {
+        let ui = UIError::from(TimeRangeError::Inverted);
+        assert_eq!(ui.message, "Time range: end is before start");
+    }
+
+    #[test]
+    fn from_client_certificate_error_maps_key_mismatch() {
+        let e = ClientCertificateError::ClientIdentity(ClientIdentityError::KeyMismatch(
+            rustls::Error::InconsistentKeys(rustls::InconsistentKeys::KeyMismatch),
+        ));
+        let ui = UIError::from(e);
+        assert!(ui.message.contains("does not match"));
+    }
+
     #[test]
     fn from_settings_error_maps_keystore_error() {
         use crate::infrastructure::keystore::KeystoreError;
@@ -171,12 +434,38 @@ impl From<GrpcClientError> for UIError {
             GrpcClientError::InvalidTlsDomain(_) => UIError {
                 message: "grpc: invalid TLS domain detected".into(),
             },
+            ref e @ GrpcClientError::Transport(_) if e.is_timeout() => connect_timeout_error(),
             GrpcClientError::Transport(_) => UIError {
                 message: "grpc: transport error occurred".into(),
             },
             GrpcClientError::InvalidAuthToken(_) => UIError {
                 message: "grpc: invalid auth token detected".into(),
             },
+            GrpcClientError::InvalidAuthHeaderName(_) => UIError {
+                message: "grpc: invalid auth header name detected".into(),
+            },
+            GrpcClientError::UnsupportedScheme(_) => UIError {
+                message: "grpc: unsupported URL scheme (use http:// or https://)".into(),
+            },
+            GrpcClientError::UnsupportedProxyScheme(_) => UIError {
+                message:
+                    "grpc: unsupported proxy scheme (use http://, https://, socks5:// or socks5h://)"
+                        .into(),
+            },
+            GrpcClientError::ProxyTls(_) => UIError {
+                message: "grpc: proxy TLS configuration error".into(),
+            },
+            GrpcClientError::CaBundleRead { path, .. } => UIError {
+                message: format!("grpc: failed to read CA bundle file: {path}"),
+            },
+            GrpcClientError::InvalidCaBundle(_) => UIError {
+                message: "grpc: CA bundle contains no valid PEM certificates".into(),
+            },
+            GrpcClientError::ConnectTimeout(_) => connect_timeout_error(),
+            GrpcClientError::NoTrustAnchors => UIError {
+                message: "grpc: no trusted root certificates (enable webpki roots or add a CA bundle)"
+                    .into(),
+            },
         }
     }
 }
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::sqlite::SqliteConnection;
+
+use crate::domain::aggregation::{AggregateBucket, AggregateInterval, MetricAggregate};
+use crate::domain::ambient::AmbientMetric;
+
+// Diesel 用のスキーマ定義
+pub mod schema {
+    use diesel::table;
+
+    table! {
+        aggregate_buckets (endpoint, interval, time_zone, start_ms) {
+            endpoint -> Text,
+            interval -> Text,
+            time_zone -> Text,
+            start_ms -> BigInt,
+            end_ms -> BigInt,
+        }
+    }
+
+    table! {
+        aggregate_metrics (endpoint, interval, time_zone, start_ms, metric) {
+            endpoint -> Text,
+            interval -> Text,
+            time_zone -> Text,
+            start_ms -> BigInt,
+            metric -> Text,
+            sample_count -> BigInt,
+            min_value -> Double,
+            mean_value -> Double,
+            max_value -> Double,
+        }
+    }
+}
+
+#[derive(Queryable)]
+struct AggregateMetricEntity {
+    pub start_ms: i64,
+    pub metric: String,
+    pub sample_count: i64,
+    pub min_value: f64,
+    pub mean_value: f64,
+    pub max_value: f64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::aggregate_buckets)]
+struct NewAggregateBucket<'a> {
+    pub endpoint: &'a str,
+    pub interval: &'a str,
+    pub time_zone: &'a str,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::aggregate_metrics)]
+struct NewAggregateMetric<'a> {
+    pub endpoint: &'a str,
+    pub interval: &'a str,
+    pub time_zone: &'a str,
+    pub start_ms: i64,
+    pub metric: &'a str,
+    pub sample_count: i64,
+    pub min_value: f64,
+    pub mean_value: f64,
+    pub max_value: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DieselAggregateRepositoryError {
+    #[error("database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// 保存済みの集計を区別するキー（接続先・区切り方・タイムゾーン）
+#[derive(Debug, Clone, Copy)]
+pub struct RollupKey<'a> {
+    pub endpoint: &'a str,
+    pub interval: AggregateInterval,
+    /// IANA タイムゾーン名
+    pub time_zone: &'a str,
+}
+
+/// リポジトリインターフェース（区切りごとの集計のキャッシュ）
+pub trait AggregateRepository {
+    /// 開始が `from_ms` 以上 `to_ms` 未満の保存済みの集計を古い順に返す
+    fn rollups(
+        &mut self,
+        key: &RollupKey,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<AggregateBucket>, DieselAggregateRepositoryError>;
+    /// 集計を保存する（同じ区切りは上書き）
+    fn store(
+        &mut self,
+        key: &RollupKey,
+        buckets: &[AggregateBucket],
+    ) -> Result<(), DieselAggregateRepositoryError>;
+}
+
+/// Diesel を利用したリポジトリ実装
+pub struct DieselAggregateRepository {
+    pub conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+}
+
+impl AggregateRepository for DieselAggregateRepository {
+    fn rollups(
+        &mut self,
+        key: &RollupKey,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<AggregateBucket>, DieselAggregateRepositoryError> {
+        let buckets = {
+            use self::schema::aggregate_buckets::dsl::*;
+            aggregate_buckets
+                .filter(endpoint.eq(key.endpoint))
+                .filter(interval.eq(key.interval.as_str()))
+                .filter(time_zone.eq(key.time_zone))
+                .filter(start_ms.ge(from_ms))
+                .filter(start_ms.lt(to_ms))
+                .order(start_ms.asc())
+                .select((start_ms, end_ms))
+                .load::<(i64, i64)>(&mut self.conn)?
+        };
+        let metrics = {
+            use self::schema::aggregate_metrics::dsl::*;
+            aggregate_metrics
+                .filter(endpoint.eq(key.endpoint))
+                .filter(interval.eq(key.interval.as_str()))
+                .filter(time_zone.eq(key.time_zone))
+                .filter(start_ms.ge(from_ms))
+                .filter(start_ms.lt(to_ms))
+                .select((
+                    start_ms,
+                    metric,
+                    sample_count,
+                    min_value,
+                    mean_value,
+                    max_value,
+                ))
+                .load::<AggregateMetricEntity>(&mut self.conn)?
+        };
+
+        let mut by_start: BTreeMap<i64, AggregateBucket> = buckets
+            .into_iter()
+            .map(|(start, end)| {
+                let bucket = AggregateBucket {
+                    start,
+                    end,
+                    metrics: BTreeMap::new(),
+                };
+                (start, bucket)
+            })
+            .collect();
+        // 知らない指標や区切りのない行は読み飛ばす
+        for row in metrics {
+            if let Some(bucket) = by_start.get_mut(&row.start_ms)
+                && let Some(metric) = AmbientMetric::parse(&row.metric)
+            {
+                bucket.metrics.insert(
+                    metric,
+                    MetricAggregate {
+                        count: usize::try_from(row.sample_count).unwrap_or_default(),
+                        min: row.min_value,
+                        mean: row.mean_value,
+                        max: row.max_value,
+                    },
+                );
+            }
+        }
+        Ok(by_start.into_values().collect())
+    }
+
+    fn store(
+        &mut self,
+        key: &RollupKey,
+        buckets: &[AggregateBucket],
+    ) -> Result<(), DieselAggregateRepositoryError> {
+        let interval = key.interval.as_str();
+        let bucket_rows: Vec<_> = buckets
+            .iter()
+            .map(|bucket| NewAggregateBucket {
+                endpoint: key.endpoint,
+                interval,
+                time_zone: key.time_zone,
+                start_ms: bucket.start,
+                end_ms: bucket.end,
+            })
+            .collect();
+        let metric_rows: Vec<_> = buckets
+            .iter()
+            .flat_map(|bucket| {
+                bucket
+                    .metrics
+                    .iter()
+                    .map(|(metric, aggregate)| NewAggregateMetric {
+                        endpoint: key.endpoint,
+                        interval,
+                        time_zone: key.time_zone,
+                        start_ms: bucket.start,
+                        metric: metric.as_str(),
+                        sample_count: i64::try_from(aggregate.count).unwrap_or(i64::MAX),
+                        min_value: aggregate.min,
+                        mean_value: aggregate.mean,
+                        max_value: aggregate.max,
+                    })
+            })
+            .collect();
+
+        self.conn.transaction(|conn| {
+            // SQLite の変数の上限を超えないよう分けて挿入する
+            for rows in bucket_rows.chunks(500) {
+                diesel::replace_into(schema::aggregate_buckets::table)
+                    .values(rows)
+                    .execute(conn)?;
+            }
+            for rows in metric_rows.chunks(500) {
+                diesel::replace_into(schema::aggregate_metrics::table)
+                    .values(rows)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::Pool;
+
+    #[test]
+    fn stored_rollups_are_read_back_per_key() {
+        let manager = ConnectionManager::<SqliteConnection>::new(
+            "file:memdb_aggregate_rollups?mode=memory&cache=shared",
+        );
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselAggregateRepository {
+            conn: pool.get().expect("conn"),
+        };
+        let key = RollupKey {
+            endpoint: "https://a.example",
+            interval: AggregateInterval::Daily,
+            time_zone: "Asia/Tokyo",
+        };
+        let aggregate = MetricAggregate {
+            count: 3,
+            min: 1.0,
+            mean: 2.0,
+            max: 3.0,
+        };
+        let buckets = [
+            AggregateBucket {
+                start: 0,
+                end: 100,
+                metrics: BTreeMap::from([(AmbientMetric::Temperature, aggregate)]),
+            },
+            // 測定値のない区切りも保存する
+            AggregateBucket {
+                start: 100,
+                end: 200,
+                metrics: BTreeMap::new(),
+            },
+        ];
+        repo.store(&key, &buckets).expect("store");
+        // 上書きしても重複しない
+        repo.store(&key, &buckets[..1]).expect("store again");
+
+        assert_eq!(repo.rollups(&key, 0, 200).expect("rollups"), buckets);
+        assert_eq!(repo.rollups(&key, 100, 200).expect("rollups"), buckets[1..]);
+        let other = RollupKey {
+            time_zone: "UTC",
+            ..key
+        };
+        assert_eq!(repo.rollups(&other, 0, 200).expect("rollups"), []);
+    }
+}
@@ -0,0 +1,378 @@
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::sqlite::SqliteConnection;
+
+use crate::domain::alert::{AlertEvent, AlertRule, Comparator, QuietHours};
+use crate::domain::ambient::AmbientMetric;
+
+// Diesel 用のスキーマ定義
+pub mod schema {
+    use diesel::table;
+
+    table! {
+        alert_rules (id) {
+            id -> Integer,
+            name -> Text,
+            metric -> Text,
+            comparator -> Text,
+            threshold -> Double,
+            sustained_ms -> BigInt,
+            quiet_start_minute -> Nullable<Integer>,
+            quiet_end_minute -> Nullable<Integer>,
+            enabled -> Bool,
+        }
+    }
+
+    table! {
+        alert_events (id) {
+            id -> Integer,
+            rule_id -> Integer,
+            fired_at -> BigInt,
+            cleared_at -> Nullable<BigInt>,
+            value -> Double,
+        }
+    }
+}
+
+#[derive(Queryable)]
+struct AlertRuleEntity {
+    pub id: i32,
+    pub name: String,
+    pub metric: String,
+    pub comparator: String,
+    pub threshold: f64,
+    pub sustained_ms: i64,
+    pub quiet_start_minute: Option<i32>,
+    pub quiet_end_minute: Option<i32>,
+    pub enabled: bool,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = schema::alert_rules, treat_none_as_null = true)]
+struct NewAlertRule<'a> {
+    pub name: &'a str,
+    pub metric: &'a str,
+    pub comparator: &'a str,
+    pub threshold: f64,
+    pub sustained_ms: i64,
+    pub quiet_start_minute: Option<i32>,
+    pub quiet_end_minute: Option<i32>,
+    pub enabled: bool,
+}
+
+impl<'a> From<&'a AlertRule> for NewAlertRule<'a> {
+    fn from(rule: &'a AlertRule) -> Self {
+        Self {
+            name: &rule.name,
+            metric: rule.metric.as_str(),
+            comparator: rule.comparator.as_str(),
+            threshold: rule.threshold,
+            sustained_ms: rule.sustained_ms,
+            quiet_start_minute: rule.quiet_hours.map(|quiet| quiet.start_minute.into()),
+            quiet_end_minute: rule.quiet_hours.map(|quiet| quiet.end_minute.into()),
+            enabled: rule.enabled,
+        }
+    }
+}
+
+#[derive(Queryable)]
+struct AlertEventEntity {
+    pub id: i32,
+    pub rule_id: i32,
+    pub fired_at: i64,
+    pub cleared_at: Option<i64>,
+    pub value: f64,
+}
+
+impl From<AlertEventEntity> for AlertEvent {
+    fn from(row: AlertEventEntity) -> Self {
+        Self {
+            id: row.id,
+            rule_id: row.rule_id,
+            fired_at_ms: row.fired_at,
+            cleared_at_ms: row.cleared_at,
+            value: row.value,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::alert_events)]
+struct NewAlertEvent {
+    pub rule_id: i32,
+    pub fired_at: i64,
+    pub value: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DieselAlertRepositoryError {
+    #[error("database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// リポジトリインターフェース（アラートの規則と発報の履歴）
+pub trait AlertRepository {
+    /// 規則を作成順に返す
+    fn rules(&mut self) -> Result<Vec<AlertRule>, DieselAlertRepositoryError>;
+    /// 規則を追加し、振った ID を返す
+    fn create_rule(&mut self, rule: &AlertRule) -> Result<i32, DieselAlertRepositoryError>;
+    /// `rule.id` の規則を置き換える。規則がなければ false
+    fn update_rule(&mut self, rule: &AlertRule) -> Result<bool, DieselAlertRepositoryError>;
+    /// 規則とその履歴を削除する。規則がなければ false
+    fn delete_rule(&mut self, id: i32) -> Result<bool, DieselAlertRepositoryError>;
+    /// 新しい順に最大 `limit` 件の履歴
+    fn events(&mut self, limit: i64) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError>;
+    /// 発報中（未解除）の履歴
+    fn active_events(&mut self) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError>;
+    /// 発報を記録する。その規則がすでに発報中なら記録せずに `None`
+    fn fire(
+        &mut self,
+        rule_id: i32,
+        fired_at_ms: i64,
+        value: f64,
+    ) -> Result<Option<AlertEvent>, DieselAlertRepositoryError>;
+    /// 発報中の履歴を解除する。発報中でなければ false
+    fn clear(
+        &mut self,
+        rule_id: i32,
+        cleared_at_ms: i64,
+    ) -> Result<bool, DieselAlertRepositoryError>;
+}
+
+/// Diesel を利用したリポジトリ実装
+pub struct DieselAlertRepository {
+    pub conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+}
+
+impl AlertRepository for DieselAlertRepository {
+    fn rules(&mut self) -> Result<Vec<AlertRule>, DieselAlertRepositoryError> {
+        use self::schema::alert_rules::dsl::*;
+
+        let rows = alert_rules
+            .order(id.asc())
+            .select((
+                id,
+                name,
+                metric,
+                comparator,
+                threshold,
+                sustained_ms,
+                quiet_start_minute,
+                quiet_end_minute,
+                enabled,
+            ))
+            .load::<AlertRuleEntity>(&mut self.conn)?;
+
+        // 知らない指標や比べ方の行は読み飛ばす
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let quiet_hours = match (row.quiet_start_minute, row.quiet_end_minute) {
+                    (Some(start), Some(end)) => Some(QuietHours {
+                        start_minute: u16::try_from(start).ok()?,
+                        end_minute: u16::try_from(end).ok()?,
+                    }),
+                    _ => None,
+                };
+                Some(AlertRule {
+                    id: row.id,
+                    name: row.name,
+                    metric: AmbientMetric::parse(&row.metric)?,
+                    comparator: Comparator::parse(&row.comparator)?,
+                    threshold: row.threshold,
+                    sustained_ms: row.sustained_ms,
+                    quiet_hours,
+                    enabled: row.enabled,
+                })
+            })
+            .collect())
+    }
+
+    fn create_rule(&mut self, rule: &AlertRule) -> Result<i32, DieselAlertRepositoryError> {
+        use self::schema::alert_rules::dsl::*;
+
+        self.conn.transaction(|conn| {
+            diesel::insert_into(alert_rules)
+                .values(&NewAlertRule::from(rule))
+                .execute(conn)?;
+            // AUTOINCREMENT なので、いま挿入した行の ID が最大になる
+            Ok(alert_rules.select(id).order(id.desc()).first(conn)?)
+        })
+    }
+
+    fn update_rule(&mut self, rule: &AlertRule) -> Result<bool, DieselAlertRepositoryError> {
+        use self::schema::alert_rules::dsl::*;
+
+        let updated = diesel::update(alert_rules.filter(id.eq(rule.id)))
+            .set(&NewAlertRule::from(rule))
+            .execute(&mut self.conn)?;
+        Ok(updated > 0)
+    }
+
+    fn delete_rule(&mut self, rule: i32) -> Result<bool, DieselAlertRepositoryError> {
+        self.conn.transaction(|conn| {
+            {
+                use self::schema::alert_events::dsl::*;
+                diesel::delete(alert_events.filter(rule_id.eq(rule))).execute(conn)?;
+            }
+            use self::schema::alert_rules::dsl::*;
+            let deleted = diesel::delete(alert_rules.filter(id.eq(rule))).execute(conn)?;
+            Ok(deleted > 0)
+        })
+    }
+
+    fn events(&mut self, limit: i64) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError> {
+        use self::schema::alert_events::dsl::*;
+
+        let rows = alert_events
+            .order((fired_at.desc(), id.desc()))
+            .limit(limit)
+            .select((id, rule_id, fired_at, cleared_at, value))
+            .load::<AlertEventEntity>(&mut self.conn)?;
+        Ok(rows.into_iter().map(AlertEvent::from).collect())
+    }
+
+    fn active_events(&mut self) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError> {
+        use self::schema::alert_events::dsl::*;
+
+        let rows = alert_events
+            .filter(cleared_at.is_null())
+            .select((id, rule_id, fired_at, cleared_at, value))
+            .load::<AlertEventEntity>(&mut self.conn)?;
+        Ok(rows.into_iter().map(AlertEvent::from).collect())
+    }
+
+    fn fire(
+        &mut self,
+        rule: i32,
+        fired_at_ms: i64,
+        fired_value: f64,
+    ) -> Result<Option<AlertEvent>, DieselAlertRepositoryError> {
+        use self::schema::alert_events::dsl::*;
+
+        // 発報中の履歴は規則ごとに 1 件だけ（部分一意インデックス）なので、重複は挿入されない
+        let inserted = diesel::insert_or_ignore_into(alert_events)
+            .values(&NewAlertEvent {
+                rule_id: rule,
+                fired_at: fired_at_ms,
+                value: fired_value,
+            })
+            .execute(&mut self.conn)?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+        let row = alert_events
+            .filter(rule_id.eq(rule))
+            .filter(cleared_at.is_null())
+            .select((id, rule_id, fired_at, cleared_at, value))
+            .first::<AlertEventEntity>(&mut self.conn)?;
+        Ok(Some(row.into()))
+    }
+
+    fn clear(&mut self, rule: i32, cleared_at_ms: i64) -> Result<bool, DieselAlertRepositoryError> {
+        use self::schema::alert_events::dsl::*;
+
+        let updated = diesel::update(
+            alert_events
+                .filter(rule_id.eq(rule))
+                .filter(cleared_at.is_null()),
+        )
+        .set(cleared_at.eq(Some(cleared_at_ms)))
+        .execute(&mut self.conn)?;
+        Ok(updated > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::Pool;
+
+    fn repo(name: &str) -> DieselAlertRepository {
+        let manager = ConnectionManager::<SqliteConnection>::new(format!(
+            "file:{name}?mode=memory&cache=shared"
+        ));
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        DieselAlertRepository {
+            conn: pool.get().expect("conn"),
+        }
+    }
+
+    fn rule() -> AlertRule {
+        AlertRule {
+            id: 0,
+            name: "Too humid".into(),
+            metric: AmbientMetric::Humidity,
+            comparator: Comparator::Ge,
+            threshold: 70.0,
+            sustained_ms: 60_000,
+            quiet_hours: Some(QuietHours {
+                start_minute: 22 * 60,
+                end_minute: 7 * 60,
+            }),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn rule_crud_roundtrip() {
+        let mut repo = repo("memdb_alert_rules");
+        let id = repo.create_rule(&rule()).expect("create ok");
+        let created = AlertRule { id, ..rule() };
+        assert_eq!(repo.rules().expect("rules"), std::slice::from_ref(&created));
+
+        let updated = AlertRule {
+            quiet_hours: None,
+            enabled: false,
+            ..created
+        };
+        assert!(repo.update_rule(&updated).expect("update ok"));
+        assert_eq!(repo.rules().expect("rules"), std::slice::from_ref(&updated));
+        assert!(
+            !repo
+                .update_rule(&AlertRule {
+                    id: id + 1,
+                    ..updated
+                })
+                .expect("update ok")
+        );
+
+        repo.fire(id, 1_000, 75.0).expect("fire ok");
+        assert!(repo.delete_rule(id).expect("delete ok"));
+        assert!(!repo.delete_rule(id).expect("delete ok"));
+        assert_eq!(repo.rules().expect("rules"), []);
+        assert_eq!(repo.events(10).expect("events"), []);
+    }
+
+    #[test]
+    fn fire_is_deduplicated_until_cleared() {
+        let mut repo = repo("memdb_alert_events");
+        let id = repo.create_rule(&rule()).expect("create ok");
+
+        let fired = repo.fire(id, 1_000, 75.0).expect("fire ok").expect("fired");
+        assert_eq!(fired.cleared_at_ms, None);
+        assert_eq!(repo.fire(id, 2_000, 80.0).expect("fire ok"), None);
+        assert_eq!(
+            repo.active_events().expect("active"),
+            std::slice::from_ref(&fired)
+        );
+
+        assert!(repo.clear(id, 3_000).expect("clear ok"));
+        assert!(!repo.clear(id, 4_000).expect("clear ok"));
+        assert_eq!(repo.active_events().expect("active"), []);
+
+        let refired = repo.fire(id, 5_000, 72.0).expect("fire ok").expect("fired");
+        let events = repo.events(10).expect("events");
+        assert_eq!(
+            events,
+            [
+                refired,
+                AlertEvent {
+                    cleared_at_ms: Some(3_000),
+                    ..fired
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::sqlite::SqliteConnection;
+
+use crate::domain::ambient::{AmbientReading, CachedSpan};
+use crate::domain::time_range::TimeRange;
+
+// Diesel 用のスキーマ定義
+pub mod schema {
+    use diesel::table;
+
+    table! {
+        ambient_readings (endpoint, id) {
+            endpoint -> Text,
+            id -> Text,
+            recorded_at -> BigInt,
+            temperature -> Nullable<Double>,
+            humidity -> Nullable<Double>,
+            illumination -> Nullable<Double>,
+        }
+    }
+
+    table! {
+        ambient_cached_spans (id) {
+            id -> Integer,
+            endpoint -> Text,
+            start_ms -> BigInt,
+            end_ms -> BigInt,
+            interval_ns -> BigInt,
+        }
+    }
+
+    table! {
+        ambient_sync (endpoint) {
+            endpoint -> Text,
+            last_synced_at -> BigInt,
+        }
+    }
+}
+
+/// 1 回の INSERT にまとめる行数（SQLite のバインド変数の上限を超えないように）
+const INSERT_BATCH: usize = 500;
+
+/// 値の列は NaN を NULL として持つ（SQLite は NaN を NULL として保存する）
+#[derive(Queryable)]
+struct AmbientReadingEntity {
+    pub id: String,
+    pub recorded_at: i64,
+    pub temperature: Option<f64>,
+    pub humidity: Option<f64>,
+    pub illumination: Option<f64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::ambient_readings)]
+struct NewAmbientReading<'a> {
+    pub endpoint: &'a str,
+    pub id: &'a str,
+    pub recorded_at: i64,
+    pub temperature: Option<f64>,
+    pub humidity: Option<f64>,
+    pub illumination: Option<f64>,
+}
+
+/// NaN を NULL にして保存する
+fn to_column(value: f64) -> Option<f64> {
+    (!value.is_nan()).then_some(value)
+}
+
+/// NULL は NaN として読み出す
+fn from_column(value: Option<f64>) -> f64 {
+    value.unwrap_or(f64::NAN)
+}
+
+#[derive(Queryable)]
+struct CachedSpanEntity {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub interval_ns: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::ambient_cached_spans)]
+struct NewCachedSpan<'a> {
+    pub endpoint: &'a str,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub interval_ns: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::ambient_sync)]
+struct NewAmbientSync<'a> {
+    pub endpoint: &'a str,
+    pub last_synced_at: i64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DieselAmbientReadingRepositoryError {
+    #[error("database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// リポジトリインターフェース（接続先ごとの測定値と取得済み期間のキャッシュ）
+pub trait AmbientReadingRepository {
+    /// `range` 内の測定値を記録時刻の順に返す
+    fn readings(
+        &mut self,
+        endpoint: &str,
+        range: &TimeRange,
+    ) -> Result<Vec<AmbientReading>, DieselAmbientReadingRepositoryError>;
+    /// `range` と重なる取得済み期間
+    fn spans(
+        &mut self,
+        endpoint: &str,
+        range: &TimeRange,
+    ) -> Result<Vec<CachedSpan>, DieselAmbientReadingRepositoryError>;
+    /// 測定値を保存（同じ ID は上書き）し、`span` があれば取得済みとして記録する。
+    /// `synced_at_ms` は最後にサーバーから取得した時刻として残す
+    fn store(
+        &mut self,
+        endpoint: &str,
+        readings: &[AmbientReading],
+        span: Option<&CachedSpan>,
+        synced_at_ms: i64,
+    ) -> Result<(), DieselAmbientReadingRepositoryError>;
+    /// 最後にサーバーから取得した時刻（Unix エポックからのミリ秒）
+    fn last_synced_at(
+        &mut self,
+        endpoint: &str,
+    ) -> Result<Option<i64>, DieselAmbientReadingRepositoryError>;
+}
+
+/// Diesel を利用したリポジトリ実装
+pub struct DieselAmbientReadingRepository {
+    pub conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+}
+
+impl AmbientReadingRepository for DieselAmbientReadingRepository {
+    fn readings(
+        &mut self,
+        endpoint_url: &str,
+        range: &TimeRange,
+    ) -> Result<Vec<AmbientReading>, DieselAmbientReadingRepositoryError> {
+        use self::schema::ambient_readings::dsl::*;
+
+        let millis = range.to_millis();
+        let rows = ambient_readings
+            .filter(endpoint.eq(endpoint_url))
+            .filter(recorded_at.ge(millis.start))
+            .filter(recorded_at.lt(millis.end))
+            .order((recorded_at.asc(), id.asc()))
+            .select((id, recorded_at, temperature, humidity, illumination))
+            .load::<AmbientReadingEntity>(&mut self.conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AmbientReading {
+                id: row.id,
+                recorded_at_ms: row.recorded_at,
+                temperature: from_column(row.temperature),
+                humidity: from_column(row.humidity),
+                illumination: from_column(row.illumination),
+            })
+            .collect())
+    }
+
+    fn spans(
+        &mut self,
+        endpoint_url: &str,
+        range: &TimeRange,
+    ) -> Result<Vec<CachedSpan>, DieselAmbientReadingRepositoryError> {
+        use self::schema::ambient_cached_spans::dsl::*;
+
+        let millis = range.to_millis();
+        let rows = ambient_cached_spans
+            .filter(endpoint.eq(endpoint_url))
+            .filter(start_ms.lt(millis.end))
+            .filter(end_ms.gt(millis.start))
+            .order(start_ms.asc())
+            .select((start_ms, end_ms, interval_ns))
+            .load::<CachedSpanEntity>(&mut self.conn)?;
+
+        // 壊れた行（開始 >= 終了）は読み飛ばす
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(CachedSpan {
+                    range: TimeRange::from_millis(row.start_ms, row.end_ms).ok()?,
+                    interval: Duration::from_nanos(u64::try_from(row.interval_ns).ok()?),
+                })
+            })
+            .collect())
+    }
+
+    fn store(
+        &mut self,
+        endpoint_url: &str,
+        readings: &[AmbientReading],
+        span: Option<&CachedSpan>,
+        synced_at_ms: i64,
+    ) -> Result<(), DieselAmbientReadingRepositoryError> {
+        self.conn.transaction(|conn| {
+            for batch in readings.chunks(INSERT_BATCH) {
+                let rows: Vec<_> = batch
+                    .iter()
+                    .map(|reading| NewAmbientReading {
+                        endpoint: endpoint_url,
+                        id: &reading.id,
+                        recorded_at: reading.recorded_at_ms,
+                        temperature: to_column(reading.temperature),
+                        humidity: to_column(reading.humidity),
+                        illumination: to_column(reading.illumination),
+                    })
+                    .collect();
+                diesel::replace_into(schema::ambient_readings::table)
+                    .values(&rows)
+                    .execute(conn)?;
+            }
+
+            if let Some(span) = span {
+                use self::schema::ambient_cached_spans::dsl::*;
+
+                let millis = span.range.to_millis();
+                let interval = i64::try_from(span.interval.as_nanos()).unwrap_or(i64::MAX);
+                // 新しい期間に含まれ、解像度も同じか粗い期間は不要になる
+                diesel::delete(
+                    ambient_cached_spans
+                        .filter(endpoint.eq(endpoint_url))
+                        .filter(start_ms.ge(millis.start))
+                        .filter(end_ms.le(millis.end))
+                        .filter(interval_ns.ge(interval)),
+                )
+                .execute(conn)?;
+                diesel::insert_into(ambient_cached_spans)
+                    .values(&NewCachedSpan {
+                        endpoint: endpoint_url,
+                        start_ms: millis.start,
+                        end_ms: millis.end,
+                        interval_ns: interval,
+                    })
+                    .execute(conn)?;
+            }
+
+            diesel::replace_into(schema::ambient_sync::table)
+                .values(&NewAmbientSync {
+                    endpoint: endpoint_url,
+                    last_synced_at: synced_at_ms,
+                })
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn last_synced_at(
+        &mut self,
+        endpoint_url: &str,
+    ) -> Result<Option<i64>, DieselAmbientReadingRepositoryError> {
+        use self::schema::ambient_sync::dsl::*;
+
+        Ok(ambient_sync
+            .filter(endpoint.eq(endpoint_url))
+            .select(last_synced_at)
+            .first::<i64>(&mut self.conn)
+            .optional()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::Pool;
+
+    const ENDPOINT: &str = "https://tempgrpcd.example";
+
+    fn repo(name: &str) -> DieselAmbientReadingRepository {
+        let manager = ConnectionManager::<SqliteConnection>::new(format!(
+            "file:{name}?mode=memory&cache=shared"
+        ));
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        DieselAmbientReadingRepository {
+            conn: pool.get().expect("conn"),
+        }
+    }
+
+    fn reading(recorded_at_ms: i64, temperature: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 40.0,
+            illumination: 100.0,
+        }
+    }
+
+    fn millis(start: i64, end: i64) -> TimeRange {
+        TimeRange::from_millis(start, end).unwrap()
+    }
+
+    #[test]
+    fn stores_and_reads_readings_per_endpoint() {
+        let mut repo = repo("memdb_ambient_readings");
+        repo.store(
+            ENDPOINT,
+            &[reading(2_000, 21.0), reading(1_000, 20.0)],
+            None,
+            100,
+        )
+        .expect("store");
+        repo.store("https://other.example", &[reading(1_500, 30.0)], None, 0)
+            .expect("store");
+        // 同じ ID は上書きされる
+        repo.store(ENDPOINT, &[reading(2_000, 22.0)], None, 200)
+            .expect("store");
+        assert_eq!(repo.last_synced_at(ENDPOINT).expect("sync"), Some(200));
+        assert_eq!(
+            repo.last_synced_at("https://unknown.example")
+                .expect("sync"),
+            None
+        );
+
+        let got = repo.readings(ENDPOINT, &millis(0, 3_000)).expect("read");
+        assert_eq!(got, vec![reading(1_000, 20.0), reading(2_000, 22.0)]);
+
+        // 終了時刻ちょうどの測定値は含まない
+        let got = repo
+            .readings(ENDPOINT, &millis(1_000, 2_000))
+            .expect("read");
+        assert_eq!(got, vec![reading(1_000, 20.0)]);
+    }
+
+    #[test]
+    fn stores_nan_readings_with_their_span() {
+        let mut repo = repo("memdb_ambient_nan");
+        let span = CachedSpan {
+            range: millis(0, 3_000),
+            interval: Duration::from_secs(1),
+        };
+        let broken = AmbientReading {
+            humidity: f64::NAN,
+            ..reading(2_000, f64::NAN)
+        };
+        repo.store(ENDPOINT, &[reading(1_000, 20.0), broken], Some(&span), 100)
+            .expect("store");
+
+        let got = repo.readings(ENDPOINT, &millis(0, 3_000)).expect("read");
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0], reading(1_000, 20.0));
+        assert!(got[1].temperature.is_nan());
+        assert!(got[1].humidity.is_nan());
+        assert_eq!(got[1].illumination, 100.0);
+        assert_eq!(
+            repo.spans(ENDPOINT, &millis(0, 3_000)).expect("spans"),
+            [span]
+        );
+        assert_eq!(repo.last_synced_at(ENDPOINT).expect("sync"), Some(100));
+    }
+
+    #[test]
+    fn records_spans_and_drops_redundant_ones() {
+        let mut repo = repo("memdb_ambient_spans");
+        let span = |start, end, secs| CachedSpan {
+            range: millis(start, end),
+            interval: Duration::from_secs(secs),
+        };
+        repo.store(ENDPOINT, &[], Some(&span(1_000, 2_000, 60)), 0)
+            .expect("store");
+        repo.store(ENDPOINT, &[], Some(&span(5_000, 6_000, 1)), 0)
+            .expect("store");
+        // 1 つ目を含み、解像度も細かいので 1 つ目は消える
+        repo.store(ENDPOINT, &[], Some(&span(0, 3_000, 10)), 0)
+            .expect("store");
+
+        let got = repo.spans(ENDPOINT, &millis(0, 10_000)).expect("spans");
+        assert_eq!(got, vec![span(0, 3_000, 10), span(5_000, 6_000, 1)]);
+
+        let got = repo.spans(ENDPOINT, &millis(3_000, 5_000)).expect("spans");
+        assert!(got.is_empty());
+        assert!(
+            repo.spans("https://other.example", &millis(0, 10_000))
+                .expect("spans")
+                .is_empty()
+        );
+    }
+}
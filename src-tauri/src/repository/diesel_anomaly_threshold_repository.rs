@@ -0,0 +1,138 @@
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::sqlite::SqliteConnection;
+
+use crate::domain::ambient::AmbientMetric;
+use crate::domain::anomaly::AnomalyThresholds;
+
+// Diesel 用のスキーマ定義
+pub mod schema {
+    use diesel::table;
+
+    table! {
+        anomaly_thresholds (metric) {
+            metric -> Text,
+            window_size -> Integer,
+            z_score -> Double,
+            iqr_multiplier -> Double,
+            rate_of_change -> Double,
+        }
+    }
+}
+
+#[derive(Queryable)]
+struct AnomalyThresholdEntity {
+    pub metric: String,
+    pub window_size: i32,
+    pub z_score: f64,
+    pub iqr_multiplier: f64,
+    pub rate_of_change: f64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::anomaly_thresholds)]
+struct NewAnomalyThreshold<'a> {
+    pub metric: &'a str,
+    pub window_size: i32,
+    pub z_score: f64,
+    pub iqr_multiplier: f64,
+    pub rate_of_change: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DieselAnomalyThresholdRepositoryError {
+    #[error("database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// リポジトリインターフェース（指標ごとの異常検出のしきい値）
+pub trait AnomalyThresholdRepository {
+    /// 保存済みのしきい値を指標の順に返す。保存されていない指標は含まない
+    fn get(&mut self) -> Result<Vec<AnomalyThresholds>, DieselAnomalyThresholdRepositoryError>;
+    /// しきい値を保存する（同じ指標は上書き）
+    fn set(
+        &mut self,
+        thresholds: &[AnomalyThresholds],
+    ) -> Result<(), DieselAnomalyThresholdRepositoryError>;
+}
+
+/// Diesel を利用したリポジトリ実装
+pub struct DieselAnomalyThresholdRepository {
+    pub conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+}
+
+impl AnomalyThresholdRepository for DieselAnomalyThresholdRepository {
+    fn get(&mut self) -> Result<Vec<AnomalyThresholds>, DieselAnomalyThresholdRepositoryError> {
+        use self::schema::anomaly_thresholds::dsl::*;
+
+        let rows = anomaly_thresholds
+            .select((metric, window_size, z_score, iqr_multiplier, rate_of_change))
+            .load::<AnomalyThresholdEntity>(&mut self.conn)?;
+
+        // 知らない指標や壊れた行は読み飛ばす
+        let mut thresholds: Vec<AnomalyThresholds> = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(AnomalyThresholds {
+                    metric: AmbientMetric::parse(&row.metric)?,
+                    window: u32::try_from(row.window_size).ok()?,
+                    z_score: row.z_score,
+                    iqr_multiplier: row.iqr_multiplier,
+                    rate_of_change: row.rate_of_change,
+                })
+            })
+            .collect();
+        thresholds.sort_by_key(|thresholds| thresholds.metric);
+        Ok(thresholds)
+    }
+
+    fn set(
+        &mut self,
+        thresholds: &[AnomalyThresholds],
+    ) -> Result<(), DieselAnomalyThresholdRepositoryError> {
+        let rows: Vec<_> = thresholds
+            .iter()
+            .map(|thresholds| NewAnomalyThreshold {
+                metric: thresholds.metric.as_str(),
+                window_size: i32::try_from(thresholds.window).unwrap_or(i32::MAX),
+                z_score: thresholds.z_score,
+                iqr_multiplier: thresholds.iqr_multiplier,
+                rate_of_change: thresholds.rate_of_change,
+            })
+            .collect();
+        diesel::replace_into(schema::anomaly_thresholds::table)
+            .values(&rows)
+            .execute(&mut self.conn)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::Pool;
+
+    #[test]
+    fn set_overwrites_thresholds_per_metric() {
+        let manager = ConnectionManager::<SqliteConnection>::new(
+            "file:memdb_anomaly_thresholds?mode=memory&cache=shared",
+        );
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselAnomalyThresholdRepository {
+            conn: pool.get().expect("conn"),
+        };
+        assert_eq!(repo.get().expect("get ok"), []);
+
+        let humidity = AnomalyThresholds::default_for(AmbientMetric::Humidity);
+        let temperature = AnomalyThresholds::default_for(AmbientMetric::Temperature);
+        repo.set(&[humidity, temperature]).expect("set ok");
+        let stricter = AnomalyThresholds {
+            z_score: 2.0,
+            ..humidity
+        };
+        repo.set(&[stricter]).expect("set ok");
+
+        assert_eq!(repo.get().expect("get ok"), [temperature, stricter]);
+    }
+}
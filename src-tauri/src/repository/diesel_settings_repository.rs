@@ -1,8 +1,12 @@
+use std::str::FromStr as _;
+
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, PooledConnection};
 use diesel::sqlite::SqliteConnection;
 
-use crate::domain::settings::Settings;
+use crate::domain::settings::{
+    AuthMode, DEFAULT_MAX_SAMPLES, DEFAULT_MIN_SAMPLES, DEFAULT_POLL_INTERVAL_MS, Settings,
+};
 use crate::infrastructure::crypto::{Crypto, CryptoBox};
 use crate::infrastructure::keystore::KeyStore;
 
@@ -17,7 +21,24 @@ pub mod schema {
             encrypted_access_token -> Blob,
             encrypted_access_token_nonce -> Blob,
             use_proxies -> Bool,
-            proxy_url -> Text
+            proxy_url -> Text,
+            auth_mode -> Text,
+            auth_header_name -> Text,
+            auth_header_prefix -> Text,
+            proxy_username -> Text,
+            encrypted_proxy_password -> Nullable<Blob>,
+            encrypted_proxy_password_nonce -> Nullable<Blob>,
+            ca_bundle_pem -> Text,
+            ca_bundle_path -> Text,
+            disable_webpki_roots -> Bool,
+            connect_timeout_ms -> BigInt,
+            request_timeout_ms -> BigInt,
+            keepalive_interval_ms -> BigInt,
+            keepalive_timeout_ms -> BigInt,
+            keepalive_while_idle -> Bool,
+            min_samples -> BigInt,
+            max_samples -> BigInt,
+            poll_interval_ms -> BigInt,
         }
     }
 }
@@ -30,6 +51,23 @@ struct SettingEntity {
     pub encrypted_access_token_nonce: Vec<u8>,
     pub use_proxies: bool,
     pub proxy_url: String,
+    pub auth_mode: String,
+    pub auth_header_name: String,
+    pub auth_header_prefix: String,
+    pub proxy_username: String,
+    pub encrypted_proxy_password: Option<Vec<u8>>,
+    pub encrypted_proxy_password_nonce: Option<Vec<u8>>,
+    pub ca_bundle_pem: String,
+    pub ca_bundle_path: String,
+    pub disable_webpki_roots: bool,
+    pub connect_timeout_ms: i64,
+    pub request_timeout_ms: i64,
+    pub keepalive_interval_ms: i64,
+    pub keepalive_timeout_ms: i64,
+    pub keepalive_while_idle: bool,
+    pub min_samples: i64,
+    pub max_samples: i64,
+    pub poll_interval_ms: i64,
 }
 
 #[derive(Insertable)]
@@ -41,6 +79,23 @@ struct NewSetting<'a> {
     pub encrypted_access_token_nonce: &'a [u8],
     pub use_proxies: bool,
     pub proxy_url: &'a str,
+    pub auth_mode: &'a str,
+    pub auth_header_name: &'a str,
+    pub auth_header_prefix: &'a str,
+    pub proxy_username: &'a str,
+    pub encrypted_proxy_password: Option<&'a [u8]>,
+    pub encrypted_proxy_password_nonce: Option<&'a [u8]>,
+    pub ca_bundle_pem: &'a str,
+    pub ca_bundle_path: &'a str,
+    pub disable_webpki_roots: bool,
+    pub connect_timeout_ms: i64,
+    pub request_timeout_ms: i64,
+    pub keepalive_interval_ms: i64,
+    pub keepalive_timeout_ms: i64,
+    pub keepalive_while_idle: bool,
+    pub min_samples: i64,
+    pub max_samples: i64,
+    pub poll_interval_ms: i64,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -51,6 +106,9 @@ pub enum DieselSettingsRepositoryError {
     Crypto(#[from] crate::infrastructure::crypto::CryptoError),
     #[error("keystore error: {0}")]
     Keystore(#[from] crate::infrastructure::keystore::KeystoreError),
+    /// 保存されている認証方式が読めない。勝手に既定値で補うとトークンを意図しない相手に送りかねない
+    #[error("unknown auth mode stored: {0}")]
+    UnknownAuthMode(String),
 }
 
 /// リポジトリインターフェース（設定の取得・保存）
@@ -81,6 +139,13 @@ impl SettingsRepository for DieselSettingsRepository {
                 &entity.encrypted_access_token,
                 &entity.encrypted_access_token_nonce,
             )?;
+            let proxy_password = match (
+                &entity.encrypted_proxy_password,
+                &entity.encrypted_proxy_password_nonce,
+            ) {
+                (Some(ciphertext), Some(nonce)) => Some(crypt.decrypt_string(ciphertext, nonce)?),
+                _ => None,
+            };
 
             Ok(Some(Settings {
                 id: entity.id,
@@ -88,28 +153,63 @@ impl SettingsRepository for DieselSettingsRepository {
                 access_token,
                 use_proxies: entity.use_proxies,
                 proxy_url: entity.proxy_url,
+                auth_mode: AuthMode::from_str(&entity.auth_mode).map_err(|_| {
+                    DieselSettingsRepositoryError::UnknownAuthMode(entity.auth_mode)
+                })?,
+                auth_header_name: entity.auth_header_name,
+                auth_header_prefix: entity.auth_header_prefix,
+                proxy_username: entity.proxy_username,
+                has_proxy_password: proxy_password.is_some(),
+                proxy_password,
+                ca_bundle_pem: entity.ca_bundle_pem,
+                ca_bundle_path: entity.ca_bundle_path,
+                disable_webpki_roots: entity.disable_webpki_roots,
+                connect_timeout_ms: millis_from_column(entity.connect_timeout_ms),
+                request_timeout_ms: millis_from_column(entity.request_timeout_ms),
+                keepalive_interval_ms: millis_from_column(entity.keepalive_interval_ms),
+                keepalive_timeout_ms: millis_from_column(entity.keepalive_timeout_ms),
+                keepalive_while_idle: entity.keepalive_while_idle,
+                min_samples: u32::try_from(entity.min_samples).unwrap_or(DEFAULT_MIN_SAMPLES),
+                max_samples: u32::try_from(entity.max_samples).unwrap_or(DEFAULT_MAX_SAMPLES),
+                poll_interval_ms: u32::try_from(entity.poll_interval_ms)
+                    .unwrap_or(DEFAULT_POLL_INTERVAL_MS),
             }))
         } else {
-            let (ciphertext, nonce) = crypt.encrypt_string("")?;
+            let defaults = Settings {
+                id: 1,
+                ..Settings::default()
+            };
+            let (ciphertext, nonce) = crypt.encrypt_string(&defaults.access_token)?;
 
             diesel::insert_into(settings)
                 .values(&NewSetting {
                     id: 1,
-                    url: "",
+                    url: &defaults.url,
                     encrypted_access_token: &ciphertext,
                     encrypted_access_token_nonce: &nonce,
-                    use_proxies: false,
-                    proxy_url: "",
+                    use_proxies: defaults.use_proxies,
+                    proxy_url: &defaults.proxy_url,
+                    auth_mode: defaults.auth_mode.as_str(),
+                    auth_header_name: &defaults.auth_header_name,
+                    auth_header_prefix: &defaults.auth_header_prefix,
+                    proxy_username: &defaults.proxy_username,
+                    encrypted_proxy_password: None,
+                    encrypted_proxy_password_nonce: None,
+                    ca_bundle_pem: &defaults.ca_bundle_pem,
+                    ca_bundle_path: &defaults.ca_bundle_path,
+                    disable_webpki_roots: defaults.disable_webpki_roots,
+                    connect_timeout_ms: defaults.connect_timeout_ms.into(),
+                    request_timeout_ms: defaults.request_timeout_ms.into(),
+                    keepalive_interval_ms: defaults.keepalive_interval_ms.into(),
+                    keepalive_timeout_ms: defaults.keepalive_timeout_ms.into(),
+                    keepalive_while_idle: defaults.keepalive_while_idle,
+                    min_samples: defaults.min_samples.into(),
+                    max_samples: defaults.max_samples.into(),
+                    poll_interval_ms: defaults.poll_interval_ms.into(),
                 })
                 .execute(&mut self.conn)?;
 
-            Ok(Some(Settings {
-                id: 1,
-                url: "".to_string(),
-                access_token: "".to_string(),
-                use_proxies: false,
-                proxy_url: "".to_string(),
-            }))
+            Ok(Some(defaults))
         }
     }
 
@@ -121,6 +221,21 @@ impl SettingsRepository for DieselSettingsRepository {
 
         let (ciphertext, nonce) = crypt.encrypt_string(&setting.access_token)?;
 
+        let (proxy_password_ciphertext, proxy_password_nonce) = match &setting.proxy_password {
+            // None は「変更しない」ので、保存済みの暗号文をそのまま引き継ぐ
+            None => settings
+                .filter(id.eq(1))
+                .select((encrypted_proxy_password, encrypted_proxy_password_nonce))
+                .first::<(Option<Vec<u8>>, Option<Vec<u8>>)>(&mut self.conn)
+                .optional()?
+                .unwrap_or((None, None)),
+            Some(password) if password.is_empty() => (None, None),
+            Some(password) => {
+                let (ciphertext, nonce) = crypt.encrypt_string(password)?;
+                (Some(ciphertext), Some(nonce))
+            }
+        };
+
         // 既存レコードの削除（簡易アップサート）
         diesel::delete(settings.filter(id.eq(1))).execute(&mut self.conn)?;
         let new_setting = NewSetting {
@@ -130,6 +245,23 @@ impl SettingsRepository for DieselSettingsRepository {
             encrypted_access_token_nonce: &nonce,
             use_proxies: setting.use_proxies,
             proxy_url: &setting.proxy_url,
+            auth_mode: setting.auth_mode.as_str(),
+            auth_header_name: &setting.auth_header_name,
+            auth_header_prefix: &setting.auth_header_prefix,
+            proxy_username: &setting.proxy_username,
+            encrypted_proxy_password: proxy_password_ciphertext.as_deref(),
+            encrypted_proxy_password_nonce: proxy_password_nonce.as_deref(),
+            ca_bundle_pem: &setting.ca_bundle_pem,
+            ca_bundle_path: &setting.ca_bundle_path,
+            disable_webpki_roots: setting.disable_webpki_roots,
+            connect_timeout_ms: setting.connect_timeout_ms.into(),
+            request_timeout_ms: setting.request_timeout_ms.into(),
+            keepalive_interval_ms: setting.keepalive_interval_ms.into(),
+            keepalive_timeout_ms: setting.keepalive_timeout_ms.into(),
+            keepalive_while_idle: setting.keepalive_while_idle,
+            min_samples: setting.min_samples.into(),
+            max_samples: setting.max_samples.into(),
+            poll_interval_ms: setting.poll_interval_ms.into(),
         };
         diesel::insert_into(settings)
             .values(&new_setting)
@@ -138,10 +270,15 @@ impl SettingsRepository for DieselSettingsRepository {
     }
 }
 
+/// 負の値など u32 に収まらない値は 0（無効）として扱う
+fn millis_from_column(value: i64) -> u32 {
+    u32::try_from(value).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::settings::Settings;
+    use crate::domain::settings::{AuthMode, Settings};
     use crate::infrastructure::keystore::KeyStore;
     use diesel::connection::SimpleConnection;
     use diesel::r2d2::ConnectionManager;
@@ -170,7 +307,24 @@ mod tests {
                 encrypted_access_token BLOB NOT NULL,
                 encrypted_access_token_nonce BLOB NOT NULL,
                 use_proxies BOOLEAN NOT NULL,
-                proxy_url TEXT NOT NULL
+                proxy_url TEXT NOT NULL,
+                auth_mode TEXT NOT NULL DEFAULT 'bearer',
+                auth_header_name TEXT NOT NULL DEFAULT '',
+                auth_header_prefix TEXT NOT NULL DEFAULT '',
+                proxy_username TEXT NOT NULL DEFAULT '',
+                encrypted_proxy_password BLOB,
+                encrypted_proxy_password_nonce BLOB,
+                ca_bundle_pem TEXT NOT NULL DEFAULT '',
+                ca_bundle_path TEXT NOT NULL DEFAULT '',
+                disable_webpki_roots BOOLEAN NOT NULL DEFAULT 0,
+                connect_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                request_timeout_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_interval_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                keepalive_while_idle BOOLEAN NOT NULL DEFAULT 0,
+                min_samples INTEGER NOT NULL DEFAULT 100,
+                max_samples INTEGER NOT NULL DEFAULT 5000,
+                poll_interval_ms INTEGER NOT NULL DEFAULT 10000
             );",
         )
         .unwrap();
@@ -184,6 +338,23 @@ mod tests {
             access_token: "tok".into(),
             use_proxies: true,
             proxy_url: "http://p".into(),
+            auth_mode: AuthMode::Custom,
+            auth_header_name: "x-api-key".into(),
+            auth_header_prefix: "".into(),
+            proxy_username: "alice".into(),
+            proxy_password: Some("s3cret".into()),
+            has_proxy_password: false,
+            ca_bundle_pem: "-----BEGIN CERTIFICATE-----".into(),
+            ca_bundle_path: "/etc/ssl/roomtemp-ca.pem".into(),
+            disable_webpki_roots: true,
+            connect_timeout_ms: 5_000,
+            request_timeout_ms: 0,
+            keepalive_interval_ms: 15_000,
+            keepalive_timeout_ms: 3_000,
+            keepalive_while_idle: true,
+            min_samples: 50,
+            max_samples: 2_000,
+            poll_interval_ms: 2_500,
         };
         repo.set(s.clone()).expect("set ok");
 
@@ -194,6 +365,102 @@ mod tests {
 
         assert_eq!(got.url, "https://example.com");
         assert_eq!(got.use_proxies, true);
+        assert_eq!(got.auth_mode, AuthMode::Custom);
+        assert_eq!(got.auth_header_name, "x-api-key");
+        assert_eq!(got.proxy_username, "alice");
+        assert_eq!(got.proxy_password.as_deref(), Some("s3cret"));
+        assert!(got.has_proxy_password);
+        assert_eq!(got.ca_bundle_pem, "-----BEGIN CERTIFICATE-----");
+        assert_eq!(got.ca_bundle_path, "/etc/ssl/roomtemp-ca.pem");
+        assert!(got.disable_webpki_roots);
+        assert_eq!(got.connect_timeout_ms, 5_000);
+        assert_eq!(got.request_timeout_ms, 0);
+        assert_eq!(got.keepalive_interval_ms, 15_000);
+        assert_eq!(got.keepalive_timeout_ms, 3_000);
+        assert!(got.keepalive_while_idle);
+        assert_eq!(got.min_samples, 50);
+        assert_eq!(got.max_samples, 2_000);
+        assert_eq!(got.poll_interval_ms, 2_500);
+
+        // パスワードは暗号化されて保存される
+        #[derive(QueryableByName)]
+        struct PasswordRow {
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Blob>)]
+            encrypted_proxy_password: Option<Vec<u8>>,
+        }
+        let mut conn3 = pool.get().unwrap();
+        let row: PasswordRow =
+            diesel::sql_query("SELECT encrypted_proxy_password FROM settings WHERE id = 1")
+                .get_result(&mut conn3)
+                .expect("query");
+        let stored = row.encrypted_proxy_password.expect("stored");
+        assert!(!stored.windows(6).any(|w| w == b"s3cret"));
+    }
+
+    #[test]
+    fn set_keeps_or_clears_proxy_password() {
+        KeyStore::set_test_key([3u8; 32]);
+        let f = NamedTempFile::new().expect("temp file");
+        let manager = ConnectionManager::<SqliteConnection>::new(f.path().to_str().unwrap());
+        let pool = Pool::builder().build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselSettingsRepository {
+            conn: pool.get().unwrap(),
+        };
+
+        let base = Settings {
+            id: 1,
+            proxy_username: "alice".into(),
+            proxy_password: Some("s3cret".into()),
+            ..Settings::default()
+        };
+        repo.set(base.clone()).expect("set ok");
+
+        // None は保存済みのパスワードを維持する
+        repo.set(Settings {
+            proxy_password: None,
+            ..base.clone()
+        })
+        .expect("set ok");
+        let got = repo.get().expect("get ok").expect("some");
+        assert_eq!(got.proxy_password.as_deref(), Some("s3cret"));
+
+        // 空文字列は削除
+        repo.set(Settings {
+            proxy_password: Some(String::new()),
+            ..base
+        })
+        .expect("set ok");
+        let got = repo.get().expect("get ok").expect("some");
+        assert_eq!(got.proxy_password, None);
+        assert!(!got.has_proxy_password);
+    }
+
+    #[test]
+    fn get_rejects_unknown_auth_mode() {
+        KeyStore::set_test_key([5u8; 32]);
+        let f = NamedTempFile::new().expect("temp file");
+        let manager = ConnectionManager::<SqliteConnection>::new(f.path().to_str().unwrap());
+        let pool = Pool::builder().build(manager).expect("pool");
+        crate::infrastructure::db::run_migrations(&pool);
+        let mut repo = DieselSettingsRepository {
+            conn: pool.get().unwrap(),
+        };
+        repo.set(Settings {
+            id: 1,
+            auth_mode: AuthMode::None,
+            ..Settings::default()
+        })
+        .expect("set ok");
+
+        pool.get()
+            .unwrap()
+            .batch_execute("UPDATE settings SET auth_mode = 'basic' WHERE id = 1")
+            .unwrap();
+        assert!(matches!(
+            repo.get(),
+            Err(DieselSettingsRepositoryError::UnknownAuthMode(mode)) if mode == "basic"
+        ));
     }
 
     #[test]
@@ -212,7 +479,24 @@ mod tests {
                 encrypted_access_token BLOB NOT NULL,
                 encrypted_access_token_nonce BLOB NOT NULL,
                 use_proxies BOOLEAN NOT NULL,
-                proxy_url TEXT NOT NULL
+                proxy_url TEXT NOT NULL,
+                auth_mode TEXT NOT NULL DEFAULT 'bearer',
+                auth_header_name TEXT NOT NULL DEFAULT '',
+                auth_header_prefix TEXT NOT NULL DEFAULT '',
+                proxy_username TEXT NOT NULL DEFAULT '',
+                encrypted_proxy_password BLOB,
+                encrypted_proxy_password_nonce BLOB,
+                ca_bundle_pem TEXT NOT NULL DEFAULT '',
+                ca_bundle_path TEXT NOT NULL DEFAULT '',
+                disable_webpki_roots BOOLEAN NOT NULL DEFAULT 0,
+                connect_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                request_timeout_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_interval_ms INTEGER NOT NULL DEFAULT 30000,
+                keepalive_timeout_ms INTEGER NOT NULL DEFAULT 10000,
+                keepalive_while_idle BOOLEAN NOT NULL DEFAULT 0,
+                min_samples INTEGER NOT NULL DEFAULT 100,
+                max_samples INTEGER NOT NULL DEFAULT 5000,
+                poll_interval_ms INTEGER NOT NULL DEFAULT 10000
             );",
         )
         .unwrap();
@@ -224,5 +508,7 @@ mod tests {
         assert!(maybe.is_some());
         let s = maybe.unwrap();
         assert_eq!(s.id, 1);
+        assert_eq!(s.auth_mode, AuthMode::Bearer);
+        assert_eq!(s.request_timeout_ms, 30_000);
     }
 }
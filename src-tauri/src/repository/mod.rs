@@ -1 +1,6 @@
+pub mod diesel_aggregate_repository;
+pub mod diesel_alert_repository;
+pub mod diesel_ambient_reading_repository;
+pub mod diesel_anomaly_threshold_repository;
+pub mod diesel_client_certificate_repository;
 pub mod diesel_settings_repository;
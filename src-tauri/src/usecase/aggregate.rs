@@ -0,0 +1,293 @@
+use jiff::tz::TimeZone;
+use thiserror::Error;
+
+use crate::domain::aggregation::{
+    self, AggregateBucket, AggregateInterval, AggregationError, Bucket,
+};
+use crate::domain::ambient::AmbientReading;
+use crate::domain::time_range::TimeRange;
+use crate::repository::diesel_aggregate_repository::{
+    AggregateRepository, DieselAggregateRepositoryError, RollupKey,
+};
+
+#[derive(Debug, Error)]
+pub enum AggregateError {
+    #[error(transparent)]
+    DieselAggregateRepository(#[from] DieselAggregateRepositoryError),
+    #[error("unknown time zone: {0}")]
+    UnknownTimeZone(String),
+    #[error(transparent)]
+    Aggregation(#[from] AggregationError),
+}
+
+/// 要求された期間の区切りと、そのうち保存済みの集計
+#[derive(Debug)]
+pub struct RollupPlan {
+    pub interval: AggregateInterval,
+    /// IANA タイムゾーン名
+    pub time_zone: String,
+    pub buckets: Vec<Bucket>,
+    /// `buckets` のうち保存済みの集計（古い順）
+    pub cached: Vec<AggregateBucket>,
+}
+
+impl RollupPlan {
+    /// 集計し直す必要がある区切り
+    pub fn missing(&self) -> Vec<Bucket> {
+        self.buckets
+            .iter()
+            .filter(|bucket| {
+                !self
+                    .cached
+                    .iter()
+                    .any(|cached| cached.start == bucket.start_ms)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// 集計し直す区切りの最初から最後まで。なければ `None`
+    pub fn missing_range(&self) -> Option<TimeRange> {
+        let missing = self.missing();
+        let (first, last) = (missing.first()?, missing.last()?);
+        TimeRange::from_millis(first.start_ms, last.end_ms).ok()
+    }
+
+    /// 集計し直す区切りを、隣り合うものどうしつないだ期間（測定値を集める期間、古い順）
+    pub fn missing_ranges(&self) -> Vec<TimeRange> {
+        let mut spans: Vec<(i64, i64)> = Vec::new();
+        for bucket in self.missing() {
+            match spans.last_mut() {
+                Some((_, end)) if *end == bucket.start_ms => *end = bucket.end_ms,
+                _ => spans.push((bucket.start_ms, bucket.end_ms)),
+            }
+        }
+        spans
+            .into_iter()
+            .filter_map(|(start, end)| TimeRange::from_millis(start, end).ok())
+            .collect()
+    }
+}
+
+/// IANA タイムゾーン名を解決する
+pub fn time_zone(name: &str) -> Result<TimeZone, AggregateError> {
+    TimeZone::get(name).map_err(|_| AggregateError::UnknownTimeZone(name.to_string()))
+}
+
+/// `range` を `interval` で区切り、保存済みの集計を読み出すユースケース
+pub fn plan_rollups<R: AggregateRepository>(
+    repo: &mut R,
+    endpoint: &str,
+    range: &TimeRange,
+    interval: AggregateInterval,
+    time_zone_name: &str,
+) -> Result<RollupPlan, AggregateError> {
+    let tz = time_zone(time_zone_name)?;
+    let buckets = aggregation::buckets(range, interval, &tz)?;
+    let cached = match (buckets.first(), buckets.last()) {
+        (Some(first), Some(last)) => {
+            let key = RollupKey {
+                endpoint,
+                interval,
+                time_zone: time_zone_name,
+            };
+            // タイムゾーンの規則が変わって区切りがずれたものは使わない
+            repo.rollups(&key, first.start_ms, last.end_ms)?
+                .into_iter()
+                .filter(|cached| {
+                    buckets
+                        .iter()
+                        .any(|b| b.start_ms == cached.start && b.end_ms == cached.end)
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    Ok(RollupPlan {
+        interval,
+        time_zone: time_zone_name.to_string(),
+        buckets,
+        cached,
+    })
+}
+
+/// 足りない区切りを `readings` から集計し、保存済みのものと合わせて古い順に返すユースケース。
+/// `settled_before_ms` より前に終わる区切りだけを保存する（`None` なら保存しない）
+pub fn complete_rollups<R: AggregateRepository>(
+    repo: &mut R,
+    endpoint: &str,
+    plan: RollupPlan,
+    mut readings: Vec<AmbientReading>,
+    settled_before_ms: Option<i64>,
+) -> Result<Vec<AggregateBucket>, AggregateError> {
+    readings.sort_by_key(|reading| reading.recorded_at_ms);
+    let computed = aggregation::aggregate(&readings, &plan.missing());
+    if let Some(settled_before_ms) = settled_before_ms {
+        let settled: Vec<_> = computed
+            .iter()
+            .filter(|bucket| bucket.end <= settled_before_ms)
+            .cloned()
+            .collect();
+        if !settled.is_empty() {
+            let key = RollupKey {
+                endpoint,
+                interval: plan.interval,
+                time_zone: &plan.time_zone,
+            };
+            repo.store(&key, &settled)?;
+        }
+    }
+
+    let mut buckets: Vec<_> = plan.cached.into_iter().chain(computed).collect();
+    buckets.sort_by_key(|bucket| bucket.start);
+    Ok(buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::domain::aggregation::MetricAggregate;
+    use crate::domain::ambient::AmbientMetric;
+    use mockall::mock;
+
+    mock! {
+        pub AggregateRepo {}
+        impl AggregateRepository for AggregateRepo {
+            fn rollups<'a>(&mut self, key: &RollupKey<'a>, from_ms: i64, to_ms: i64) -> Result<Vec<AggregateBucket>, DieselAggregateRepositoryError>;
+            fn store<'a>(&mut self, key: &RollupKey<'a>, buckets: &[AggregateBucket]) -> Result<(), DieselAggregateRepositoryError>;
+        }
+    }
+
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+
+    fn reading(recorded_at_ms: i64, temperature: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature,
+            humidity: 40.0,
+            illumination: 100.0,
+        }
+    }
+
+    fn cached_first_hour() -> AggregateBucket {
+        AggregateBucket {
+            start: 0,
+            end: HOUR_MS,
+            metrics: BTreeMap::from([(
+                AmbientMetric::Temperature,
+                MetricAggregate {
+                    count: 1,
+                    min: 1.0,
+                    mean: 1.0,
+                    max: 1.0,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn plan_reuses_cached_buckets() {
+        let mut repo = MockAggregateRepo::new();
+        repo.expect_rollups()
+            .withf(|key, from, to| key.time_zone == "UTC" && *from == 0 && *to == 3 * HOUR_MS)
+            .returning(|_, _, _| Ok(vec![cached_first_hour()]));
+
+        let range = TimeRange::from_millis(0, 3 * HOUR_MS).unwrap();
+        let plan = plan_rollups(
+            &mut repo,
+            "https://a.example",
+            &range,
+            AggregateInterval::Hourly,
+            "UTC",
+        )
+        .expect("plan ok");
+
+        assert_eq!(plan.buckets.len(), 3);
+        assert_eq!(plan.missing().len(), 2);
+        assert_eq!(
+            plan.missing_range(),
+            Some(TimeRange::from_millis(HOUR_MS, 3 * HOUR_MS).unwrap())
+        );
+    }
+
+    #[test]
+    fn missing_ranges_join_adjacent_buckets() {
+        let middle = AggregateBucket {
+            start: HOUR_MS,
+            end: 2 * HOUR_MS,
+            ..cached_first_hour()
+        };
+        let plan = RollupPlan {
+            interval: AggregateInterval::Hourly,
+            time_zone: "UTC".into(),
+            buckets: (0..4)
+                .map(|i| Bucket {
+                    start_ms: i * HOUR_MS,
+                    end_ms: (i + 1) * HOUR_MS,
+                })
+                .collect(),
+            cached: vec![middle],
+        };
+        assert_eq!(
+            plan.missing_ranges(),
+            [
+                TimeRange::from_millis(0, HOUR_MS).unwrap(),
+                TimeRange::from_millis(2 * HOUR_MS, 4 * HOUR_MS).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_time_zone_is_rejected() {
+        let mut repo = MockAggregateRepo::new();
+        let range = TimeRange::from_millis(0, HOUR_MS).unwrap();
+        assert!(matches!(
+            plan_rollups(
+                &mut repo,
+                "e",
+                &range,
+                AggregateInterval::Daily,
+                "Mars/Base"
+            ),
+            Err(AggregateError::UnknownTimeZone(_))
+        ));
+    }
+
+    #[test]
+    fn complete_stores_only_settled_buckets() {
+        let mut repo = MockAggregateRepo::new();
+        repo.expect_store()
+            .withf(|_, buckets| buckets.len() == 1 && buckets[0].start == HOUR_MS)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let plan = RollupPlan {
+            interval: AggregateInterval::Hourly,
+            time_zone: "UTC".into(),
+            buckets: (0..3)
+                .map(|i| Bucket {
+                    start_ms: i * HOUR_MS,
+                    end_ms: (i + 1) * HOUR_MS,
+                })
+                .collect(),
+            cached: vec![cached_first_hour()],
+        };
+        // 並んでいなくても集計できる
+        let readings = vec![
+            reading(2 * HOUR_MS + 1, 30.0),
+            reading(HOUR_MS + 2, 20.0),
+            reading(HOUR_MS + 1, 10.0),
+        ];
+        let buckets = complete_rollups(&mut repo, "e", plan, readings, Some(2 * HOUR_MS))
+            .expect("complete ok");
+
+        let means: Vec<_> = buckets
+            .iter()
+            .map(|bucket| bucket.metrics[&AmbientMetric::Temperature].mean)
+            .collect();
+        assert_eq!(means, [1.0, 15.0, 30.0]);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+
+use jiff::tz::TimeZone;
+use thiserror::Error;
+
+use crate::domain::alert::{self, AlertEvent, AlertFired, AlertRule, RuleState, Transition};
+use crate::domain::ambient::AmbientReading;
+use crate::repository::diesel_alert_repository::{AlertRepository, DieselAlertRepositoryError};
+
+#[derive(Debug, Error)]
+pub enum AlertError {
+    #[error(transparent)]
+    DieselAlertRepository(#[from] DieselAlertRepositoryError),
+    #[error("invalid alert rule")]
+    InvalidRule,
+    #[error("alert rule {0} not found")]
+    RuleNotFound(i32),
+}
+
+/// 規則の一覧を返すユースケース
+pub fn list_rules<R: AlertRepository>(repo: &mut R) -> Result<Vec<AlertRule>, AlertError> {
+    Ok(repo.rules()?)
+}
+
+/// 規則を検証して追加し、ID を振った規則を返すユースケース
+pub fn create_rule<R: AlertRepository>(
+    repo: &mut R,
+    rule: AlertRule,
+) -> Result<AlertRule, AlertError> {
+    if !rule.is_valid() {
+        return Err(AlertError::InvalidRule);
+    }
+    let id = repo.create_rule(&rule)?;
+    Ok(AlertRule { id, ..rule })
+}
+
+/// 規則を検証して置き換えるユースケース
+pub fn update_rule<R: AlertRepository>(repo: &mut R, rule: AlertRule) -> Result<(), AlertError> {
+    if !rule.is_valid() {
+        return Err(AlertError::InvalidRule);
+    }
+    if !repo.update_rule(&rule)? {
+        return Err(AlertError::RuleNotFound(rule.id));
+    }
+    Ok(())
+}
+
+/// 規則とその履歴を削除するユースケース
+pub fn delete_rule<R: AlertRepository>(repo: &mut R, id: i32) -> Result<bool, AlertError> {
+    Ok(repo.delete_rule(id)?)
+}
+
+/// 新しい順の発報履歴を返すユースケース
+pub fn list_events<R: AlertRepository>(
+    repo: &mut R,
+    limit: i64,
+) -> Result<Vec<AlertEvent>, AlertError> {
+    Ok(repo.events(limit)?)
+}
+
+/// 新しく届いた `readings` を有効な規則で判定し、発報・解除を記録するユースケース。
+/// `states` は呼び出しをまたいで規則ごとの途中経過を持つ。発報中かどうかは記録から読み直すので、
+/// 再起動しても発報中の規則は重ねて発報しない。新たに発報したものを返す
+pub fn evaluate_rules<R: AlertRepository>(
+    repo: &mut R,
+    states: &mut HashMap<i32, RuleState>,
+    readings: &[AmbientReading],
+    tz: &TimeZone,
+) -> Result<Vec<AlertFired>, AlertError> {
+    let rules: Vec<AlertRule> = repo.rules()?.into_iter().filter(|r| r.enabled).collect();
+    let active: HashSet<i32> = repo
+        .active_events()?
+        .into_iter()
+        .map(|event| event.rule_id)
+        .collect();
+    // 削除・無効化された規則の途中経過は捨てる
+    states.retain(|id, _| rules.iter().any(|rule| rule.id == *id));
+
+    let mut fired = Vec::new();
+    for rule in rules {
+        let state = states.entry(rule.id).or_default();
+        state.active = active.contains(&rule.id);
+        for transition in alert::evaluate(&rule, readings, state, tz) {
+            match transition {
+                Transition::Fire { at_ms, value } => {
+                    if let Some(event) = repo.fire(rule.id, at_ms, value)? {
+                        fired.push(AlertFired {
+                            rule: rule.clone(),
+                            event,
+                        });
+                    }
+                }
+                Transition::Clear { at_ms } => {
+                    repo.clear(rule.id, at_ms)?;
+                }
+            }
+        }
+    }
+    Ok(fired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::alert::Comparator;
+    use crate::domain::ambient::AmbientMetric;
+    use mockall::mock;
+    use mockall::predicate::eq;
+
+    mock! {
+        pub AlertRepo {}
+        impl AlertRepository for AlertRepo {
+            fn rules(&mut self) -> Result<Vec<AlertRule>, DieselAlertRepositoryError>;
+            fn create_rule(&mut self, rule: &AlertRule) -> Result<i32, DieselAlertRepositoryError>;
+            fn update_rule(&mut self, rule: &AlertRule) -> Result<bool, DieselAlertRepositoryError>;
+            fn delete_rule(&mut self, id: i32) -> Result<bool, DieselAlertRepositoryError>;
+            fn events(&mut self, limit: i64) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError>;
+            fn active_events(&mut self) -> Result<Vec<AlertEvent>, DieselAlertRepositoryError>;
+            fn fire(&mut self, rule_id: i32, fired_at_ms: i64, value: f64) -> Result<Option<AlertEvent>, DieselAlertRepositoryError>;
+            fn clear(&mut self, rule_id: i32, cleared_at_ms: i64) -> Result<bool, DieselAlertRepositoryError>;
+        }
+    }
+
+    fn rule(id: i32) -> AlertRule {
+        AlertRule {
+            id,
+            name: "Dark".into(),
+            metric: AmbientMetric::Illumination,
+            comparator: Comparator::Lt,
+            threshold: 10.0,
+            sustained_ms: 0,
+            quiet_hours: None,
+            enabled: true,
+        }
+    }
+
+    fn reading(recorded_at_ms: i64, illumination: f64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature: 20.0,
+            humidity: 40.0,
+            illumination,
+        }
+    }
+
+    fn event(rule_id: i32, fired_at_ms: i64) -> AlertEvent {
+        AlertEvent {
+            id: 1,
+            rule_id,
+            fired_at_ms,
+            cleared_at_ms: None,
+            value: 5.0,
+        }
+    }
+
+    #[test]
+    fn create_rule_validates_and_assigns_id() {
+        let mut repo = MockAlertRepo::new();
+        repo.expect_create_rule().times(1).returning(|_| Ok(7));
+
+        assert_eq!(create_rule(&mut repo, rule(0)).expect("create ok").id, 7);
+        let invalid = AlertRule {
+            threshold: f64::INFINITY,
+            ..rule(0)
+        };
+        assert!(matches!(
+            create_rule(&mut repo, invalid),
+            Err(AlertError::InvalidRule)
+        ));
+    }
+
+    #[test]
+    fn update_rule_reports_missing_rule() {
+        let mut repo = MockAlertRepo::new();
+        repo.expect_update_rule().returning(|_| Ok(false));
+        assert!(matches!(
+            update_rule(&mut repo, rule(3)),
+            Err(AlertError::RuleNotFound(3))
+        ));
+    }
+
+    #[test]
+    fn evaluate_rules_records_transitions_and_skips_active_rules() {
+        let mut repo = MockAlertRepo::new();
+        repo.expect_rules().returning(|| {
+            Ok(vec![
+                rule(1),
+                rule(2),
+                AlertRule {
+                    enabled: false,
+                    ..rule(3)
+                },
+            ])
+        });
+        // 規則 2 は前回から発報中
+        repo.expect_active_events()
+            .returning(|| Ok(vec![event(2, 0)]));
+        repo.expect_fire()
+            .with(eq(1), eq(1_000), eq(5.0))
+            .times(1)
+            .returning(|rule_id, at, _| Ok(Some(event(rule_id, at))));
+        repo.expect_clear()
+            .with(eq(1), eq(2_000))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        repo.expect_clear()
+            .with(eq(2), eq(2_000))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut states = HashMap::from([(9, RuleState::default())]);
+        let fired = evaluate_rules(
+            &mut repo,
+            &mut states,
+            &[reading(1_000, 5.0), reading(2_000, 50.0)],
+            &TimeZone::UTC,
+        )
+        .expect("evaluate ok");
+
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule.id, 1);
+        assert_eq!(fired[0].event.fired_at_ms, 1_000);
+        let mut ids: Vec<_> = states.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, [1, 2]);
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost::Message as _;
+use tempgrpcd_protos::tempgrpcd::v1::{AmbientCondition, GetAmbientConditionsResponse};
+use thiserror::Error;
+
+use crate::domain::ambient::{self, AmbientReading, CachedSpan};
+use crate::domain::time_range::TimeRange;
+use crate::repository::diesel_ambient_reading_repository::{
+    AmbientReadingRepository, DieselAmbientReadingRepositoryError,
+};
+
+/// 現在からこの時間以内の測定値はまだ増えうるので、取得済みとは記録しない
+pub const SETTLE_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Error)]
+pub enum AmbientCacheError {
+    #[error(transparent)]
+    DieselAmbientReadingRepository(#[from] DieselAmbientReadingRepositoryError),
+    #[error("invalid GetAmbientConditionsResponse: {0}")]
+    Decode(#[from] prost::DecodeError),
+}
+
+/// 保存済み測定値から組み立てた応答
+#[derive(Debug, Default)]
+pub struct CachedResponse {
+    /// エンコード済みの `GetAmbientConditionsResponse`
+    pub body: Vec<u8>,
+    /// 応答に含めた測定値の最初から最後まで（測定値がなければ `None`）
+    pub covered: Option<TimeRange>,
+}
+
+/// `range` のうち、`interval` 以下の間隔で取得済みの期間がない部分（サーバーから取得する必要がある部分）
+pub fn missing_spans<R: AmbientReadingRepository>(
+    repo: &mut R,
+    endpoint: &str,
+    range: &TimeRange,
+    interval: Duration,
+) -> Result<Vec<TimeRange>, AmbientCacheError> {
+    let covered: Vec<_> = repo
+        .spans(endpoint, range)?
+        .into_iter()
+        .filter(|span| span.interval <= interval)
+        .map(|span| span.range)
+        .collect();
+    Ok(range.gaps(&covered))
+}
+
+/// `ranges` の保存済み測定値を `interval` ごとに間引き、`GetAmbientConditionsResponse` にエンコードして返す
+pub fn cached_response<R: AmbientReadingRepository>(
+    repo: &mut R,
+    endpoint: &str,
+    ranges: &[TimeRange],
+    interval: Duration,
+) -> Result<CachedResponse, AmbientCacheError> {
+    let mut ambient_conditions = HashMap::new();
+    let mut extent: Option<(i64, i64)> = None;
+    for range in ranges {
+        let readings = repo.readings(endpoint, range)?;
+        for reading in thin(readings, interval) {
+            let at = reading.recorded_at_ms;
+            extent = Some(extent.map_or((at, at), |(first, last)| (first.min(at), last.max(at))));
+            ambient_conditions.insert(
+                reading.id,
+                AmbientCondition {
+                    temperature: reading.temperature as _,
+                    humidity: reading.humidity as _,
+                    illumination: reading.illumination as _,
+                },
+            );
+        }
+    }
+    Ok(CachedResponse {
+        body: GetAmbientConditionsResponse { ambient_conditions }.encode_to_vec(),
+        // 最後の測定値も含むよう、終了は 1 ミリ秒後にする
+        covered: extent.and_then(|(first, last)| TimeRange::from_millis(first, last + 1).ok()),
+    })
+}
+
+/// 最後にサーバーから取得した時刻（Unix エポックからのミリ秒）
+pub fn last_synced_at<R: AmbientReadingRepository>(
+    repo: &mut R,
+    endpoint: &str,
+) -> Result<Option<i64>, AmbientCacheError> {
+    Ok(repo.last_synced_at(endpoint)?)
+}
+
+/// サーバーから `window` を `interval` で取得した応答を保存する。
+/// `now` から `SETTLE_MARGIN` 以内の部分は取得済みとして記録しない
+pub fn store_fetched<R: AmbientReadingRepository>(
+    repo: &mut R,
+    endpoint: &str,
+    window: &TimeRange,
+    interval: Duration,
+    body: &[u8],
+    now: SystemTime,
+) -> Result<(), AmbientCacheError> {
+    let readings = ambient::readings_from_response(GetAmbientConditionsResponse::decode(body)?);
+
+    let now_ms = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64);
+    let settled_ms = now
+        .checked_sub(SETTLE_MARGIN)
+        .and_then(|settled| settled.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as i64);
+    let span = TimeRange::from_millis(window.to_millis().start, settled_ms)
+        .ok()
+        .and_then(|settled| window.intersection(&settled))
+        .map(|range| CachedSpan { range, interval });
+
+    repo.store(endpoint, &readings, span.as_ref(), now_ms)?;
+    Ok(())
+}
+
+/// 記録時刻の順に並んだ測定値を、`interval` より詰まらないように間引く
+fn thin(readings: Vec<AmbientReading>, interval: Duration) -> Vec<AmbientReading> {
+    let interval_ns = interval.as_nanos() as i128;
+    let mut next_allowed = i128::MIN;
+    readings
+        .into_iter()
+        .filter(|reading| {
+            let at = i128::from(reading.recorded_at_ms) * 1_000_000;
+            if at < next_allowed {
+                return false;
+            }
+            next_allowed = at + interval_ns;
+            true
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::mock;
+    use mockall::predicate::eq;
+
+    mock! {
+        pub AmbientRepo {}
+        impl AmbientReadingRepository for AmbientRepo {
+            fn readings(&mut self, endpoint: &str, range: &TimeRange) -> Result<Vec<AmbientReading>, DieselAmbientReadingRepositoryError>;
+            fn spans(&mut self, endpoint: &str, range: &TimeRange) -> Result<Vec<CachedSpan>, DieselAmbientReadingRepositoryError>;
+            fn store<'a>(&mut self, endpoint: &str, readings: &[AmbientReading], span: Option<&'a CachedSpan>, synced_at_ms: i64) -> Result<(), DieselAmbientReadingRepositoryError>;
+            fn last_synced_at(&mut self, endpoint: &str) -> Result<Option<i64>, DieselAmbientReadingRepositoryError>;
+        }
+    }
+
+    const ENDPOINT: &str = "https://tempgrpcd.example";
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn millis(start: i64, end: i64) -> TimeRange {
+        TimeRange::from_millis(start, end).unwrap()
+    }
+
+    fn reading(recorded_at_ms: i64) -> AmbientReading {
+        AmbientReading {
+            id: format!("{recorded_at_ms}-0"),
+            recorded_at_ms,
+            temperature: 21.5,
+            humidity: 40.0,
+            illumination: 120.0,
+        }
+    }
+
+    #[test]
+    fn missing_spans_ignore_coarser_cache() {
+        let mut repo = MockAmbientRepo::new();
+        repo.expect_spans().returning(|_, _| {
+            Ok(vec![
+                CachedSpan {
+                    range: millis(0, 60_000),
+                    interval: Duration::from_secs(1),
+                },
+                // 要求より粗いので使えない
+                CachedSpan {
+                    range: millis(60_000, 120_000),
+                    interval: Duration::from_secs(600),
+                },
+            ])
+        });
+
+        let gaps = missing_spans(&mut repo, ENDPOINT, &millis(0, 180_000), MINUTE).expect("gaps");
+        assert_eq!(gaps, vec![millis(60_000, 180_000)]);
+    }
+
+    #[test]
+    fn cached_response_thins_to_interval() {
+        let mut repo = MockAmbientRepo::new();
+        repo.expect_readings()
+            .with(eq(ENDPOINT), eq(millis(0, 300_000)))
+            .returning(|_, _| {
+                Ok(vec![
+                    reading(0),
+                    reading(30_000),
+                    reading(60_000),
+                    reading(100_000),
+                    reading(130_000),
+                ])
+            });
+
+        let cached =
+            cached_response(&mut repo, ENDPOINT, &[millis(0, 300_000)], MINUTE).expect("response");
+        assert_eq!(cached.covered, Some(millis(0, 130_001)));
+        let response =
+            GetAmbientConditionsResponse::decode(cached.body.as_slice()).expect("decode");
+        let mut ids: Vec<_> = response.ambient_conditions.into_keys().collect();
+        ids.sort();
+        assert_eq!(ids, ["0-0", "130000-0", "60000-0"]);
+    }
+
+    #[test]
+    fn store_fetched_records_only_settled_part_of_window() {
+        let now = UNIX_EPOCH + Duration::from_secs(3_600);
+        let window = millis(0, 3_600_000);
+        let mut ambient_conditions = HashMap::new();
+        ambient_conditions.insert(
+            "1000-0".to_string(),
+            AmbientCondition {
+                temperature: 21.5,
+                humidity: 40.0,
+                illumination: 120.0,
+            },
+        );
+        let body = GetAmbientConditionsResponse { ambient_conditions }.encode_to_vec();
+
+        let mut repo = MockAmbientRepo::new();
+        repo.expect_store()
+            .withf(|endpoint, readings, span, synced_at_ms| {
+                endpoint == ENDPOINT
+                    && *synced_at_ms == 3_600_000
+                    && readings == [reading(1_000)]
+                    && *span
+                        == Some(&CachedSpan {
+                            range: millis(0, 3_300_000),
+                            interval: MINUTE,
+                        })
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        store_fetched(&mut repo, ENDPOINT, &window, MINUTE, &body, now).expect("store");
+    }
+
+    #[test]
+    fn store_fetched_skips_span_within_settle_margin() {
+        let now = UNIX_EPOCH + Duration::from_secs(3_600);
+        let mut repo = MockAmbientRepo::new();
+        repo.expect_store()
+            .withf(|_, readings, span, _| readings.is_empty() && span.is_none())
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        store_fetched(
+            &mut repo,
+            ENDPOINT,
+            &millis(3_400_000, 3_600_000),
+            MINUTE,
+            &[],
+            now,
+        )
+        .expect("store");
+    }
+}
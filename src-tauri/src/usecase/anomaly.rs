@@ -0,0 +1,131 @@
+use thiserror::Error;
+
+use crate::domain::ambient::{AmbientMetric, AmbientReading};
+use crate::domain::anomaly::{self, Anomaly, AnomalyThresholds};
+use crate::repository::diesel_anomaly_threshold_repository::{
+    AnomalyThresholdRepository, DieselAnomalyThresholdRepositoryError,
+};
+
+#[derive(Debug, Error)]
+pub enum AnomalyError {
+    #[error(transparent)]
+    DieselAnomalyThresholdRepository(#[from] DieselAnomalyThresholdRepositoryError),
+    #[error("invalid anomaly thresholds for {0:?}")]
+    InvalidThresholds(AmbientMetric),
+}
+
+/// 全指標のしきい値を返すユースケース。保存されていない指標は既定値にする
+pub fn get_thresholds<R: AnomalyThresholdRepository>(
+    repo: &mut R,
+) -> Result<Vec<AnomalyThresholds>, AnomalyError> {
+    let stored = repo.get()?;
+    Ok(AmbientMetric::ALL
+        .into_iter()
+        .map(|metric| {
+            stored
+                .iter()
+                .find(|thresholds| thresholds.metric == metric)
+                .copied()
+                .unwrap_or_else(|| AnomalyThresholds::default_for(metric))
+        })
+        .collect())
+}
+
+/// しきい値を検証して保存するユースケース
+pub fn set_thresholds<R: AnomalyThresholdRepository>(
+    repo: &mut R,
+    thresholds: &[AnomalyThresholds],
+) -> Result<(), AnomalyError> {
+    if let Some(invalid) = thresholds.iter().find(|thresholds| !thresholds.is_valid()) {
+        return Err(AnomalyError::InvalidThresholds(invalid.metric));
+    }
+    Ok(repo.set(thresholds)?)
+}
+
+/// 保存済みのしきい値で `readings` の `metrics` を調べるユースケース
+pub fn detect_anomalies<R: AnomalyThresholdRepository>(
+    repo: &mut R,
+    readings: &[AmbientReading],
+    metrics: &[AmbientMetric],
+) -> Result<Vec<Anomaly>, AnomalyError> {
+    let thresholds: Vec<AnomalyThresholds> = get_thresholds(repo)?
+        .into_iter()
+        .filter(|thresholds| metrics.contains(&thresholds.metric))
+        .collect();
+    Ok(anomaly::detect(readings, &thresholds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::mock;
+
+    mock! {
+        pub AnomalyThresholdRepo {}
+        impl AnomalyThresholdRepository for AnomalyThresholdRepo {
+            fn get(&mut self) -> Result<Vec<AnomalyThresholds>, DieselAnomalyThresholdRepositoryError>;
+            fn set(&mut self, thresholds: &[AnomalyThresholds]) -> Result<(), DieselAnomalyThresholdRepositoryError>;
+        }
+    }
+
+    #[test]
+    fn get_thresholds_fills_defaults_for_missing_metrics() {
+        let stored = AnomalyThresholds {
+            z_score: 2.5,
+            ..AnomalyThresholds::default_for(AmbientMetric::Humidity)
+        };
+        let mut repo = MockAnomalyThresholdRepo::new();
+        repo.expect_get().returning(move || Ok(vec![stored]));
+
+        let thresholds = get_thresholds(&mut repo).expect("get ok");
+        assert_eq!(
+            thresholds,
+            [
+                AnomalyThresholds::default_for(AmbientMetric::Temperature),
+                stored,
+                AnomalyThresholds::default_for(AmbientMetric::Illumination),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_thresholds_rejects_invalid_values() {
+        let mut repo = MockAnomalyThresholdRepo::new();
+        repo.expect_set().never();
+        let invalid = AnomalyThresholds {
+            rate_of_change: 0.0,
+            ..AnomalyThresholds::default_for(AmbientMetric::Illumination)
+        };
+
+        let err = set_thresholds(&mut repo, &[invalid]).unwrap_err();
+        assert!(matches!(
+            err,
+            AnomalyError::InvalidThresholds(AmbientMetric::Illumination)
+        ));
+    }
+
+    #[test]
+    fn detect_anomalies_only_checks_requested_metrics() {
+        let mut repo = MockAnomalyThresholdRepo::new();
+        repo.expect_get().returning(|| Ok(Vec::new()));
+        // 1 分ごとに温度も湿度も跳ね上がる
+        let readings: Vec<_> = (0..3)
+            .map(|i| AmbientReading {
+                id: format!("{}-0", i * 60_000),
+                recorded_at_ms: i * 60_000,
+                temperature: 20.0 + 10.0 * i as f64,
+                humidity: 40.0 + 30.0 * i as f64,
+                illumination: 0.0,
+            })
+            .collect();
+
+        let anomalies =
+            detect_anomalies(&mut repo, &readings, &[AmbientMetric::Humidity]).expect("detect");
+        assert!(!anomalies.is_empty());
+        assert!(
+            anomalies
+                .iter()
+                .all(|anomaly| anomaly.metric == AmbientMetric::Humidity)
+        );
+    }
+}
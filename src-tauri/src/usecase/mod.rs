@@ -1 +1,6 @@
+pub mod aggregate;
+pub mod alert;
+pub mod ambient_cache;
+pub mod anomaly;
+pub mod client_certificate;
 pub mod settings;
@@ -1,9 +1,30 @@
 "use client";
 
-import React, { useContext, useState } from "react";
+import React, { useContext, useMemo, useRef, useState } from "react";
+import { AggregateInterval, DerivedMetric, Resolution } from "@/domain/types";
 import { useGraphData } from "@/interfaces/react/hooks/useGraphData";
 import { GraphChart } from "@/interfaces/react/components/GraphChart";
+import { StatisticsTable } from "@/interfaces/react/components/StatisticsTable";
+import { AnomalyList } from "@/interfaces/react/components/AnomalyList";
+import { AggregateTable } from "@/interfaces/react/components/AggregateTable";
+import { DERIVED_METRICS } from "@/interfaces/presenters/graphPresenter";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { useStatistics } from "@/interfaces/react/hooks/useStatistics";
+import { useAnomalies } from "@/interfaces/react/hooks/useAnomalies";
+import { AnomalyRepositoryImpl } from "@/interfaces/repositories/anomaly";
+import { useAggregates } from "@/interfaces/react/hooks/useAggregates";
+import { AggregateRepositoryImpl } from "@/interfaces/repositories/aggregate";
 import { Button } from "@/components/ui/button";
+import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert";
+import { CloudOff } from "lucide-react";
 import { DateTimePicker24h } from "@/components/ui/expansions/datetime-picker";
 import { GrpcRepoContext } from "@/interfaces/react/contexts/grpcRepoContext";
 
@@ -13,7 +34,32 @@ export default function Home() {
     new Date(Date.now() - 6 * 60 * 60 * 1000)
   ); // 6 hours ago
   const [end, setEnd] = useState<Date>(new Date());
-  const { data, fetch, loading, error } = useGraphData(grpcRepo);
+  const { data, fetch, cancel, loading, error, warning, stale } =
+    useGraphData(grpcRepo);
+  const statistics = useStatistics(grpcRepo);
+  const anomalyRepo = useMemo(() => new AnomalyRepositoryImpl(), []);
+  const anomalies = useAnomalies(anomalyRepo);
+  const aggregateRepo = useMemo(() => new AggregateRepositoryImpl(), []);
+  const aggregates = useAggregates(aggregateRepo);
+  // Calendar buckets for month- and year-scale views ("none" skips them).
+  const [aggregateInterval, setAggregateInterval] = useState<
+    AggregateInterval | "none"
+  >("none");
+  const chartsRef = useRef<HTMLDivElement>(null);
+  // Extra series computed by the backend from temperature and humidity.
+  const [derived, setDerived] = useState<DerivedMetric[]>([]);
+
+  // One sample per physical pixel of the chart area.
+  const chartResolution = (): Resolution | undefined => {
+    const width = chartsRef.current?.clientWidth ?? 0;
+    if (width === 0) {
+      return undefined;
+    }
+    return {
+      kind: "pixels",
+      width: Math.round(width * window.devicePixelRatio),
+    };
+  };
 
   return (
     <div>
@@ -31,31 +77,137 @@ export default function Home() {
             <DateTimePicker24h value={end} onChange={(end) => setEnd(end)} />
           </div>
         </div>
-        <Button onClick={() => fetch(start, end)} disabled={loading}>
-          Fetch Data
-        </Button>
+        <div className="flex flex-row flex-wrap gap-4 mb-4">
+          {DERIVED_METRICS.map(({ metric, label }) => (
+            <div key={metric} className="flex flex-row items-center space-x-2">
+              <Checkbox
+                id={`derived-${metric}`}
+                checked={derived.includes(metric)}
+                onCheckedChange={(checked: boolean) =>
+                  setDerived((current) =>
+                    checked
+                      ? [...current, metric]
+                      : current.filter((m) => m !== metric)
+                  )
+                }
+              />
+              <Label htmlFor={`derived-${metric}`}>{label}</Label>
+            </div>
+          ))}
+          <div className="flex flex-row items-center space-x-2">
+            <Label htmlFor="aggregate-interval">Aggregate</Label>
+            <Select
+              value={aggregateInterval}
+              onValueChange={(value: string) =>
+                setAggregateInterval(value as AggregateInterval | "none")
+              }
+            >
+              <SelectTrigger id="aggregate-interval" className="w-[120px]">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="none">None</SelectItem>
+                <SelectItem value="hourly">Hourly</SelectItem>
+                <SelectItem value="daily">Daily</SelectItem>
+                <SelectItem value="weekly">Weekly</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+        </div>
+        <div className="flex flex-row gap-2">
+          <Button
+            className="flex-1"
+            onClick={() => {
+              fetch(start, end, chartResolution(), derived);
+              statistics.load(start, end, derived);
+              anomalies.detect(start, end);
+              if (aggregateInterval === "none") {
+                aggregates.clear();
+              } else {
+                aggregates.load(start, end, aggregateInterval);
+              }
+            }}
+            disabled={loading}
+          >
+            Fetch Data
+          </Button>
+          {loading && (
+            <Button variant="outline" onClick={() => cancel()}>
+              Cancel
+            </Button>
+          )}
+        </div>
         {error && <p className="text-red-500">{error}</p>}
-        {data && (
-          <GraphChart
-            data={data}
-            dataKey="temperature"
-            description="Temperature (℃)"
-          />
+        {warning && <p className="text-amber-600">{warning}</p>}
+        {stale && (
+          <Alert className="flex flex-col space-y-1.5 my-2 text-amber-600">
+            <CloudOff className="h-4 w-4" />
+            <AlertTitle>Stale data</AlertTitle>
+            <AlertDescription>
+              The server could not be reached, so cached readings are shown.
+              {" Last synced: "}
+              {stale.lastSync ? stale.lastSync.toLocaleString() : "never"}.
+              {stale.coveredRange &&
+                ` Cached readings cover ${stale.coveredRange.start.toLocaleString()} – ${stale.coveredRange.end.toLocaleString()}.`}
+            </AlertDescription>
+          </Alert>
+        )}
+        {statistics.error && (
+          <p className="text-red-500">
+            Failed to compute statistics: {statistics.error}
+          </p>
+        )}
+        {statistics.statistics && (
+          <StatisticsTable report={statistics.statistics} />
         )}
-        {data && (
-          <GraphChart
-            data={data}
-            dataKey="humidity"
-            description="Humidity (%)"
-          />
+        {aggregates.error && (
+          <p className="text-red-500">
+            Failed to aggregate readings: {aggregates.error}
+          </p>
         )}
-        {data && (
-          <GraphChart
-            data={data}
-            dataKey="illumination"
-            description="Illumination (lx)"
-          />
+        {aggregates.aggregates && (
+          <AggregateTable report={aggregates.aggregates} />
         )}
+        {anomalies.error && (
+          <p className="text-red-500">
+            Failed to detect anomalies: {anomalies.error}
+          </p>
+        )}
+        {anomalies.anomalies && <AnomalyList anomalies={anomalies.anomalies} />}
+        <div ref={chartsRef} className="flex flex-col">
+          {data && (
+            <GraphChart
+              data={data}
+              dataKey="temperature"
+              description="Temperature (℃)"
+            />
+          )}
+          {data && (
+            <GraphChart
+              data={data}
+              dataKey="humidity"
+              description="Humidity (%)"
+            />
+          )}
+          {data && (
+            <GraphChart
+              data={data}
+              dataKey="illumination"
+              description="Illumination (lx)"
+            />
+          )}
+          {data &&
+            DERIVED_METRICS.filter(({ metric }) =>
+              data.some((point) => point[metric] !== undefined)
+            ).map(({ metric, label }) => (
+              <GraphChart
+                key={metric}
+                data={data}
+                dataKey={metric}
+                description={label}
+              />
+            ))}
+        </div>
       </div>
     </div>
   );
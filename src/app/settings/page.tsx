@@ -17,10 +17,26 @@ import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { GrpcRepoContext } from "@/interfaces/react/contexts/grpcRepoContext";
 import { Checkbox } from "@/components/ui/checkbox";
+import { Textarea } from "@/components/ui/textarea";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { AuthMode } from "@/domain/types";
+import { AlertRulesCard } from "@/interfaces/react/components/AlertRulesCard";
+import { AnomalyThresholdsCard } from "@/interfaces/react/components/AnomalyThresholdsCard";
+import { ClientCertificateCard } from "@/interfaces/react/components/ClientCertificateCard";
+import { ConnectionTestReport } from "@/interfaces/react/components/ConnectionTestReport";
+import { ServerInfoCard } from "@/interfaces/react/components/ServerInfoCard";
+import { useConnectionTest } from "@/interfaces/react/hooks/useConnectionTest";
 
 export default function SettingsPage() {
   const settingsRepo = useMemo(() => new SettingsRepositoryImpl(), []);
-  const { connect } = useContext(GrpcRepoContext);
+  const { grpcRepo, connect } = useContext(GrpcRepoContext);
+  const connectionTest = useConnectionTest(grpcRepo);
   const {
     state: settingsState,
     dispatch,
@@ -35,130 +51,491 @@ export default function SettingsPage() {
     await connect(); // Reconnect to apply new settings
   };
 
+  const testConnection = async () => {
+    // Test what is in the form, not what was last saved.
+    const { error: _error, ...settings } = settingsState;
+    await connectionTest.run(settings);
+  };
+
   useEffect(() => {
     load();
   }, [load]);
 
   return (
-    <Card className="w-full">
-      <form onSubmit={saveSettings}>
-        <CardHeader className="my-4">
-          <CardTitle>Settings</CardTitle>
-        </CardHeader>
-        <CardContent className="my-4">
-          <div className="w-full items-center gap-4">
-            <div className="flex flex-col space-y-1.5">
-              <Label htmlFor="name">URL</Label>
-              <p className="text-sm text-muted-foreground">
-                Enter the URL of the gRPC endpoint which provides the graph
-                data:
-              </p>
-              <Input
-                id="name"
-                placeholder="https://example.com/grpc"
-                value={settingsState.url}
-                onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
-                  dispatch({
-                    type: "SET_FIELD",
-                    payload: { ...settingsState, url: e.currentTarget.value },
-                  });
-                }}
-              />
-              <div className="flex flex-col ml-4 mt-1.5 mb-8 space-y-1.5">
+    <>
+      <Card className="w-full">
+        <form onSubmit={saveSettings}>
+          <CardHeader className="my-4">
+            <CardTitle>Settings</CardTitle>
+          </CardHeader>
+          <CardContent className="my-4">
+            <div className="w-full items-center gap-4">
+              <div className="flex flex-col space-y-1.5">
+                <Label htmlFor="name">URL</Label>
+                <p className="text-sm text-muted-foreground">
+                  Enter the URL of the gRPC endpoint which provides the graph
+                  data:
+                </p>
+                <Input
+                  id="name"
+                  placeholder="https://example.com/grpc"
+                  value={settingsState.url}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: { ...settingsState, url: e.currentTarget.value },
+                    });
+                  }}
+                />
+                <div className="flex flex-col ml-4 mt-1.5 mb-8 space-y-1.5">
+                  <div className="flex flex-row items-center space-x-2 space-y-1.5">
+                    <Checkbox
+                      id="use-proxies"
+                      checked={settingsState.useProxies}
+                      onCheckedChange={(checked: boolean) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: { ...settingsState, useProxies: checked },
+                        });
+                      }}
+                    />
+                    <Label htmlFor="use-proxies">Use a Proxy Server</Label>
+                  </div>
+                  <div
+                    className={`ml-6 space-y-1.5 transition-opacity duration-150 ${
+                      settingsState.useProxies
+                        ? "opacity-100"
+                        : "opacity-50 pointer-events-none"
+                    }`}
+                    aria-disabled={!settingsState.useProxies}
+                  >
+                    <Label
+                      htmlFor="proxy_url"
+                      className={
+                        settingsState.useProxies ? "" : "text-muted-foreground"
+                      }
+                    >
+                      Proxy URL
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      Enter the proxy server URL (http://, https://, socks5://
+                      or socks5h://):
+                    </p>
+                    <Input
+                      id="proxy_url"
+                      placeholder="http://proxy.example.com:8080"
+                      value={settingsState.proxyUrl}
+                      disabled={!settingsState.useProxies}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            proxyUrl: e.currentTarget.value,
+                          },
+                        });
+                      }}
+                    />
+                    <Label
+                      htmlFor="proxy_username"
+                      className={
+                        settingsState.useProxies ? "" : "text-muted-foreground"
+                      }
+                    >
+                      Proxy Credentials
+                    </Label>
+                    <p className="text-sm text-muted-foreground">
+                      Optional username and password for the proxy server:
+                    </p>
+                    <Input
+                      id="proxy_username"
+                      placeholder="Username"
+                      value={settingsState.proxyUsername}
+                      disabled={!settingsState.useProxies}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            proxyUsername: e.currentTarget.value,
+                          },
+                        });
+                      }}
+                    />
+                    <Input
+                      id="proxy_password"
+                      type="password"
+                      placeholder={
+                        settingsState.hasProxyPassword
+                          ? "(unchanged)"
+                          : "Password"
+                      }
+                      value={settingsState.proxyPassword ?? ""}
+                      disabled={!settingsState.useProxies}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            proxyPassword: e.currentTarget.value,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                </div>
+              </div>
+
+              <div className="flex flex-col space-y-1.5">
+                <Label htmlFor="name">Access Token</Label>
+                <p className="text-sm text-muted-foreground">
+                  Enter the access token for the gRPC endpoint:
+                </p>
+                <Input
+                  id="access-token"
+                  placeholder="Your Access Token"
+                  type="password"
+                  value={settingsState.accessToken}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: {
+                        ...settingsState,
+                        accessToken: e.currentTarget.value,
+                      },
+                    });
+                  }}
+                />
+              </div>
+
+              <div className="flex flex-col mt-8 space-y-1.5">
+                <Label htmlFor="auth-mode">Authentication</Label>
+                <p className="text-sm text-muted-foreground">
+                  Choose how the access token is sent to the gRPC endpoint:
+                </p>
+                <Select
+                  value={settingsState.authMode}
+                  onValueChange={(value: string) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: { ...settingsState, authMode: value as AuthMode },
+                    });
+                  }}
+                >
+                  <SelectTrigger id="auth-mode" className="w-[240px]">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value="bearer">Bearer token</SelectItem>
+                    <SelectItem value="custom">Custom header</SelectItem>
+                    <SelectItem value="none">None</SelectItem>
+                  </SelectContent>
+                </Select>
+                {settingsState.authMode === "custom" && (
+                  <div className="ml-6 space-y-1.5">
+                    <Label htmlFor="auth-header-name">Header Name</Label>
+                    <Input
+                      id="auth-header-name"
+                      placeholder="x-api-key"
+                      value={settingsState.authHeaderName}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            authHeaderName: e.currentTarget.value,
+                          },
+                        });
+                      }}
+                    />
+                    <Label htmlFor="auth-header-prefix">Value Prefix</Label>
+                    <Input
+                      id="auth-header-prefix"
+                      placeholder="Token"
+                      value={settingsState.authHeaderPrefix}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            authHeaderPrefix: e.currentTarget.value,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                )}
+              </div>
+
+              <div className="flex flex-col mt-8 mb-8 space-y-1.5">
+                <Label htmlFor="ca-bundle-pem">Trusted Certificates</Label>
+                <p className="text-sm text-muted-foreground">
+                  Paste additional CA certificates (or a self-signed server
+                  certificate) in PEM format, and/or enter the path of a PEM
+                  file:
+                </p>
+                <Textarea
+                  id="ca-bundle-pem"
+                  className="font-mono"
+                  placeholder="-----BEGIN CERTIFICATE-----"
+                  value={settingsState.caBundlePem}
+                  onChange={(e: React.ChangeEvent<HTMLTextAreaElement>) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: {
+                        ...settingsState,
+                        caBundlePem: e.currentTarget.value,
+                      },
+                    });
+                  }}
+                />
+                <Input
+                  id="ca-bundle-path"
+                  placeholder="/path/to/ca-bundle.pem"
+                  value={settingsState.caBundlePath}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: {
+                        ...settingsState,
+                        caBundlePath: e.currentTarget.value,
+                      },
+                    });
+                  }}
+                />
                 <div className="flex flex-row items-center space-x-2 space-y-1.5">
                   <Checkbox
-                    id="use-proxies"
-                    checked={settingsState.useProxies}
+                    id="disable-webpki-roots"
+                    checked={settingsState.disableWebpkiRoots}
                     onCheckedChange={(checked: boolean) => {
                       dispatch({
                         type: "SET_FIELD",
-                        payload: { ...settingsState, useProxies: checked },
+                        payload: { ...settingsState, disableWebpkiRoots: checked },
                       });
                     }}
                   />
-                  <Label htmlFor="use-proxies">Use a Proxy Server</Label>
-                </div>
-                <div
-                  className={`ml-6 space-y-1.5 transition-opacity duration-150 ${
-                    settingsState.useProxies
-                      ? "opacity-100"
-                      : "opacity-50 pointer-events-none"
-                  }`}
-                  aria-disabled={!settingsState.useProxies}
-                >
-                  <Label
-                    htmlFor="proxy_url"
-                    className={
-                      settingsState.useProxies ? "" : "text-muted-foreground"
-                    }
-                  >
-                    Proxy URL
+                  <Label htmlFor="disable-webpki-roots">
+                    Trust only these certificates (disable built-in roots)
                   </Label>
-                  <p className="text-sm text-muted-foreground">
-                    Enter the proxy server URL:
-                  </p>
-                  <Input
-                    id="proxy_url"
-                    placeholder="http://proxy.example.com:8080"
-                    value={settingsState.proxyUrl}
-                    disabled={!settingsState.useProxies}
-                    onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                </div>
+              </div>
+
+              <div className="flex flex-col mb-8 space-y-1.5">
+                <Label>Timeouts &amp; Keepalive</Label>
+                <p className="text-sm text-muted-foreground">
+                  Limits for connecting and for each request, and how often
+                  HTTP/2 pings check that the connection is alive. Use 0 to
+                  disable a limit or keepalive pings:
+                </p>
+                <div className="grid grid-cols-2 gap-4">
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="connect-timeout">Connect timeout (ms)</Label>
+                    <Input
+                      id="connect-timeout"
+                      type="number"
+                      min={0}
+                      value={settingsState.connectTimeoutMs}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            connectTimeoutMs: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="request-timeout">Request timeout (ms)</Label>
+                    <Input
+                      id="request-timeout"
+                      type="number"
+                      min={0}
+                      value={settingsState.requestTimeoutMs}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            requestTimeoutMs: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="keepalive-interval">Keepalive interval (ms)</Label>
+                    <Input
+                      id="keepalive-interval"
+                      type="number"
+                      min={0}
+                      value={settingsState.keepaliveIntervalMs}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            keepaliveIntervalMs: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="keepalive-timeout">Keepalive timeout (ms)</Label>
+                    <Input
+                      id="keepalive-timeout"
+                      type="number"
+                      min={0}
+                      value={settingsState.keepaliveTimeoutMs}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            keepaliveTimeoutMs: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                </div>
+                <div className="flex flex-row items-center space-x-2 space-y-1.5">
+                  <Checkbox
+                    id="keepalive-while-idle"
+                    checked={settingsState.keepaliveWhileIdle}
+                    onCheckedChange={(checked: boolean) => {
                       dispatch({
                         type: "SET_FIELD",
-                        payload: {
-                          ...settingsState,
-                          proxyUrl: e.currentTarget.value,
-                        },
+                        payload: { ...settingsState, keepaliveWhileIdle: checked },
                       });
                     }}
                   />
+                  <Label htmlFor="keepalive-while-idle">
+                    Send keepalive pings while idle
+                  </Label>
                 </div>
               </div>
-            </div>
 
-            <div className="flex flex-col space-y-1.5">
-              <Label htmlFor="name">Access Token</Label>
-              <p className="text-sm text-muted-foreground">
-                Enter the access token for the gRPC endpoint:
-              </p>
-              <Input
-                id="access-token"
-                placeholder="Your Access Token"
-                type="password"
-                value={settingsState.accessToken}
-                onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
-                  dispatch({
-                    type: "SET_FIELD",
-                    payload: {
-                      ...settingsState,
-                      accessToken: e.currentTarget.value,
-                    },
-                  });
-                }}
-              />
+              <div className="flex flex-col mb-8 space-y-1.5">
+                <Label>Graph Samples</Label>
+                <p className="text-sm text-muted-foreground">
+                  The number of samples requested per fetch follows the chart
+                  width, kept within these bounds:
+                </p>
+                <div className="grid grid-cols-2 gap-4">
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="min-samples">Minimum samples</Label>
+                    <Input
+                      id="min-samples"
+                      type="number"
+                      min={1}
+                      value={settingsState.minSamples}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            minSamples: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                  <div className="flex flex-col space-y-1.5">
+                    <Label htmlFor="max-samples">Maximum samples</Label>
+                    <Input
+                      id="max-samples"
+                      type="number"
+                      min={1}
+                      value={settingsState.maxSamples}
+                      onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                        dispatch({
+                          type: "SET_FIELD",
+                          payload: {
+                            ...settingsState,
+                            maxSamples: Number(e.currentTarget.value) || 0,
+                          },
+                        });
+                      }}
+                    />
+                  </div>
+                </div>
+              </div>
+
+              <div className="flex flex-col mb-8 space-y-1.5">
+                <Label htmlFor="poll-interval">Live update interval (ms)</Label>
+                <p className="text-sm text-muted-foreground">
+                  While connected, new readings are fetched this often and
+                  appended to the charts. 0 disables live updates.
+                </p>
+                <Input
+                  id="poll-interval"
+                  type="number"
+                  min={0}
+                  value={settingsState.pollIntervalMs}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) => {
+                    dispatch({
+                      type: "SET_FIELD",
+                      payload: {
+                        ...settingsState,
+                        pollIntervalMs: Number(e.currentTarget.value) || 0,
+                      },
+                    });
+                  }}
+                />
+              </div>
+
+              {settingsState.error && (
+                <Alert
+                  variant="destructive"
+                  className="flex flex-col space-y-1.5"
+                >
+                  <AlertCircle className="h-4 w-4" />
+                  <AlertTitle>Error!</AlertTitle>
+                  <AlertDescription>{settingsState.error}</AlertDescription>
+                </Alert>
+              )}
+
+              {connectionTest.error && (
+                <Alert
+                  variant="destructive"
+                  className="flex flex-col space-y-1.5"
+                >
+                  <AlertCircle className="h-4 w-4" />
+                  <AlertTitle>Connection test failed to run</AlertTitle>
+                  <AlertDescription>{connectionTest.error}</AlertDescription>
+                </Alert>
+              )}
+              {connectionTest.report && (
+                <ConnectionTestReport report={connectionTest.report} />
+              )}
             </div>
+          </CardContent>
 
-            {settingsState.error && (
-              <Alert
-                variant="destructive"
-                className="flex flex-col space-y-1.5"
+          <div className="flex">
+            <CardFooter className="flex justify-between mt-4">
+              <Button type="submit" className="cursor-pointer">
+                Update
+              </Button>
+              <Button
+                type="button"
+                variant="outline"
+                className="cursor-pointer ml-2"
+                disabled={connectionTest.isTesting}
+                onClick={testConnection}
               >
-                <AlertCircle className="h-4 w-4" />
-                <AlertTitle>Error!</AlertTitle>
-                <AlertDescription>{settingsState.error}</AlertDescription>
-              </Alert>
-            )}
+                {connectionTest.isTesting ? "Testing..." : "Test connection"}
+              </Button>
+            </CardFooter>
           </div>
-        </CardContent>
-
-        <div className="flex">
-          <CardFooter className="flex justify-between mt-4">
-            <Button type="submit" className="cursor-pointer">
-              Update
-            </Button>
-          </CardFooter>
-        </div>
-      </form>
-    </Card>
+        </form>
+      </Card>
+      <ClientCertificateCard />
+      <ServerInfoCard />
+      <AnomalyThresholdsCard />
+      <AlertRulesCard />
+    </>
   );
 }
@@ -6,9 +6,290 @@ export type AmbientCondition = {
 
 export type GraphData = Map<string, AmbientCondition>;
 
+// A single reading; `recordedAt` is in milliseconds since the Unix epoch.
+export type AmbientReading = AmbientCondition & {
+  id: string;
+  recordedAt: number;
+};
+
+// Readings newer than the previous live update, oldest first.
+export type AmbientUpdate = {
+  readings: AmbientReading[];
+};
+
+// Time windows of a long range are fetched separately; failed ones are
+// reported here and left out of the data.
+export type FailedWindow = TimeRange & { error: string };
+
+// Series computed in the backend from temperature and humidity: dew point
+// (℃), heat index (℃), humidex, absolute humidity (g/m³) and vapour
+// pressure deficit (kPa).
+export type DerivedMetric =
+  | "dewPoint"
+  | "heatIndex"
+  | "humidex"
+  | "absoluteHumidity"
+  | "vpd";
+
+// Derived values keyed by reading id, for the metrics that were requested.
+export type DerivedSeries = Partial<Record<DerivedMetric, Record<string, number>>>;
+
+// When the server can't be reached, the data comes from the local cache only
+// (`servedFromCache`) and may be out of date. `lastSync` is in milliseconds
+// since the Unix epoch; `coveredRange` spans the cached readings returned.
+export type GraphDataMeta = {
+  partial: boolean;
+  failedWindows: FailedWindow[];
+  servedFromCache: boolean;
+  lastSync: number | null;
+  coveredRange: TimeRange | null;
+  derivedSeries?: DerivedSeries;
+  // Number of readings before the backend downsampled them, if it did.
+  downsampledFrom?: number;
+};
+
+export type GraphDataResult = {
+  data: GraphData;
+  meta: GraphDataMeta;
+};
+
+// Identifies a cancellable request. A newer request for the same `view`
+// cancels older ones.
+export type RequestTag = {
+  id: string;
+  view?: string;
+};
+
+// Minimum or maximum value and when it was recorded (ms since the epoch).
+export type Extreme = {
+  value: number;
+  recordedAt: number;
+};
+
+export type MetricStatistics = {
+  count: number;
+  min: Extreme;
+  max: Extreme;
+  mean: number;
+  median: number;
+  // Population standard deviation.
+  stdDev: number;
+  p5: number;
+  p95: number;
+};
+
+// Null for a metric with no valid readings in the range.
+export type StatisticsReport = {
+  temperature: MetricStatistics | null;
+  humidity: MetricStatistics | null;
+  illumination: MetricStatistics | null;
+  derived?: Partial<Record<DerivedMetric, MetricStatistics | null>>;
+  meta: GraphDataMeta;
+};
+
+export type TimeUnit = "seconds" | "milliseconds" | "microseconds" | "nanoseconds";
+
+// Start and end since the Unix epoch, both in `unit`.
+export type TimeRange = {
+  start: number;
+  end: number;
+  unit: TimeUnit;
+};
+
+// How finely the chart will be drawn; the backend turns it into a sample count
+// clamped to the configured min/max.
+export type AmbientMetric = "temperature" | "humidity" | "illumination";
+
+// Per-metric thresholds for anomaly detection, stored by the backend.
+// `window` is the number of preceding readings used for the z-score and IQR
+// detectors; `rateOfChange` is per minute.
+export type AnomalyThresholds = {
+  metric: AmbientMetric;
+  window: number;
+  zScore: number;
+  iqrMultiplier: number;
+  rateOfChange: number;
+};
+
+export type AnomalyDetector = "zScore" | "iqr" | "rateOfChange";
+
+export type Anomaly = {
+  recordedAt: number;
+  metric: AmbientMetric;
+  value: number;
+  score: number;
+  detector: AnomalyDetector;
+};
+
+export type AnomalyReport = {
+  anomalies: Anomaly[];
+  meta: GraphDataMeta;
+};
+
+export type Comparator = "gt" | "ge" | "lt" | "le";
+
+// Minutes since local midnight; the range is end-exclusive and wraps past
+// midnight when `startMinute > endMinute`.
+export type QuietHours = {
+  startMinute: number;
+  endMinute: number;
+};
+
+// A threshold rule evaluated against live readings. It fires once the
+// comparison has held for `sustainedMs`, and clears when it stops holding.
+// `id` is assigned by the backend on creation.
+export type AlertRule = {
+  id: number;
+  name: string;
+  metric: AmbientMetric;
+  comparator: Comparator;
+  threshold: number;
+  sustainedMs: number;
+  quietHours: QuietHours | null;
+  enabled: boolean;
+};
+
+export type AlertEvent = {
+  id: number;
+  ruleId: number;
+  firedAt: number;
+  clearedAt: number | null;
+  value: number;
+};
+
+// Payload of the `alert://fired` event.
+export type AlertFired = {
+  rule: AlertRule;
+  event: AlertEvent;
+};
+
+export type AggregateInterval = "hourly" | "daily" | "weekly";
+
+export type MetricAggregate = {
+  count: number;
+  min: number;
+  mean: number;
+  max: number;
+};
+
+// One calendar bucket in the requested time zone; `end` is exclusive.
+// Metrics without valid readings in the bucket are omitted.
+export type AggregateBucket = {
+  start: number;
+  end: number;
+  metrics: Partial<Record<AmbientMetric, MetricAggregate>>;
+};
+
+export type AggregateReport = {
+  interval: AggregateInterval;
+  timeZone: string;
+  buckets: AggregateBucket[];
+  // How many buckets came from the persisted rollups.
+  cachedBuckets: number;
+  meta: GraphDataMeta;
+};
+
+// How the backend thins out readings that exceed the target point count
+// (per metric; defaults to the requested sample count).
+export type DownsampleMethod = "lttb" | "minMax";
+
+export type DownsampleOptions = {
+  method?: DownsampleMethod;
+  targetPoints?: number;
+};
+
+export type Resolution =
+  | { kind: "pixels"; width: number }
+  | { kind: "interval"; intervalMs: number };
+
+export type ClientCertificateImport =
+  | { format: "pem"; certPem: string; keyPem: string }
+  | { format: "pkcs12"; path: string; password: string };
+
+export type ClientCertificateInfo = {
+  subject: string;
+  issuer: string;
+  // Unix epoch milliseconds.
+  notBefore: number;
+  notAfter: number;
+};
+
+export type ConnectionState =
+  | { state: "disconnected" }
+  | { state: "connecting" }
+  | { state: "connected" }
+  | { state: "backoff"; attempt: number; retryInMs: number }
+  | { state: "failed"; reason: string };
+
+export type ConnectionStatus = ConnectionState & {
+  endpoint: string | null;
+  // Unix epoch milliseconds; null unless currently connected.
+  connectedAt: number | null;
+  lastError: string | null;
+};
+
+export type DiagnosticStepKind = "url" | "dns" | "tcp" | "proxy" | "tls" | "rpc";
+
+export type DiagnosticStep = {
+  step: DiagnosticStepKind;
+  outcome: "ok" | "failed" | "skipped";
+  durationMs: number;
+  detail: string;
+};
+
+export type ConnectionReport = {
+  ok: boolean;
+  steps: DiagnosticStep[];
+};
+
+export type MethodDescription = {
+  name: string;
+  inputType: string;
+  outputType: string;
+  clientStreaming: boolean;
+  serverStreaming: boolean;
+};
+
+export type ServiceDescription = {
+  name: string;
+  methods: MethodDescription[];
+};
+
+export type ServerDescription = {
+  services: ServiceDescription[];
+  // e.g. "v1"; null when the server exposes no tempgrpcd service.
+  apiVersion: string | null;
+  expectedApiVersion: string;
+};
+
+export type AuthMode = "none" | "bearer" | "custom";
+
 export type Settings = {
   url: string;
   accessToken: string;
   useProxies: boolean;
   proxyUrl: string;
+  proxyUsername: string;
+  // Write-only: the backend never returns the stored password.
+  // Leave undefined to keep the stored password, or "" to clear it.
+  proxyPassword?: string;
+  hasProxyPassword?: boolean;
+  authMode: AuthMode;
+  authHeaderName: string;
+  authHeaderPrefix: string;
+  // Extra PEM trust anchors, pasted in and/or read from a file path.
+  caBundlePem: string;
+  caBundlePath: string;
+  disableWebpkiRoots: boolean;
+  // Milliseconds; 0 disables the limit (or keepalive pings).
+  connectTimeoutMs: number;
+  requestTimeoutMs: number;
+  keepaliveIntervalMs: number;
+  keepaliveTimeoutMs: number;
+  keepaliveWhileIdle: boolean;
+  // Bounds for the number of samples requested per graph fetch.
+  minSamples: number;
+  maxSamples: number;
+  // Milliseconds between live-update polls; 0 disables live updates.
+  pollIntervalMs: number;
 };
@@ -0,0 +1,30 @@
+import { GraphDataMeta } from "@/domain/types";
+
+// get_graph_data replies with
+// [metadata length (u32, little-endian)][metadata JSON][GetAmbientConditionsResponse].
+const LENGTH_BYTES = 4;
+
+export function splitGraphDataFrame(buffer: ArrayBuffer): {
+  meta: GraphDataMeta;
+  body: ArrayBuffer;
+} {
+  const headerLength = new DataView(buffer).getUint32(0, true);
+  const headerEnd = LENGTH_BYTES + headerLength;
+  const meta = JSON.parse(
+    new TextDecoder().decode(new Uint8Array(buffer, LENGTH_BYTES, headerLength))
+  ) as GraphDataMeta;
+  return { meta, body: buffer.slice(headerEnd) };
+}
+
+// Builds a frame the same way the backend does (used by the WebDriver mocks).
+export function frameGraphData(
+  meta: GraphDataMeta,
+  body: ArrayBuffer
+): ArrayBuffer {
+  const header = new TextEncoder().encode(JSON.stringify(meta));
+  const framed = new Uint8Array(LENGTH_BYTES + header.length + body.byteLength);
+  new DataView(framed.buffer).setUint32(0, header.length, true);
+  framed.set(header, LENGTH_BYTES);
+  framed.set(new Uint8Array(body), LENGTH_BYTES + header.length);
+  return framed.buffer;
+}
@@ -0,0 +1,22 @@
+import { ConnectionState } from "@/domain/types";
+
+export function presentConnectionState(state: ConnectionState): {
+  status: string;
+  hasError: boolean;
+} {
+  switch (state.state) {
+    case "disconnected":
+      return { status: "Disconnected", hasError: true };
+    case "connecting":
+      return { status: "Connecting...", hasError: false };
+    case "connected":
+      return { status: "Connected to gRPC server", hasError: false };
+    case "backoff":
+      return {
+        status: `Reconnecting in ${Math.ceil(state.retryInMs / 1000)}s (attempt ${state.attempt})`,
+        hasError: true,
+      };
+    case "failed":
+      return { status: `Connection failed: ${state.reason}`, hasError: true };
+  }
+}
@@ -1,22 +1,49 @@
-import { GraphData } from "@/domain/types";
+import {
+  AmbientReading,
+  DerivedMetric,
+  DerivedSeries,
+  GraphData,
+} from "@/domain/types";
+
+// Optional extra series the dashboard can request, with chart labels.
+export const DERIVED_METRICS: { metric: DerivedMetric; label: string }[] = [
+  { metric: "dewPoint", label: "Dew point (℃)" },
+  { metric: "heatIndex", label: "Heat index (℃)" },
+  { metric: "humidex", label: "Humidex" },
+  { metric: "absoluteHumidity", label: "Absolute humidity (g/m³)" },
+  { metric: "vpd", label: "Vapour pressure deficit (kPa)" },
+];
 
 export type RechartPoint = {
   time: string;
   temperature: number;
   humidity: number;
   illumination: number;
-};
+} & Partial<Record<DerivedMetric, number>>;
 
-export function presentGraphData(graphData: GraphData): RechartPoint[] {
+export function presentGraphData(
+  graphData: GraphData,
+  derivedSeries: DerivedSeries = {}
+): RechartPoint[] {
+  const derived = Object.entries(derivedSeries) as [
+    DerivedMetric,
+    Record<string, number>
+  ][];
   return Array.from(graphData.entries())
     .map(([key, v]) => {
       const ms = Number(key.split('-')[0]);
-      return {
+      const point: Omit<RechartPoint, "time"> & { time: Date } = {
         time: new Date(ms),
         temperature: v.temperature,
         humidity: v.humidity,
         illumination: v.illumination,
       };
+      for (const [metric, values] of derived) {
+        if (key in values) {
+          point[metric] = values[key];
+        }
+      }
+      return point;
     })
     .sort((a, b) => a.time.getTime() - b.time.getTime())
     .map(point => ({
@@ -24,3 +51,14 @@ export function presentGraphData(graphData: GraphData): RechartPoint[] {
       time: point.time.toISOString(), // Convert Date to ISO string for better compatibility
     }));
 }
+
+export function presentAmbientReadings(
+  readings: AmbientReading[]
+): RechartPoint[] {
+  return readings.map((reading) => ({
+    time: new Date(reading.recordedAt).toISOString(),
+    temperature: reading.temperature,
+    humidity: reading.humidity,
+    illumination: reading.illumination,
+  }));
+}
@@ -0,0 +1,75 @@
+import React from "react";
+import {
+  AggregateBucket,
+  AggregateInterval,
+  AggregateReport,
+  AmbientMetric,
+} from "@/domain/types";
+
+const METRICS: { key: AmbientMetric; label: string }[] = [
+  { key: "temperature", label: "Temperature (℃)" },
+  { key: "humidity", label: "Humidity (%)" },
+  { key: "illumination", label: "Illumination (lx)" },
+];
+
+const format = (value: number) => value.toFixed(2);
+
+// Label each bucket by its start in the time zone used for bucketing.
+const bucketLabel = (
+  bucket: AggregateBucket,
+  interval: AggregateInterval,
+  timeZone: string
+) =>
+  new Date(bucket.start).toLocaleString(undefined, {
+    timeZone,
+    year: "numeric",
+    month: "2-digit",
+    day: "2-digit",
+    ...(interval === "hourly" ? { hour: "2-digit", minute: "2-digit" } : {}),
+  });
+
+export const AggregateTable: React.FC<{ report: AggregateReport }> = ({
+  report,
+}) =>
+  report.buckets.length === 0 ? (
+    <p className="text-sm text-muted-foreground my-4">No buckets.</p>
+  ) : (
+    <table className="w-full text-sm my-4">
+      <thead>
+        <tr className="text-left text-muted-foreground">
+          <th className="pr-4">
+            {report.interval === "weekly" ? "Week of" : "Start"} (
+            {report.timeZone})
+          </th>
+          {METRICS.map(({ key, label }) => (
+            <th key={key} className="pr-4">
+              {label} min / mean / max (n)
+            </th>
+          ))}
+        </tr>
+      </thead>
+      <tbody>
+        {report.buckets.map((bucket) => (
+          <tr key={bucket.start} className="border-t">
+            <td className="py-1 pr-4 font-medium">
+              {bucketLabel(bucket, report.interval, report.timeZone)}
+            </td>
+            {METRICS.map(({ key }) => {
+              const aggregate = bucket.metrics[key];
+              return (
+                <td key={key} className="pr-4">
+                  {aggregate ? (
+                    `${format(aggregate.min)} / ${format(
+                      aggregate.mean
+                    )} / ${format(aggregate.max)} (${aggregate.count})`
+                  ) : (
+                    <span className="text-muted-foreground">—</span>
+                  )}
+                </td>
+              );
+            })}
+          </tr>
+        ))}
+      </tbody>
+    </table>
+  );
@@ -0,0 +1,309 @@
+import React, { useEffect, useMemo, useState } from "react";
+import { AlertCircle } from "lucide-react";
+import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { AlertRule, AmbientMetric, Comparator } from "@/domain/types";
+import { AlertRepositoryImpl } from "@/interfaces/repositories/alert";
+import { useAlertRules } from "@/interfaces/react/hooks/useAlertRules";
+
+const COMPARATOR_LABELS: Record<Comparator, string> = {
+  gt: ">",
+  ge: "≥",
+  lt: "<",
+  le: "≤",
+};
+
+const EMPTY_RULE: AlertRule = {
+  id: 0,
+  name: "",
+  metric: "temperature",
+  comparator: "gt",
+  threshold: 0,
+  sustainedMs: 0,
+  quietHours: null,
+  enabled: true,
+};
+
+// "HH:MM" <-> minutes since midnight
+const toMinutes = (time: string) => {
+  const [hours, minutes] = time.split(":").map(Number);
+  return (hours || 0) * 60 + (minutes || 0);
+};
+const toTime = (minutes: number) =>
+  `${String(Math.floor(minutes / 60)).padStart(2, "0")}:${String(
+    minutes % 60
+  ).padStart(2, "0")}`;
+
+export const AlertRulesCard: React.FC = () => {
+  const repo = useMemo(() => new AlertRepositoryImpl(), []);
+  const { rules, events, error, load, create, update, remove } =
+    useAlertRules(repo);
+  const [draft, setDraft] = useState<AlertRule>(EMPTY_RULE);
+
+  useEffect(() => {
+    load();
+  }, [load]);
+
+  const addRule = async () => {
+    await create(draft);
+    setDraft(EMPTY_RULE);
+  };
+
+  const ruleName = (id: number) =>
+    rules.find((rule) => rule.id === id)?.name ?? `#${id}`;
+
+  return (
+    <Card className="w-full mt-8">
+      <CardHeader className="my-4">
+        <CardTitle>Alerts</CardTitle>
+      </CardHeader>
+      <CardContent className="my-4 space-y-4">
+        <p className="text-sm text-muted-foreground">
+          Live readings are checked against these rules. A rule fires a desktop
+          notification once its condition has held for the given duration, and
+          does not fire again until it clears. Rules do not fire during their
+          quiet hours.
+        </p>
+
+        {rules.length > 0 && (
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="text-left text-muted-foreground">
+                <th className="pr-4">Enabled</th>
+                <th className="pr-4">Name</th>
+                <th className="pr-4">Condition</th>
+                <th className="pr-4">For</th>
+                <th className="pr-4">Quiet hours</th>
+                <th />
+              </tr>
+            </thead>
+            <tbody>
+              {rules.map((rule) => (
+                <tr key={rule.id} className="border-t">
+                  <td className="py-1 pr-4">
+                    <Checkbox
+                      checked={rule.enabled}
+                      onCheckedChange={(checked: boolean) =>
+                        update({ ...rule, enabled: checked })
+                      }
+                    />
+                  </td>
+                  <td className="pr-4">{rule.name}</td>
+                  <td className="pr-4">
+                    {rule.metric} {COMPARATOR_LABELS[rule.comparator]}{" "}
+                    {rule.threshold}
+                  </td>
+                  <td className="pr-4">{rule.sustainedMs / 60_000} min</td>
+                  <td className="pr-4">
+                    {rule.quietHours
+                      ? `${toTime(rule.quietHours.startMinute)}–${toTime(
+                          rule.quietHours.endMinute
+                        )}`
+                      : "—"}
+                  </td>
+                  <td>
+                    <Button
+                      type="button"
+                      variant="outline"
+                      className="cursor-pointer"
+                      onClick={() => remove(rule.id)}
+                    >
+                      Delete
+                    </Button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        )}
+
+        <div className="flex flex-col space-y-1.5">
+          <Label htmlFor="alert-name">New rule</Label>
+          <div className="flex flex-row flex-wrap gap-2 items-end">
+            <Input
+              id="alert-name"
+              className="w-[160px]"
+              placeholder="Name"
+              value={draft.name}
+              onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                setDraft({ ...draft, name: e.currentTarget.value })
+              }
+            />
+            <Select
+              value={draft.metric}
+              onValueChange={(value: string) =>
+                setDraft({ ...draft, metric: value as AmbientMetric })
+              }
+            >
+              <SelectTrigger className="w-[140px]">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="temperature">Temperature</SelectItem>
+                <SelectItem value="humidity">Humidity</SelectItem>
+                <SelectItem value="illumination">Illumination</SelectItem>
+              </SelectContent>
+            </Select>
+            <Select
+              value={draft.comparator}
+              onValueChange={(value: string) =>
+                setDraft({ ...draft, comparator: value as Comparator })
+              }
+            >
+              <SelectTrigger className="w-[70px]">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {Object.entries(COMPARATOR_LABELS).map(([value, label]) => (
+                  <SelectItem key={value} value={value}>
+                    {label}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+            <Input
+              className="w-[100px]"
+              type="number"
+              step="any"
+              aria-label="Threshold"
+              value={draft.threshold}
+              onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                setDraft({
+                  ...draft,
+                  threshold: Number(e.currentTarget.value) || 0,
+                })
+              }
+            />
+            <div className="flex flex-col space-y-1">
+              <Label
+                htmlFor="alert-sustained"
+                className="text-xs text-muted-foreground"
+              >
+                For (min)
+              </Label>
+              <Input
+                id="alert-sustained"
+                className="w-[90px]"
+                type="number"
+                min={0}
+                value={draft.sustainedMs / 60_000}
+                onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                  setDraft({
+                    ...draft,
+                    sustainedMs: (Number(e.currentTarget.value) || 0) * 60_000,
+                  })
+                }
+              />
+            </div>
+          </div>
+          <div className="flex flex-row items-center gap-2">
+            <Checkbox
+              id="alert-quiet"
+              checked={draft.quietHours !== null}
+              onCheckedChange={(checked: boolean) =>
+                setDraft({
+                  ...draft,
+                  quietHours: checked
+                    ? { startMinute: 22 * 60, endMinute: 7 * 60 }
+                    : null,
+                })
+              }
+            />
+            <Label htmlFor="alert-quiet">Quiet hours</Label>
+            {draft.quietHours && (
+              <>
+                <Input
+                  className="w-[120px]"
+                  type="time"
+                  aria-label="Quiet hours start"
+                  value={toTime(draft.quietHours.startMinute)}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                    setDraft({
+                      ...draft,
+                      quietHours: {
+                        ...draft.quietHours!,
+                        startMinute: toMinutes(e.currentTarget.value),
+                      },
+                    })
+                  }
+                />
+                <span>–</span>
+                <Input
+                  className="w-[120px]"
+                  type="time"
+                  aria-label="Quiet hours end"
+                  value={toTime(draft.quietHours.endMinute)}
+                  onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                    setDraft({
+                      ...draft,
+                      quietHours: {
+                        ...draft.quietHours!,
+                        endMinute: toMinutes(e.currentTarget.value),
+                      },
+                    })
+                  }
+                />
+              </>
+            )}
+          </div>
+        </div>
+        <Button type="button" className="cursor-pointer" onClick={addRule}>
+          Add rule
+        </Button>
+
+        <div className="flex flex-col space-y-1.5">
+          <Label>Recent alerts</Label>
+          {events.length === 0 ? (
+            <p className="text-sm text-muted-foreground">No alerts yet.</p>
+          ) : (
+            <table className="w-full text-sm">
+              <thead>
+                <tr className="text-left text-muted-foreground">
+                  <th className="pr-4">Rule</th>
+                  <th className="pr-4">Fired</th>
+                  <th className="pr-4">Value</th>
+                  <th>Cleared</th>
+                </tr>
+              </thead>
+              <tbody>
+                {events.map((event) => (
+                  <tr key={event.id} className="border-t">
+                    <td className="py-1 pr-4">{ruleName(event.ruleId)}</td>
+                    <td className="pr-4">
+                      {new Date(event.firedAt).toLocaleString()}
+                    </td>
+                    <td className="pr-4">{event.value.toFixed(2)}</td>
+                    <td>
+                      {event.clearedAt
+                        ? new Date(event.clearedAt).toLocaleString()
+                        : "Active"}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+        </div>
+
+        {error && (
+          <Alert variant="destructive" className="flex flex-col space-y-1.5">
+            <AlertCircle className="h-4 w-4" />
+            <AlertTitle>Error!</AlertTitle>
+            <AlertDescription>{error}</AlertDescription>
+          </Alert>
+        )}
+      </CardContent>
+    </Card>
+  );
+};
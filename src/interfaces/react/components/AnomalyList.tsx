@@ -0,0 +1,43 @@
+import React from "react";
+import { Anomaly, AnomalyDetector } from "@/domain/types";
+
+const DETECTOR_LABELS: Record<AnomalyDetector, string> = {
+  zScore: "Z-score",
+  iqr: "IQR",
+  rateOfChange: "Rate of change",
+};
+
+export const AnomalyList: React.FC<{ anomalies: Anomaly[] }> = ({
+  anomalies,
+}) =>
+  anomalies.length === 0 ? (
+    <p className="text-sm text-muted-foreground my-4">No anomalies found.</p>
+  ) : (
+    <table className="w-full text-sm my-4">
+      <thead>
+        <tr className="text-left text-muted-foreground">
+          <th className="pr-4">Time</th>
+          <th className="pr-4">Metric</th>
+          <th className="pr-4">Value</th>
+          <th className="pr-4">Detector</th>
+          <th>Score</th>
+        </tr>
+      </thead>
+      <tbody>
+        {anomalies.map((anomaly) => (
+          <tr
+            key={`${anomaly.recordedAt}-${anomaly.metric}-${anomaly.detector}`}
+            className="border-t"
+          >
+            <td className="py-1 pr-4">
+              {new Date(anomaly.recordedAt).toLocaleString()}
+            </td>
+            <td className="pr-4">{anomaly.metric}</td>
+            <td className="pr-4">{anomaly.value.toFixed(2)}</td>
+            <td className="pr-4">{DETECTOR_LABELS[anomaly.detector]}</td>
+            <td>{anomaly.score.toFixed(2)}</td>
+          </tr>
+        ))}
+      </tbody>
+    </table>
+  );
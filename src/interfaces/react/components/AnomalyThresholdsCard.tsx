@@ -0,0 +1,98 @@
+import React, { useEffect, useMemo } from "react";
+import { AlertCircle } from "lucide-react";
+import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { AmbientMetric, AnomalyThresholds } from "@/domain/types";
+import { AnomalyRepositoryImpl } from "@/interfaces/repositories/anomaly";
+import { useAnomalyThresholds } from "@/interfaces/react/hooks/useAnomalyThresholds";
+
+const METRIC_LABELS: Record<AmbientMetric, string> = {
+  temperature: "Temperature (℃)",
+  humidity: "Humidity (%)",
+  illumination: "Illumination (lx)",
+};
+
+const FIELDS: {
+  key: Exclude<keyof AnomalyThresholds, "metric">;
+  label: string;
+}[] = [
+  { key: "window", label: "Window (readings)" },
+  { key: "zScore", label: "Z-score" },
+  { key: "iqrMultiplier", label: "IQR multiplier" },
+  { key: "rateOfChange", label: "Rate of change (/min)" },
+];
+
+export const AnomalyThresholdsCard: React.FC = () => {
+  const repo = useMemo(() => new AnomalyRepositoryImpl(), []);
+  const { thresholds, setThresholds, error, load, save } =
+    useAnomalyThresholds(repo);
+
+  useEffect(() => {
+    load();
+  }, [load]);
+
+  const update = (metric: AmbientMetric, key: string, value: number) =>
+    setThresholds(
+      thresholds.map((t) => (t.metric === metric ? { ...t, [key]: value } : t))
+    );
+
+  return (
+    <Card className="w-full mt-8">
+      <CardHeader className="my-4">
+        <CardTitle>Anomaly Detection</CardTitle>
+      </CardHeader>
+      <CardContent className="my-4 space-y-4">
+        <p className="text-sm text-muted-foreground">
+          A reading is flagged when its z-score or its distance outside the
+          interquartile range of the preceding window exceeds the threshold, or
+          when it changes faster than the rate of change per minute.
+        </p>
+        {thresholds.map((t) => (
+          <div key={t.metric} className="flex flex-col space-y-1.5">
+            <Label>{METRIC_LABELS[t.metric]}</Label>
+            <div className="flex flex-row gap-2">
+              {FIELDS.map(({ key, label }) => (
+                <div key={key} className="flex flex-col space-y-1">
+                  <Label
+                    htmlFor={`anomaly-${t.metric}-${key}`}
+                    className="text-xs text-muted-foreground"
+                  >
+                    {label}
+                  </Label>
+                  <Input
+                    id={`anomaly-${t.metric}-${key}`}
+                    type="number"
+                    min={0}
+                    step="any"
+                    value={t[key]}
+                    onChange={(e: React.ChangeEvent<HTMLInputElement>) =>
+                      update(t.metric, key, Number(e.currentTarget.value) || 0)
+                    }
+                  />
+                </div>
+              ))}
+            </div>
+          </div>
+        ))}
+        <Button
+          type="button"
+          className="cursor-pointer"
+          onClick={() => save(thresholds)}
+        >
+          Save thresholds
+        </Button>
+
+        {error && (
+          <Alert variant="destructive" className="flex flex-col space-y-1.5">
+            <AlertCircle className="h-4 w-4" />
+            <AlertTitle>Error!</AlertTitle>
+            <AlertDescription>{error}</AlertDescription>
+          </Alert>
+        )}
+      </CardContent>
+    </Card>
+  );
+};
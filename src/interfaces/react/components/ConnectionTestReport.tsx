@@ -0,0 +1,50 @@
+import React from "react";
+import { CircleCheck, CircleMinus, CircleX } from "lucide-react";
+import { ConnectionReport, DiagnosticStepKind } from "@/domain/types";
+
+const STEP_LABELS: Record<DiagnosticStepKind, string> = {
+  url: "URL",
+  dns: "DNS lookup",
+  tcp: "TCP connect",
+  proxy: "Proxy tunnel",
+  tls: "TLS handshake",
+  rpc: "Authenticated RPC",
+};
+
+export const ConnectionTestReport: React.FC<{ report: ConnectionReport }> = ({
+  report,
+}) => (
+  <ul className="space-y-1.5">
+    {report.steps.map((step) => (
+      <li key={step.step} className="flex flex-row items-start space-x-2">
+        {step.outcome === "ok" && (
+          <CircleCheck className="h-4 w-4 mt-0.5 text-green-600" />
+        )}
+        {step.outcome === "failed" && (
+          <CircleX className="h-4 w-4 mt-0.5 text-red-500" />
+        )}
+        {step.outcome === "skipped" && (
+          <CircleMinus className="h-4 w-4 mt-0.5 text-muted-foreground" />
+        )}
+        <div className="text-sm">
+          <span className="font-medium">{STEP_LABELS[step.step]}</span>
+          {step.outcome !== "skipped" && (
+            <span className="text-muted-foreground">
+              {" "}
+              ({step.durationMs} ms)
+            </span>
+          )}
+          <p
+            className={
+              step.outcome === "failed"
+                ? "text-red-500"
+                : "text-muted-foreground"
+            }
+          >
+            {step.detail}
+          </p>
+        </div>
+      </li>
+    ))}
+  </ul>
+);
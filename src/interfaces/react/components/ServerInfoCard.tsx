@@ -0,0 +1,72 @@
+import React, { useContext } from "react";
+import { AlertCircle } from "lucide-react";
+import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { GrpcRepoContext } from "@/interfaces/react/contexts/grpcRepoContext";
+import { useServerDescription } from "@/interfaces/react/hooks/useServerDescription";
+
+export const ServerInfoCard: React.FC = () => {
+  const { grpcRepo } = useContext(GrpcRepoContext);
+  const { description, error, load } = useServerDescription(grpcRepo);
+
+  const mismatch =
+    description !== null &&
+    description.apiVersion !== description.expectedApiVersion;
+
+  return (
+    <Card className="w-full mt-8">
+      <CardHeader className="my-4">
+        <CardTitle>Server</CardTitle>
+      </CardHeader>
+      <CardContent className="my-4 space-y-1.5">
+        <p className="text-sm text-muted-foreground">
+          Ask the saved endpoint which services it offers (requires gRPC
+          reflection on the server):
+        </p>
+        <Button
+          type="button"
+          variant="outline"
+          className="cursor-pointer"
+          onClick={load}
+        >
+          Describe server
+        </Button>
+        {description && (
+          <div className="space-y-1.5">
+            <p className="text-sm">
+              API version: {description.apiVersion ?? "unknown"}
+            </p>
+            {description.services.map((service) => (
+              <div key={service.name} className="text-sm">
+                <p className="font-medium">{service.name}</p>
+                <ul className="ml-4 text-muted-foreground">
+                  {service.methods.map((method) => (
+                    <li key={method.name}>{method.name}</li>
+                  ))}
+                </ul>
+              </div>
+            ))}
+          </div>
+        )}
+        {mismatch && (
+          <Alert className="flex flex-col space-y-1.5">
+            <AlertCircle className="h-4 w-4" />
+            <AlertTitle>API version mismatch</AlertTitle>
+            <AlertDescription>
+              This app expects tempgrpcd API {description.expectedApiVersion},
+              but the server offers {description.apiVersion ?? "none"}.
+            </AlertDescription>
+          </Alert>
+        )}
+        {error && (
+          <Alert variant="destructive" className="flex flex-col space-y-1.5">
+            <AlertCircle className="h-4 w-4" />
+            <AlertTitle>Error!</AlertTitle>
+            <AlertDescription>{error}</AlertDescription>
+          </Alert>
+        )}
+      </CardContent>
+    </Card>
+  );
+};
@@ -0,0 +1,72 @@
+import React from "react";
+import { MetricStatistics, StatisticsReport } from "@/domain/types";
+import { DERIVED_METRICS } from "@/interfaces/presenters/graphPresenter";
+
+const METRICS: {
+  key: "temperature" | "humidity" | "illumination";
+  label: string;
+}[] = [
+  { key: "temperature", label: "Temperature (℃)" },
+  { key: "humidity", label: "Humidity (%)" },
+  { key: "illumination", label: "Illumination (lx)" },
+];
+
+const format = (value: number) => value.toFixed(2);
+
+const extreme = (value: number, recordedAt: number) =>
+  `${format(value)} (${new Date(recordedAt).toLocaleString()})`;
+
+const Row: React.FC<{ label: string; stats: MetricStatistics | null }> = ({
+  label,
+  stats,
+}) => (
+  <tr className="border-t">
+    <td className="py-1 pr-4 font-medium">{label}</td>
+    {stats ? (
+      <>
+        <td className="pr-4">{extreme(stats.min.value, stats.min.recordedAt)}</td>
+        <td className="pr-4">{extreme(stats.max.value, stats.max.recordedAt)}</td>
+        <td className="pr-4">{format(stats.mean)}</td>
+        <td className="pr-4">{format(stats.median)}</td>
+        <td className="pr-4">{format(stats.stdDev)}</td>
+        <td className="pr-4">{format(stats.p5)}</td>
+        <td className="pr-4">{format(stats.p95)}</td>
+        <td>{stats.count}</td>
+      </>
+    ) : (
+      <td colSpan={8} className="text-muted-foreground">
+        No readings
+      </td>
+    )}
+  </tr>
+);
+
+export const StatisticsTable: React.FC<{ report: StatisticsReport }> = ({
+  report,
+}) => (
+  <table className="w-full text-sm my-4">
+    <thead>
+      <tr className="text-left text-muted-foreground">
+        <th className="pr-4">Metric</th>
+        <th className="pr-4">Min</th>
+        <th className="pr-4">Max</th>
+        <th className="pr-4">Mean</th>
+        <th className="pr-4">Median</th>
+        <th className="pr-4">Std dev</th>
+        <th className="pr-4">P5</th>
+        <th className="pr-4">P95</th>
+        <th>Count</th>
+      </tr>
+    </thead>
+    <tbody>
+      {METRICS.map(({ key, label }) => (
+        <Row key={key} label={label} stats={report[key]} />
+      ))}
+      {DERIVED_METRICS.filter(({ metric }) => report.derived?.[metric] !== undefined).map(
+        ({ metric, label }) => (
+          <Row key={metric} label={label} stats={report.derived?.[metric] ?? null} />
+        )
+      )}
+    </tbody>
+  </table>
+);
@@ -0,0 +1,28 @@
+import { useCallback, useState } from "react";
+import { AggregateRepository } from "@/interfaces/repositories/aggregate";
+import { AggregateInterval, AggregateReport } from "@/domain/types";
+
+export function useAggregates(repo: AggregateRepository) {
+  const [aggregates, setAggregates] = useState<AggregateReport | null>(null);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const load = useCallback(
+    async (startDate: Date, endDate: Date, interval: AggregateInterval) => {
+      try {
+        setAggregates(await repo.getAggregates(startDate, endDate, interval));
+        setError(undefined);
+      } catch (error: any) {
+        setAggregates(null);
+        setError(`${error}`);
+      }
+    },
+    [repo]
+  );
+
+  const clear = useCallback(() => {
+    setAggregates(null);
+    setError(undefined);
+  }, []);
+
+  return { aggregates, error, load, clear };
+}
@@ -0,0 +1,67 @@
+import { useCallback, useEffect, useState } from "react";
+import { AlertRepository } from "@/interfaces/repositories/alert";
+import { AlertEvent, AlertRule } from "@/domain/types";
+
+export function useAlertRules(repo: AlertRepository) {
+  const [rules, setRules] = useState<AlertRule[]>([]);
+  const [events, setEvents] = useState<AlertEvent[]>([]);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const run = useCallback(async (action: () => Promise<void>) => {
+    try {
+      await action();
+      setError(undefined);
+    } catch (error: any) {
+      setError(`${error}`);
+    }
+  }, []);
+
+  const load = useCallback(
+    () =>
+      run(async () => {
+        setRules(await repo.listRules());
+        setEvents(await repo.listEvents());
+      }),
+    [repo, run]
+  );
+
+  // Newly fired alerts show up in the history without reloading
+  useEffect(() => {
+    const unlisten = repo.onAlertFired(({ event }) => {
+      setEvents((events) => [event, ...events]);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [repo]);
+
+  const create = useCallback(
+    (rule: AlertRule) =>
+      run(async () => {
+        const created = await repo.createRule(rule);
+        setRules((rules) => [...rules, created]);
+      }),
+    [repo, run]
+  );
+
+  const update = useCallback(
+    (rule: AlertRule) =>
+      run(async () => {
+        await repo.updateRule(rule);
+        setRules((rules) => rules.map((r) => (r.id === rule.id ? rule : r)));
+      }),
+    [repo, run]
+  );
+
+  const remove = useCallback(
+    (id: number) =>
+      run(async () => {
+        await repo.deleteRule(id);
+        setRules((rules) => rules.filter((r) => r.id !== id));
+        setEvents((events) => events.filter((e) => e.ruleId !== id));
+      }),
+    [repo, run]
+  );
+
+  return { rules, events, error, load, create, update, remove };
+}
@@ -0,0 +1,24 @@
+import { useCallback, useState } from "react";
+import { AnomalyRepository } from "@/interfaces/repositories/anomaly";
+import { Anomaly } from "@/domain/types";
+
+export function useAnomalies(repo: AnomalyRepository) {
+  const [anomalies, setAnomalies] = useState<Anomaly[] | null>(null);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const detect = useCallback(
+    async (startDate: Date, endDate: Date) => {
+      try {
+        const report = await repo.detect(startDate, endDate);
+        setAnomalies(report.anomalies);
+        setError(undefined);
+      } catch (error: any) {
+        setAnomalies(null);
+        setError(`${error}`);
+      }
+    },
+    [repo]
+  );
+
+  return { anomalies, error, detect };
+}
@@ -0,0 +1,32 @@
+import { useCallback, useState } from "react";
+import { AnomalyRepository } from "@/interfaces/repositories/anomaly";
+import { AnomalyThresholds } from "@/domain/types";
+
+export function useAnomalyThresholds(repo: AnomalyRepository) {
+  const [thresholds, setThresholds] = useState<AnomalyThresholds[]>([]);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const load = useCallback(async () => {
+    try {
+      setThresholds(await repo.loadThresholds());
+      setError(undefined);
+    } catch (error: any) {
+      setError(`${error}`);
+    }
+  }, [repo]);
+
+  const save = useCallback(
+    async (updated: AnomalyThresholds[]) => {
+      try {
+        await repo.saveThresholds(updated);
+        setThresholds(updated);
+        setError(undefined);
+      } catch (error: any) {
+        setError(`${error}`);
+      }
+    },
+    [repo]
+  );
+
+  return { thresholds, setThresholds, error, load, save };
+}
@@ -0,0 +1,43 @@
+import { useCallback, useState } from "react";
+import { ClientCertificateRepository } from "@/interfaces/repositories/clientCertificate";
+import { ClientCertificateImport, ClientCertificateInfo } from "@/domain/types";
+
+export function useClientCertificate(repo: ClientCertificateRepository) {
+  const [certificate, setCertificate] = useState<ClientCertificateInfo | null>(
+    null
+  );
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const load = useCallback(async () => {
+    try {
+      setCertificate(await repo.load());
+      setError(undefined);
+    } catch (error: any) {
+      setError(`${error}`);
+    }
+  }, [repo]);
+
+  const importCertificate = useCallback(
+    async (request: ClientCertificateImport) => {
+      try {
+        setCertificate(await repo.import(request));
+        setError(undefined);
+      } catch (error: any) {
+        setError(`${error}`);
+      }
+    },
+    [repo]
+  );
+
+  const remove = useCallback(async () => {
+    try {
+      await repo.remove();
+      setCertificate(null);
+      setError(undefined);
+    } catch (error: any) {
+      setError(`${error}`);
+    }
+  }, [repo]);
+
+  return { certificate, error, load, importCertificate, remove };
+}
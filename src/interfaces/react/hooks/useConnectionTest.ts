@@ -0,0 +1,26 @@
+import { useCallback, useState } from "react";
+import { GrpcRepository } from "@/interfaces/repositories/grpc";
+import { ConnectionReport, Settings } from "@/domain/types";
+
+export function useConnectionTest(repo: GrpcRepository) {
+  const [report, setReport] = useState<ConnectionReport | null>(null);
+  const [isTesting, setIsTesting] = useState(false);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const run = useCallback(
+    async (settings: Settings) => {
+      try {
+        setIsTesting(true);
+        setReport(await repo.testConnection(settings));
+        setError(undefined);
+      } catch (error: any) {
+        setError(`${error}`);
+      } finally {
+        setIsTesting(false);
+      }
+    },
+    [repo]
+  );
+
+  return { report, isTesting, error, run };
+}
@@ -1,34 +1,129 @@
-import { useState } from "react";
+import { useEffect, useRef, useState } from "react";
 
+import { DerivedMetric, Resolution } from "@/domain/types";
 import { FetchGraphData } from "@/usecases/fetchGraphData";
-import { GrpcRepositoryImpl } from "@/interfaces/repositories/grpc";
 import {
+  GrpcRepositoryImpl,
+  isCancelledError,
+} from "@/interfaces/repositories/grpc";
+import {
+  presentAmbientReadings,
   presentGraphData,
   RechartPoint,
 } from "@/interfaces/presenters/graphPresenter";
 
+// Live updates are appended only when the fetched range reaches roughly now.
+const LIVE_EDGE_MS = 60_000;
+// A new fetch from the dashboard cancels the one still running.
+const DASHBOARD_VIEW = "dashboard";
+
+export type StaleData = {
+  lastSync: Date | null;
+  coveredRange: { start: Date; end: Date } | null;
+};
+
 export function useGraphData(grpcRepo: GrpcRepositoryImpl) {
   const [connectivityStatus] = useState<string>("Disconnected");
   const [data, setData] = useState<RechartPoint[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  // Set when some time windows could not be fetched and the data has gaps.
+  const [warning, setWarning] = useState<string | null>(null);
+  // Set when the server was unreachable and the data came from the cache.
+  const [stale, setStale] = useState<StaleData | null>(null);
+  const followLive = useRef(false);
+  const currentRequest = useRef<string | null>(null);
 
   const fetchGraphData = new FetchGraphData(grpcRepo);
 
-  const fetch = async (startDate: Date, endDate: Date) => {
+  const fetch = async (
+    startDate: Date,
+    endDate: Date,
+    resolution?: Resolution,
+    derived?: DerivedMetric[]
+  ) => {
+    const requestId = crypto.randomUUID();
+    currentRequest.current = requestId;
     setLoading(true);
     setError(null);
+    setWarning(null);
+    setStale(null);
+    followLive.current = endDate.getTime() >= Date.now() - LIVE_EDGE_MS;
 
     try {
-      const response = await fetchGraphData.execute(startDate, endDate);
+      const { data: response, meta } = await fetchGraphData.execute(
+        startDate,
+        endDate,
+        resolution,
+        { id: requestId, view: DASHBOARD_VIEW },
+        derived
+      );
       console.log("Fetched graph data:");
-      setData(presentGraphData(response));
+      setData(presentGraphData(response, meta.derivedSeries));
+      if (meta.servedFromCache) {
+        setStale({
+          lastSync: meta.lastSync === null ? null : new Date(meta.lastSync),
+          coveredRange: meta.coveredRange && {
+            start: new Date(meta.coveredRange.start),
+            end: new Date(meta.coveredRange.end),
+          },
+        });
+      } else if (meta.partial) {
+        const reasons = meta.failedWindows
+          .map(
+            (w) =>
+              `${new Date(w.start).toLocaleString()} – ${new Date(
+                w.end
+              ).toLocaleString()}: ${w.error}`
+          )
+          .join("; ");
+        setWarning(`Some of the range could not be fetched (${reasons})`);
+      }
     } catch (err) {
-      setError(`Failed to fetch graph data: ${err}`);
+      if (!isCancelledError(err)) {
+        setError(`Failed to fetch graph data: ${err}`);
+      }
     } finally {
-      setLoading(false);
+      // A superseded request leaves the loading state to the newer one.
+      if (currentRequest.current === requestId) {
+        currentRequest.current = null;
+        setLoading(false);
+      }
+    }
+  };
+
+  const cancel = async () => {
+    const requestId = currentRequest.current;
+    if (requestId) {
+      await grpcRepo.cancelRequest(requestId);
     }
   };
 
-  return { data, connectivityStatus, fetch, loading, error };
+  // The backend only sends readings newer than the previous update.
+  useEffect(() => {
+    const unlisten = grpcRepo.onAmbientUpdate((update) => {
+      if (!followLive.current) {
+        return;
+      }
+      const points = presentAmbientReadings(update.readings);
+      setData((current) => {
+        const last = current[current.length - 1]?.time ?? "";
+        return [...current, ...points.filter((p) => p.time > last)];
+      });
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [grpcRepo]);
+
+  return {
+    data,
+    connectivityStatus,
+    fetch,
+    cancel,
+    loading,
+    error,
+    warning,
+    stale,
+  };
 }
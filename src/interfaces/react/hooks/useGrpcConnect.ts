@@ -1,6 +1,8 @@
-import { useCallback, useReducer } from "react";
+import { useCallback, useEffect, useReducer } from "react";
 
 import { GrpcRepositoryImpl } from "@/interfaces/repositories/grpc";
+import { presentConnectionState } from "@/interfaces/presenters/connectionPresenter";
+import { ConnectionState } from "@/domain/types";
 
 type State = {
   status: string;
@@ -40,5 +42,17 @@ export function useGrpcConnect(grpcRepo: GrpcRepositoryImpl){
     }
   }, [grpcRepo]);
 
+  // The backend reconnects on its own; mirror every state change it reports.
+  useEffect(() => {
+    const apply = (connectionState: ConnectionState) => {
+      const { status, hasError } = presentConnectionState(connectionState);
+      dispatch({ type: hasError ? "ERROR" : "SUCCESS", payload: status });
+    };
+    const unlisten = grpcRepo.onConnectionStateChange(apply);
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [grpcRepo]);
+
   return { state, connect };
 }
@@ -0,0 +1,22 @@
+import { useCallback, useState } from "react";
+import { GrpcRepository } from "@/interfaces/repositories/grpc";
+import { ServerDescription } from "@/domain/types";
+
+export function useServerDescription(repo: GrpcRepository) {
+  const [description, setDescription] = useState<ServerDescription | null>(
+    null
+  );
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const load = useCallback(async () => {
+    try {
+      setDescription(await repo.describeServer());
+      setError(undefined);
+    } catch (error: any) {
+      setDescription(null);
+      setError(`${error}`);
+    }
+  }, [repo]);
+
+  return { description, error, load };
+}
@@ -27,6 +27,21 @@ export function useSettings(repo: SettingsRepository) {
     accessToken: "",
     useProxies: false,
     proxyUrl: "",
+    proxyUsername: "",
+    authMode: "bearer",
+    authHeaderName: "",
+    authHeaderPrefix: "",
+    caBundlePem: "",
+    caBundlePath: "",
+    disableWebpkiRoots: false,
+    connectTimeoutMs: 10_000,
+    requestTimeoutMs: 30_000,
+    keepaliveIntervalMs: 30_000,
+    keepaliveTimeoutMs: 10_000,
+    keepaliveWhileIdle: false,
+    minSamples: 100,
+    maxSamples: 5_000,
+    pollIntervalMs: 10_000,
     error: undefined,
   } as State);
 
@@ -41,17 +56,14 @@ export function useSettings(repo: SettingsRepository) {
 
   const save = useCallback(async () => {
     try {
-      await repo.save({
-        url: state.url,
-        accessToken: state.accessToken,
-        useProxies: state.useProxies,
-        proxyUrl: state.proxyUrl,
-      });
+      // Send every field back so settings without a form control survive a save.
+      const { error: _error, ...settings } = state;
+      await repo.save(settings);
       dispatch({ type: "SET_ERROR", payload: { error: undefined } });
     } catch (error: any) {
       dispatch({ type: "SET_ERROR", payload: { error: error.message } });
     }
-  }, [repo, state.accessToken, state.proxyUrl, state.url, state.useProxies]);
+  }, [repo, state]);
 
   return { state, dispatch, load, save };
 }
@@ -0,0 +1,23 @@
+import { useCallback, useState } from "react";
+import { GrpcRepository } from "@/interfaces/repositories/grpc";
+import { DerivedMetric, StatisticsReport } from "@/domain/types";
+
+export function useStatistics(repo: GrpcRepository) {
+  const [statistics, setStatistics] = useState<StatisticsReport | null>(null);
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  const load = useCallback(
+    async (startDate: Date, endDate: Date, derived?: DerivedMetric[]) => {
+      try {
+        setStatistics(await repo.getStatistics(startDate, endDate, derived));
+        setError(undefined);
+      } catch (error: any) {
+        setStatistics(null);
+        setError(`${error}`);
+      }
+    },
+    [repo]
+  );
+
+  return { statistics, error, load };
+}
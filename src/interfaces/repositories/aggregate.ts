@@ -0,0 +1,54 @@
+import { invoke } from "@tauri-apps/api/core";
+import {
+  AggregateInterval,
+  AggregateReport,
+  TimeRange,
+} from "@/domain/types";
+import { isWebDriverMockEnabled } from "@/mocks/webdriver";
+
+export interface AggregateRepository {
+  getAggregates(
+    _startDate: Date,
+    _endDate: Date,
+    _interval: AggregateInterval,
+    _timeZone?: string
+  ): Promise<AggregateReport>;
+}
+
+export class AggregateRepositoryImpl implements AggregateRepository {
+  // Buckets follow the calendar of `timeZone` (an IANA name), defaulting to
+  // the time zone of this machine.
+  async getAggregates(
+    startDate: Date,
+    endDate: Date,
+    interval: AggregateInterval,
+    timeZone?: string
+  ): Promise<AggregateReport> {
+    const zone = timeZone ?? Intl.DateTimeFormat().resolvedOptions().timeZone;
+    if (isWebDriverMockEnabled()) {
+      return {
+        interval,
+        timeZone: zone,
+        buckets: [],
+        cachedBuckets: 0,
+        meta: {
+          partial: false,
+          failedWindows: [],
+          servedFromCache: false,
+          lastSync: Date.now(),
+          coveredRange: null,
+        },
+      };
+    }
+    const range: TimeRange = {
+      start: startDate.getTime(),
+      end: endDate.getTime(),
+      unit: "milliseconds",
+    };
+    return invoke<AggregateReport>("get_aggregates", {
+      range,
+      interval,
+      timeZone: zone,
+    });
+  }
+}
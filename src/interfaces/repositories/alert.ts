@@ -0,0 +1,62 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { AlertEvent, AlertFired, AlertRule } from "@/domain/types";
+import { isWebDriverMockEnabled } from "@/mocks/webdriver";
+
+export interface AlertRepository {
+  listRules(): Promise<AlertRule[]>;
+  createRule(_rule: AlertRule): Promise<AlertRule>;
+  updateRule(_rule: AlertRule): Promise<void>;
+  deleteRule(_id: number): Promise<boolean>;
+  listEvents(_limit?: number): Promise<AlertEvent[]>;
+  onAlertFired(_handler: (fired: AlertFired) => void): Promise<UnlistenFn>;
+}
+
+export class AlertRepositoryImpl implements AlertRepository {
+  async listRules(): Promise<AlertRule[]> {
+    if (isWebDriverMockEnabled()) {
+      return [];
+    }
+    return invoke<AlertRule[]>("list_alert_rules");
+  }
+
+  async createRule(rule: AlertRule): Promise<AlertRule> {
+    if (isWebDriverMockEnabled()) {
+      return { ...rule, id: Date.now() };
+    }
+    return invoke<AlertRule>("create_alert_rule", { rule });
+  }
+
+  async updateRule(rule: AlertRule): Promise<void> {
+    if (isWebDriverMockEnabled()) {
+      return;
+    }
+    await invoke("update_alert_rule", { rule });
+  }
+
+  async deleteRule(id: number): Promise<boolean> {
+    if (isWebDriverMockEnabled()) {
+      return true;
+    }
+    return invoke<boolean>("delete_alert_rule", { id });
+  }
+
+  async listEvents(limit?: number): Promise<AlertEvent[]> {
+    if (isWebDriverMockEnabled()) {
+      return [];
+    }
+    return invoke<AlertEvent[]>("list_alert_events", { limit: limit ?? null });
+  }
+
+  // Rules that fired on readings polled by the backend.
+  async onAlertFired(
+    handler: (fired: AlertFired) => void
+  ): Promise<UnlistenFn> {
+    if (isWebDriverMockEnabled()) {
+      return () => {};
+    }
+    return listen<AlertFired>("alert://fired", (event) =>
+      handler(event.payload)
+    );
+  }
+}
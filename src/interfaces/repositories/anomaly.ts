@@ -0,0 +1,65 @@
+import { invoke } from "@tauri-apps/api/core";
+import {
+  AmbientMetric,
+  AnomalyReport,
+  AnomalyThresholds,
+  TimeRange,
+} from "@/domain/types";
+import {
+  getMockAnomalyThresholds,
+  isWebDriverMockEnabled,
+} from "@/mocks/webdriver";
+
+export interface AnomalyRepository {
+  detect(
+    _startDate: Date,
+    _endDate: Date,
+    _metrics?: AmbientMetric[]
+  ): Promise<AnomalyReport>;
+  loadThresholds(): Promise<AnomalyThresholds[]>;
+  saveThresholds(_thresholds: AnomalyThresholds[]): Promise<void>;
+}
+
+export class AnomalyRepositoryImpl implements AnomalyRepository {
+  async detect(
+    startDate: Date,
+    endDate: Date,
+    metrics?: AmbientMetric[]
+  ): Promise<AnomalyReport> {
+    if (isWebDriverMockEnabled()) {
+      return {
+        anomalies: [],
+        meta: {
+          partial: false,
+          failedWindows: [],
+          servedFromCache: false,
+          lastSync: Date.now(),
+          coveredRange: null,
+        },
+      };
+    }
+    const range: TimeRange = {
+      start: startDate.getTime(),
+      end: endDate.getTime(),
+      unit: "milliseconds",
+    };
+    return invoke<AnomalyReport>("detect_anomalies", {
+      range,
+      metrics: metrics ?? null,
+    });
+  }
+
+  async loadThresholds(): Promise<AnomalyThresholds[]> {
+    if (isWebDriverMockEnabled()) {
+      return getMockAnomalyThresholds();
+    }
+    return invoke<AnomalyThresholds[]>("get_anomaly_thresholds");
+  }
+
+  async saveThresholds(thresholds: AnomalyThresholds[]): Promise<void> {
+    if (isWebDriverMockEnabled()) {
+      return;
+    }
+    await invoke("set_anomaly_thresholds", { thresholds });
+  }
+}
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/core";
+import { ClientCertificateImport, ClientCertificateInfo } from "@/domain/types";
+import { isWebDriverMockEnabled } from "@/mocks/webdriver";
+
+export interface ClientCertificateRepository {
+  load(): Promise<ClientCertificateInfo | null>;
+  import(_request: ClientCertificateImport): Promise<ClientCertificateInfo>;
+  remove(): Promise<void>;
+}
+
+export class ClientCertificateRepositoryImpl
+  implements ClientCertificateRepository
+{
+  async load(): Promise<ClientCertificateInfo | null> {
+    if (isWebDriverMockEnabled()) {
+      return null;
+    }
+    return invoke<ClientCertificateInfo | null>("get_client_certificate");
+  }
+
+  async import(request: ClientCertificateImport): Promise<ClientCertificateInfo> {
+    if (isWebDriverMockEnabled()) {
+      throw new Error("Client certificates are not available in mock mode");
+    }
+    return invoke<ClientCertificateInfo>("import_client_certificate", {
+      import: request,
+    });
+  }
+
+  async remove(): Promise<void> {
+    if (isWebDriverMockEnabled()) {
+      return;
+    }
+    await invoke("delete_client_certificate");
+  }
+}
@@ -1,12 +1,60 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import {
+  AmbientUpdate,
+  ConnectionReport,
+  ConnectionState,
+  ConnectionStatus,
+  DerivedMetric,
+  DownsampleOptions,
+  RequestTag,
+  Resolution,
+  ServerDescription,
+  Settings,
+  StatisticsReport,
+  TimeRange,
+} from "@/domain/types";
+import {
+  getMockConnectionReport,
   getMockGraphBuffer,
+  getMockServerDescription,
+  getMockStatistics,
   isWebDriverMockEnabled,
 } from "@/mocks/webdriver";
 
+// Error message of a request that was cancelled (by id or by a newer request
+// for the same view); callers should drop the result rather than show it.
+export const CANCELLED_ERROR = "Request was cancelled";
+
+export function isCancelledError(err: unknown): boolean {
+  return err === CANCELLED_ERROR;
+}
+
 export interface GrpcRepository {
   connect(): Promise<string>;
-  fetchGraphData(_startDate: Date, _endDate: Date): Promise<ArrayBuffer>;
+  fetchGraphData(
+    _startDate: Date,
+    _endDate: Date,
+    _resolution?: Resolution,
+    _request?: RequestTag,
+    _derived?: DerivedMetric[],
+    _downsample?: DownsampleOptions
+  ): Promise<ArrayBuffer>;
+  cancelRequest(_requestId: string): Promise<boolean>;
+  getStatistics(
+    _startDate: Date,
+    _endDate: Date,
+    _derived?: DerivedMetric[]
+  ): Promise<StatisticsReport>;
+  getConnectionStatus(): Promise<ConnectionStatus>;
+  testConnection(_settings: Settings): Promise<ConnectionReport>;
+  describeServer(): Promise<ServerDescription>;
+  onConnectionStateChange(
+    _handler: (_state: ConnectionState) => void
+  ): Promise<UnlistenFn>;
+  onAmbientUpdate(
+    _handler: (_update: AmbientUpdate) => void
+  ): Promise<UnlistenFn>;
 }
 
 export class GrpcRepositoryImpl implements GrpcRepository {
@@ -17,13 +65,107 @@ export class GrpcRepositoryImpl implements GrpcRepository {
     return invoke<string>("connect_to_grpc_server");
   }
 
-  async fetchGraphData(startDate: Date, endDate: Date): Promise<ArrayBuffer> {
+  async fetchGraphData(
+    startDate: Date,
+    endDate: Date,
+    resolution?: Resolution,
+    request?: RequestTag,
+    derived?: DerivedMetric[],
+    downsample?: DownsampleOptions
+  ): Promise<ArrayBuffer> {
     if (isWebDriverMockEnabled()) {
       return getMockGraphBuffer();
     }
+    const range: TimeRange = {
+      start: startDate.getTime(),
+      end: endDate.getTime(),
+      unit: "milliseconds",
+    };
     return invoke<ArrayBuffer>("get_graph_data", {
-      startTime: startDate.getTime(),
-      endTime: endDate.getTime(),
+      range,
+      resolution: resolution ?? null,
+      requestId: request?.id ?? null,
+      view: request?.view ?? null,
+      derived: derived ?? null,
+      downsample: downsample ?? null,
+    });
+  }
+
+  // Per-metric summary of the range, computed by the backend.
+  async getStatistics(
+    startDate: Date,
+    endDate: Date,
+    derived?: DerivedMetric[]
+  ): Promise<StatisticsReport> {
+    if (isWebDriverMockEnabled()) {
+      return getMockStatistics();
+    }
+    const range: TimeRange = {
+      start: startDate.getTime(),
+      end: endDate.getTime(),
+      unit: "milliseconds",
+    };
+    return invoke<StatisticsReport>("get_statistics", {
+      range,
+      derived: derived ?? null,
     });
   }
+
+  async cancelRequest(requestId: string): Promise<boolean> {
+    if (isWebDriverMockEnabled()) {
+      return false;
+    }
+    return invoke<boolean>("cancel_request", { requestId });
+  }
+
+  async getConnectionStatus(): Promise<ConnectionStatus> {
+    if (isWebDriverMockEnabled()) {
+      return {
+        state: "connected",
+        endpoint: "https://mock.grpc.example",
+        connectedAt: Date.now(),
+        lastError: null,
+      };
+    }
+    return invoke<ConnectionStatus>("get_connection_status");
+  }
+
+  // Runs each connection step against unsaved settings without reconnecting.
+  async testConnection(settings: Settings): Promise<ConnectionReport> {
+    if (isWebDriverMockEnabled()) {
+      return getMockConnectionReport();
+    }
+    return invoke<ConnectionReport>("test_connection", { settings });
+  }
+
+  // Lists the services the saved endpoint exposes via gRPC reflection.
+  async describeServer(): Promise<ServerDescription> {
+    if (isWebDriverMockEnabled()) {
+      return getMockServerDescription();
+    }
+    return invoke<ServerDescription>("describe_server");
+  }
+
+  async onConnectionStateChange(
+    handler: (state: ConnectionState) => void
+  ): Promise<UnlistenFn> {
+    if (isWebDriverMockEnabled()) {
+      return () => {};
+    }
+    return listen<ConnectionState>("connection://state", (event) =>
+      handler(event.payload)
+    );
+  }
+
+  // New readings polled by the backend while connected.
+  async onAmbientUpdate(
+    handler: (update: AmbientUpdate) => void
+  ): Promise<UnlistenFn> {
+    if (isWebDriverMockEnabled()) {
+      return () => {};
+    }
+    return listen<AmbientUpdate>("ambient://update", (event) =>
+      handler(event.payload)
+    );
+  }
 }
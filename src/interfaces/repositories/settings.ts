@@ -22,6 +22,6 @@ export class SettingsRepositoryImpl implements SettingsRepository {
     if (isWebDriverMockEnabled()) {
       return;
     }
-    await invoke("set_settings", settings);
+    await invoke("set_settings", { settings });
   }
 }
@@ -1,10 +1,35 @@
-import { AmbientCondition, GraphData, Settings } from "@/domain/types";
+import {
+  AmbientCondition,
+  AnomalyThresholds,
+  ConnectionReport,
+  GraphData,
+  MetricStatistics,
+  ServerDescription,
+  Settings,
+  StatisticsReport,
+} from "@/domain/types";
+import { frameGraphData } from "@/frameworks/graphDataFrame";
 
 const MOCK_SETTINGS: Settings = {
   url: "https://mock.grpc.example",
   accessToken: "test-token",
   useProxies: false,
   proxyUrl: "",
+  proxyUsername: "",
+  authMode: "bearer",
+  authHeaderName: "",
+  authHeaderPrefix: "",
+  caBundlePem: "",
+  caBundlePath: "",
+  disableWebpkiRoots: false,
+  connectTimeoutMs: 10_000,
+  requestTimeoutMs: 30_000,
+  keepaliveIntervalMs: 30_000,
+  keepaliveTimeoutMs: 10_000,
+  keepaliveWhileIdle: false,
+  minSamples: 100,
+  maxSamples: 5_000,
+  pollIntervalMs: 10_000,
 };
 
 const WEBDRIVER_MOCK_FLAG =
@@ -32,10 +57,143 @@ export function getMockSettings(): Settings {
   return { ...MOCK_SETTINGS };
 }
 
+export function getMockAnomalyThresholds(): AnomalyThresholds[] {
+  return (["temperature", "humidity", "illumination"] as const).map(
+    (metric) => ({
+      metric,
+      window: 30,
+      zScore: 3,
+      iqrMultiplier: 1.5,
+      rateOfChange:
+        metric === "temperature" ? 2 : metric === "humidity" ? 10 : 1_000,
+    })
+  );
+}
+
+export function getMockStatistics(): StatisticsReport {
+  const values = Array.from(GRAPH_DATA_MAP.entries());
+  const metric = (
+    key: "temperature" | "humidity" | "illumination"
+  ): MetricStatistics | null => {
+    const samples = values
+      .map(([id, condition]) => ({
+        value: condition[key],
+        recordedAt: Number(id.split("-")[0]),
+      }))
+      .sort((a, b) => a.value - b.value);
+    if (samples.length === 0) {
+      return null;
+    }
+    const mean =
+      samples.reduce((sum, sample) => sum + sample.value, 0) / samples.length;
+    const at = (p: number) =>
+      samples[Math.round((p / 100) * (samples.length - 1))].value;
+    return {
+      count: samples.length,
+      min: samples[0],
+      max: samples[samples.length - 1],
+      mean,
+      median: at(50),
+      stdDev: Math.sqrt(
+        samples.reduce((sum, s) => sum + (s.value - mean) ** 2, 0) /
+          samples.length
+      ),
+      p5: at(5),
+      p95: at(95),
+    };
+  };
+  return {
+    temperature: metric("temperature"),
+    humidity: metric("humidity"),
+    illumination: metric("illumination"),
+    meta: {
+      partial: false,
+      failedWindows: [],
+      servedFromCache: false,
+      lastSync: Date.now(),
+      coveredRange: null,
+    },
+  };
+}
+
+export function getMockConnectionReport(): ConnectionReport {
+  return {
+    ok: true,
+    steps: [
+      {
+        step: "url",
+        outcome: "ok",
+        durationMs: 0,
+        detail: "https://mock.grpc.example:443",
+      },
+      {
+        step: "dns",
+        outcome: "ok",
+        durationMs: 2,
+        detail: "mock.grpc.example -> 127.0.0.1",
+      },
+      {
+        step: "tcp",
+        outcome: "ok",
+        durationMs: 1,
+        detail: "connected to 127.0.0.1:443",
+      },
+      {
+        step: "proxy",
+        outcome: "skipped",
+        durationMs: 0,
+        detail: "no proxy configured",
+      },
+      {
+        step: "tls",
+        outcome: "ok",
+        durationMs: 12,
+        detail: "TLSv1_3, ALPN h2",
+      },
+      {
+        step: "rpc",
+        outcome: "ok",
+        durationMs: 8,
+        detail: "GetAmbientConditions succeeded",
+      },
+    ],
+  };
+}
+
+export function getMockServerDescription(): ServerDescription {
+  return {
+    services: [
+      {
+        name: "tempgrpcd.v1.TempgrpcdService",
+        methods: [
+          {
+            name: "GetAmbientConditions",
+            inputType: "tempgrpcd.v1.GetAmbientConditionsRequest",
+            outputType: "tempgrpcd.v1.GetAmbientConditionsResponse",
+            clientStreaming: false,
+            serverStreaming: false,
+          },
+        ],
+      },
+    ],
+    apiVersion: "v1",
+    expectedApiVersion: "v1",
+  };
+}
+
 export function getMockGraphData(): GraphData {
   return new Map(GRAPH_DATA_MAP);
 }
 
 export function getMockGraphBuffer(): ArrayBuffer {
-  return GRAPH_DATA_BUFFER.slice(0);
+  return frameGraphData(
+    {
+      partial: false,
+      failedWindows: [],
+      servedFromCache: false,
+      lastSync: Date.now(),
+      coveredRange: null,
+    },
+    GRAPH_DATA_BUFFER.slice(0)
+  );
 }
@@ -1,4 +1,11 @@
-import { GraphData } from "@/domain/types";
+import {
+  DerivedMetric,
+  DownsampleOptions,
+  GraphDataResult,
+  RequestTag,
+  Resolution,
+} from "@/domain/types";
+import { splitGraphDataFrame } from "@/frameworks/graphDataFrame";
 import { decodeIntoGraphData } from "@/frameworks/protobuf-ts";
 import { GrpcRepository } from "@/interfaces/repositories/grpc";
 
@@ -8,10 +15,25 @@ export class FetchGraphData {
     this.repo = repo;
   }
 
-  async execute(startDate: Date, endDate: Date): Promise<GraphData> {
-    const buffer = await this.repo.fetchGraphData(startDate, endDate);
+  async execute(
+    startDate: Date,
+    endDate: Date,
+    resolution?: Resolution,
+    request?: RequestTag,
+    derived?: DerivedMetric[],
+    downsample?: DownsampleOptions
+  ): Promise<GraphDataResult> {
+    const buffer = await this.repo.fetchGraphData(
+      startDate,
+      endDate,
+      resolution,
+      request,
+      derived,
+      downsample
+    );
 
-    const data = await decodeIntoGraphData(buffer);
-    return data;
+    const { meta, body } = splitGraphDataFrame(buffer);
+    const data = await decodeIntoGraphData(body);
+    return { data, meta };
   }
 }